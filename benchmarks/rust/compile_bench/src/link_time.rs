@@ -0,0 +1,236 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Instant;
+
+/// Generated functions in the test program; enough for compile time to be
+/// measurable next to process start-up.
+const FUNCTIONS: usize = 100;
+/// `rustc --version` runs averaged by `--measure-fork-cost`.
+const FORK_RUNS: u32 = 20;
+
+/// Times `rustc --emit=obj` (frontend + codegen, no linking) and a full
+/// `rustc --emit=link` build of the same generated program. The full build
+/// keeps its objects (`-C save-temps`) and prints its linker command
+/// (`--print link-args`); link time is that command re-run on the kept
+/// objects, i.e. the linker alone. With `lto` both builds are repeated
+/// with `-C lto=thin`; its link time is the relink of the post-LTO
+/// objects, the LTO passes themselves run inside rustc and only show up in
+/// its compile and total. With `fork_cost` the average fork+exec cost of
+/// rustc is measured first and subtracted from the rustc timings, leaving
+/// the time spent compiling.
+pub fn run(lto: bool, fork_cost: bool) {
+    let dir = std::env::temp_dir().join("compile_bench_link");
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        eprintln!("Cannot create {}: {}", dir.display(), e);
+        std::process::exit(1);
+    }
+    let src = dir.join("bench.rs");
+    if let Err(e) = std::fs::write(&src, generate_source()) {
+        eprintln!("Cannot write {}: {}", src.display(), e);
+        std::process::exit(1);
+    }
+
+    let overhead_s = if fork_cost {
+        let s = measure_fork_cost();
+        println!(
+            "Fork+exec: {:.2}ms per rustc start (avg of {} `rustc --version` runs), subtracted below",
+            s * 1e3,
+            FORK_RUNS
+        );
+        println!("RESULT:rust_fork_exec_time:{:.6}", s);
+        s
+    } else {
+        0.0
+    };
+    let net = |s: f64| (s - overhead_s).max(0.0);
+
+    let (compile_s, _) = time_rustc(&src, &["--emit=obj", "-o"], dir.join("bench.o"));
+    let compile_s = net(compile_s);
+    let link_args = ["-C", "save-temps", "--print", "link-args", "--emit=link", "-o"];
+    let (full_s, link_cmd) = time_rustc(&src, &link_args, dir.join("bench"));
+    let link_s = time_link(&link_cmd);
+    print_split("Link", compile_s, link_s, full_s, overhead_s);
+    if lto {
+        // Thin LTO leaves one object per codegen unit, so rustc warns that it
+        // ignores -o; only the time is wanted here.
+        let lto_obj_args = ["-C", "lto=thin", "-A", "warnings", "--emit=obj", "-o"];
+        let (lto_compile_s, _) = time_rustc(&src, &lto_obj_args, dir.join("bench_lto.o"));
+        let lto_compile_s = net(lto_compile_s);
+        let lto_args = ["-C", "lto=thin", "-C", "save-temps", "--print", "link-args", "--emit=link", "-o"];
+        let (lto_s, lto_cmd) = time_rustc(&src, &lto_args, dir.join("bench_lto"));
+        let lto_link_s = time_link(&lto_cmd);
+        print_split("LTO link", lto_compile_s, lto_link_s, lto_s, overhead_s);
+        println!("RESULT:rust_compile_time_obj_lto:{:.4}", lto_compile_s);
+        println!("RESULT:rust_link_time_lto:{:.4}", lto_link_s);
+    }
+    println!("RESULT:rust_compile_time_obj:{:.4}", compile_s);
+    println!("RESULT:rust_link_time:{:.4}", link_s);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// `full_s` is the raw rustc wall time; the total shown has `overhead_s`
+/// taken off, the linking share does not, as the re-run linker also paid
+/// for its own process start-up.
+fn print_split(label: &str, compile_s: f64, link_s: f64, full_s: f64, overhead_s: f64) {
+    println!(
+        "{}: compile {:.3}s, link {:.3}s, total {:.3}s ({:.1}% linking)",
+        label,
+        compile_s,
+        link_s,
+        (full_s - overhead_s).max(0.0),
+        link_s / full_s * 100.0
+    );
+}
+
+fn rustc() -> String {
+    std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string())
+}
+
+/// Average wall time in seconds of `rustc --version`: process start-up
+/// (fork+exec, dynamic loading) with next to no compiler work. Slow on hosts
+/// where fork is expensive, e.g. a large parent or many open descriptors.
+fn measure_fork_cost() -> f64 {
+    let rustc = rustc();
+    let start = Instant::now();
+    for _ in 0..FORK_RUNS {
+        match Command::new(&rustc).arg("--version").output() {
+            Ok(out) if out.status.success() => {}
+            Ok(out) => {
+                eprintln!("{} --version failed: {}", rustc, out.status);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Cannot run {}: {}", rustc, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    start.elapsed().as_secs_f64() / FORK_RUNS as f64
+}
+
+/// Runs `rustc -O <args> <out> <src>` and returns the wall time in seconds
+/// and the last line rustc printed on stdout (the linker command when
+/// `args` include `--print link-args`).
+fn time_rustc(src: &Path, args: &[&str], out: PathBuf) -> (f64, String) {
+    let rustc = rustc();
+    let start = Instant::now();
+    let output = Command::new(&rustc)
+        .arg("-O")
+        .args(args)
+        .arg(&out)
+        .arg(src)
+        .stderr(Stdio::inherit())
+        .output();
+    let elapsed = start.elapsed().as_secs_f64();
+    match output {
+        Ok(o) if o.status.success() => {
+            let stdout = String::from_utf8_lossy(&o.stdout);
+            (elapsed, stdout.lines().last().unwrap_or("").to_string())
+        }
+        Ok(o) => {
+            eprintln!("{} {} failed: {}", rustc, args.join(" "), o.status);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Cannot run {}: {}", rustc, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Re-runs a linker command printed by `rustc --print link-args` and returns
+/// its wall time in seconds.
+fn time_link(line: &str) -> f64 {
+    let LinkCommand { env, argv } = match parse_link_args(line) {
+        Some(cmd) if !cmd.argv.is_empty() => cmd,
+        _ => {
+            eprintln!("Cannot parse the linker command printed by rustc: {}", line);
+            std::process::exit(1);
+        }
+    };
+    let start = Instant::now();
+    let status = Command::new(&argv[0]).args(&argv[1..]).envs(env).status();
+    let elapsed = start.elapsed().as_secs_f64();
+    match status {
+        Ok(s) if s.success() => elapsed,
+        Ok(s) => {
+            eprintln!("Linker {} failed: {}", argv[0], s);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Cannot run linker {}: {}", argv[0], e);
+            std::process::exit(1);
+        }
+    }
+}
+
+struct LinkCommand {
+    env: Vec<(String, String)>,
+    argv: Vec<String>,
+}
+
+/// Splits `KEY="v" ... "prog" "arg" ...`, the form `--print link-args` uses
+/// on Unix (each word quoted and escaped as a Rust string literal), into
+/// environment assignments and argv.
+fn parse_link_args(line: &str) -> Option<LinkCommand> {
+    let mut env = Vec::new();
+    let mut argv = Vec::new();
+    let mut chars = line.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == ' ' {
+            chars.next();
+            continue;
+        }
+        let mut key = String::new();
+        while let Some(&c) = chars.peek() {
+            if c == '"' {
+                break;
+            }
+            key.push(c);
+            chars.next();
+        }
+        chars.next()?;
+        let mut word = String::new();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => match chars.next()? {
+                    'n' => word.push('\n'),
+                    't' => word.push('\t'),
+                    c => word.push(c),
+                },
+                c => word.push(c),
+            }
+        }
+        match key.strip_suffix('=') {
+            Some(k) if argv.is_empty() => env.push((k.to_string(), word)),
+            None if key.is_empty() => argv.push(word),
+            _ => return None,
+        }
+    }
+    Some(LinkCommand { env, argv })
+}
+
+fn generate_source() -> String {
+    let mut src = String::from("use std::collections::HashMap;\n\n");
+    for i in 0..FUNCTIONS {
+        src.push_str(&format!(
+            "fn f{i}(n: u64) -> u64 {{\n\
+             \x20   let mut m: HashMap<u64, u64> = HashMap::new();\n\
+             \x20   for k in 0..n {{\n\
+             \x20       *m.entry(k % {m}).or_insert(0) += k.wrapping_mul({i} + 1);\n\
+             \x20   }}\n\
+             \x20   m.values().fold(0u64, |a, v| a.rotate_left(5) ^ v)\n\
+             }}\n\n",
+            i = i,
+            m = i % 17 + 3
+        ));
+    }
+    src.push_str("fn main() {\n    let mut acc = 0u64;\n");
+    for i in 0..FUNCTIONS {
+        src.push_str(&format!("    acc ^= f{}(16);\n", i));
+    }
+    src.push_str("    println!(\"{}\", acc);\n}\n");
+    src
+}
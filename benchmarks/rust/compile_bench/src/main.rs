@@ -1,5 +1,140 @@
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::HashMap;
+use std::env;
+use std::time::Instant;
+
+mod link_time;
+
+/// Serialize + deserialize passes over the batch per measurement.
+const ROUND_TRIPS: u32 = 200;
+
+#[derive(Clone, Copy)]
+enum Format {
+    Json,
+    Bincode,
+    MessagePack,
+    Cbor,
+}
+
+impl Format {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "json" => Some(Format::Json),
+            "bincode" => Some(Format::Bincode),
+            "messagepack" | "msgpack" => Some(Format::MessagePack),
+            "cbor" => Some(Format::Cbor),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Bincode => "bincode",
+            Format::MessagePack => "messagepack",
+            Format::Cbor => "cbor",
+        }
+    }
+
+    fn encode<T: Serialize>(self, value: &T) -> Vec<u8> {
+        match self {
+            Format::Json => serde_json::to_vec(value).unwrap(),
+            Format::Bincode => bincode::serialize(value).unwrap(),
+            Format::MessagePack => rmp_serde::to_vec_named(value).unwrap(),
+            Format::Cbor => {
+                let mut out = Vec::new();
+                ciborium::ser::into_writer(value, &mut out).unwrap();
+                out
+            }
+        }
+    }
+
+    fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> T {
+        match self {
+            Format::Json => serde_json::from_slice(bytes).unwrap(),
+            Format::Bincode => bincode::deserialize(bytes).unwrap(),
+            Format::MessagePack => rmp_serde::from_slice(bytes).unwrap(),
+            Format::Cbor => ciborium::de::from_reader(bytes).unwrap(),
+        }
+    }
+}
+
+struct Args {
+    format: Format,
+    link_time: bool,
+    lto: bool,
+    measure_fork_cost: bool,
+}
+
+fn print_usage() {
+    eprintln!(
+        "Rust Compile Bench\n\
+Usage: compile_bench [options]\n\n\
+Options:\n\
+  --format <json|bincode|messagepack|cbor>\n\
+                        Format for the record batch round trip (default: json).\n\
+  --link-time           Time rustc --emit=obj and --emit=link on a generated\n\
+                        program, and re-run rustc's linker command on the kept\n\
+                        objects to time the link alone.\n\
+  --lto                 With --link-time, also time a -C lto=thin build and\n\
+                        its link (LTO passes count as compile, not link).\n\
+  --measure-fork-cost   Average the fork+exec cost of `rustc --version` and\n\
+                        subtract it from the --link-time timings (implies\n\
+                        --link-time).\n\
+  -h, --help            Show this help.\n"
+    );
+}
+
+fn parse_args() -> Args {
+    let mut format = Format::Json;
+    let mut link_time = false;
+    let mut lto = false;
+    let mut measure_fork_cost = false;
+
+    let mut it = env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--format" => {
+                if let Some(v) = it.next() {
+                    format = Format::parse(&v).unwrap_or_else(|| {
+                        eprintln!("Invalid format: {}", v);
+                        std::process::exit(2);
+                    });
+                } else {
+                    eprintln!("--format requires a value");
+                    std::process::exit(2);
+                }
+            }
+            "--link-time" => link_time = true,
+            "--lto" => lto = true,
+            "--measure-fork-cost" => {
+                measure_fork_cost = true;
+                link_time = true;
+            }
+            "-h" | "--help" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            _ => {
+                eprintln!("Unknown option: {}", arg);
+                print_usage();
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if lto && !link_time {
+        eprintln!("--lto requires --link-time");
+        std::process::exit(2);
+    }
+
+    Args {
+        format,
+        link_time,
+        lto,
+        measure_fork_cost,
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Record<T: Clone + std::fmt::Debug> {
@@ -29,6 +164,41 @@ impl<T: Clone + std::fmt::Debug + Serialize> Record<T> {
     }
 }
 
+/// A batch of records timed through a serialize/deserialize round trip.
+struct SerializedBatch<T: Clone + std::fmt::Debug> {
+    records: Vec<Record<T>>,
+}
+
+impl<T> SerializedBatch<T>
+where
+    T: Clone + std::fmt::Debug + Serialize + DeserializeOwned,
+{
+    fn new(records: Vec<Record<T>>) -> Self {
+        Self { records }
+    }
+
+    /// Returns (encoded bytes, serialize MB/s, deserialize MB/s).
+    fn round_trip(&self, format: Format) -> (usize, f64, f64) {
+        let mut encoded = Vec::new();
+        let start = Instant::now();
+        for _ in 0..ROUND_TRIPS {
+            encoded = format.encode(&self.records);
+        }
+        let ser_s = start.elapsed().as_secs_f64();
+
+        let start = Instant::now();
+        let mut decoded: Vec<Record<T>> = Vec::new();
+        for _ in 0..ROUND_TRIPS {
+            decoded = format.decode(&encoded);
+        }
+        let de_s = start.elapsed().as_secs_f64();
+        assert_eq!(decoded.len(), self.records.len());
+
+        let mb = encoded.len() as f64 * ROUND_TRIPS as f64 / (1024.0 * 1024.0);
+        (encoded.len(), mb / ser_s, mb / de_s)
+    }
+}
+
 trait Processor {
     type Output;
     fn process(&self, input: &str) -> Self::Output;
@@ -58,7 +228,23 @@ async fn async_work(n: u64) -> Vec<Record<String>> {
 
 #[tokio::main]
 async fn main() {
+    let args = parse_args();
+    if args.link_time {
+        link_time::run(args.lto, args.measure_fork_cost);
+        return;
+    }
     let records = async_work(1000).await;
+
+    let batch = SerializedBatch::new(records.clone());
+    let (bytes, ser_mb_s, de_mb_s) = batch.round_trip(args.format);
+    println!(
+        "Format {}: {} records, {} bytes, serialize {:.1} MB/s, deserialize {:.1} MB/s",
+        args.format.name(),
+        batch.records.len(),
+        bytes,
+        ser_mb_s,
+        de_mb_s
+    );
     let processor = JsonProcessor;
 
     let mut processed = Vec::new();
@@ -1,29 +1,225 @@
+use std::env;
+use std::ops::{AddAssign, Mul};
 use std::time::Instant;
 
-const N: usize = 1024;
+const DEFAULT_N: usize = 1024;
+const MIN_N: usize = 32;
+const MAX_N: usize = 8192;
+/// Rows and columns of C checked by `--matrix-verify`.
+const VERIFY_N: usize = 64;
+/// Mismatching elements printed before the rest are only counted.
+const VERIFY_MAX_REPORTS: usize = 10;
 
-fn main() {
-    println!("Matrix Multiply: {}x{} f64 matrices", N, N);
+trait Element: Copy + AddAssign + Mul<Output = Self> {
+    const NAME: &'static str;
+    /// Largest |reference - result| accepted by `--matrix-verify`.
+    const TOLERANCE: f64;
+    fn zero() -> Self;
+    fn from_f64(v: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Element for f32 {
+    const NAME: &'static str = "f32";
+    const TOLERANCE: f64 = 1e-4;
+    fn zero() -> Self {
+        0.0
+    }
+    fn from_f64(v: f64) -> Self {
+        v as f32
+    }
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+}
+
+impl Element for f64 {
+    const NAME: &'static str = "f64";
+    const TOLERANCE: f64 = 1e-9;
+    fn zero() -> Self {
+        0.0
+    }
+    fn from_f64(v: f64) -> Self {
+        v
+    }
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Dtype {
+    F32,
+    F64,
+    Both,
+}
+
+struct Args {
+    n: usize,
+    dtype: Dtype,
+    repeat: u32,
+    verify: bool,
+}
+
+fn print_usage() {
+    eprintln!(
+        "Rust Matrix Multiply\n\
+Usage: matrix_multiply [options]\n\n\
+Options:\n\
+  --matrix-size <N>, --size <N>\n\
+                        Matrix dimension, 32-8192 (default: 1024).\n\
+  --matrix-dtype <f32|f64|both>, --dtype <f32|f64|both>\n\
+                        Element type (default: f64). both runs f32 and f64\n\
+                        and prints the f32/f64 throughput ratio.\n\
+  --repeat <K>          Run the multiply K times and report mean/stddev\n\
+                        (default: 1).\n\
+  --matrix-verify       After each multiply, recompute the top-left 64x64\n\
+                        block of the result with a scalar triple loop and\n\
+                        compare element-wise (tolerance 1e-9 for f64, 1e-4\n\
+                        for f32). Exits 1 on a mismatch.\n\
+  -h, --help            Show this help.\n"
+    );
+}
+
+fn parse_u64(s: &str, name: &str) -> u64 {
+    s.parse::<u64>().unwrap_or_else(|_| {
+        eprintln!("Invalid {}: {}", name, s);
+        std::process::exit(2);
+    })
+}
+
+fn parse_args() -> Args {
+    let mut n = DEFAULT_N;
+    let mut dtype = Dtype::F64;
+    let mut repeat = 1u32;
+    let mut verify = false;
+
+    let mut it = env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--matrix-size" | "--size" => {
+                if let Some(v) = it.next() {
+                    n = parse_u64(&v, "matrix-size") as usize;
+                } else {
+                    eprintln!("{} requires a value", arg);
+                    std::process::exit(2);
+                }
+            }
+            "--matrix-dtype" | "--dtype" => {
+                if let Some(v) = it.next() {
+                    dtype = match v.as_str() {
+                        "f32" => Dtype::F32,
+                        "f64" => Dtype::F64,
+                        "both" => Dtype::Both,
+                        _ => {
+                            eprintln!("Invalid dtype: {}", v);
+                            std::process::exit(2);
+                        }
+                    };
+                } else {
+                    eprintln!("{} requires a value", arg);
+                    std::process::exit(2);
+                }
+            }
+            "--repeat" => {
+                if let Some(v) = it.next() {
+                    repeat = parse_u64(&v, "repeat") as u32;
+                } else {
+                    eprintln!("--repeat requires a value");
+                    std::process::exit(2);
+                }
+            }
+            "--matrix-verify" => verify = true,
+            "-h" | "--help" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            _ => {
+                eprintln!("Unknown option: {}", arg);
+                print_usage();
+                std::process::exit(2);
+            }
+        }
+    }
+
+    if !(MIN_N..=MAX_N).contains(&n) {
+        eprintln!("Invalid matrix-size (want {}-{}): {}", MIN_N, MAX_N, n);
+        std::process::exit(2);
+    }
+    if repeat == 0 {
+        eprintln!("Invalid repeat: 0");
+        std::process::exit(2);
+    }
+
+    Args {
+        n,
+        dtype,
+        repeat,
+        verify,
+    }
+}
 
-    let mut a = vec![0.0f64; N * N];
-    let mut b = vec![0.0f64; N * N];
-    let mut c = vec![0.0f64; N * N];
+/// Recomputes the top-left `VERIFY_N` x `VERIFY_N` block of `c = a * b`
+/// with a plain i-j-k dot product accumulated in f64, so it shares neither
+/// the kernel's loop order nor its f32 rounding, and compares it with `c`.
+/// Prints `VERIFIED` or one `MISMATCH` line per bad element; returns
+/// whether all matched.
+fn verify<T: Element>(n: usize, a: &[T], b: &[T], c: &[T]) -> bool {
+    let m = VERIFY_N.min(n);
+    let mut mismatches = 0usize;
+    for i in 0..m {
+        for j in 0..m {
+            let mut expected = 0.0f64;
+            for k in 0..n {
+                expected += a[i * n + k].to_f64() * b[k * n + j].to_f64();
+            }
+            let got = c[i * n + j].to_f64();
+            if (expected - got).abs() >= T::TOLERANCE {
+                if mismatches < VERIFY_MAX_REPORTS {
+                    println!("MISMATCH at [{}][{}]: expected {} got {}", i, j, expected, got);
+                }
+                mismatches += 1;
+            }
+        }
+    }
+    if mismatches == 0 {
+        println!("VERIFIED: {}x{} block of {} elements within {:e}", m, m, T::NAME, T::TOLERANCE);
+    } else {
+        println!("MISMATCH: {} of {} elements outside {:e}", mismatches, m * m, T::TOLERANCE);
+    }
+    mismatches == 0
+}
+
+/// Returns (time, checksum, verified); `verified` is true unless `check`
+/// found a mismatch.
+fn multiply<T: Element>(n: usize, check: bool) -> (f64, f64, bool) {
+    println!(
+        "Matrix Multiply: {}x{} {} matrices ({:.1} MB)",
+        n,
+        n,
+        T::NAME,
+        (3 * n * n * std::mem::size_of::<T>()) as f64 / (1024.0 * 1024.0)
+    );
+
+    let mut a = vec![T::zero(); n * n];
+    let mut b = vec![T::zero(); n * n];
+    let mut c = vec![T::zero(); n * n];
 
     // Initialize with pseudo-random values
-    for i in 0..N * N {
-        a[i] = (i as f64 * 0.001).sin();
-        b[i] = (i as f64 * 0.002).cos();
+    for i in 0..n * n {
+        a[i] = T::from_f64((i as f64 * 0.001).sin());
+        b[i] = T::from_f64((i as f64 * 0.002).cos());
     }
 
     println!("Matrices initialized, starting multiply...");
     let start = Instant::now();
 
     // Naive matrix multiply — tests raw CPU + cache behavior
-    for i in 0..N {
-        for k in 0..N {
-            let a_ik = a[i * N + k];
-            for j in 0..N {
-                c[i * N + j] += a_ik * b[k * N + j];
+    for i in 0..n {
+        for k in 0..n {
+            let a_ik = a[i * n + k];
+            for j in 0..n {
+                c[i * n + j] += a_ik * b[k * n + j];
             }
         }
     }
@@ -31,8 +227,71 @@ fn main() {
     let elapsed = start.elapsed().as_secs_f64();
 
     // Prevent optimizer from removing the computation
-    let checksum: f64 = c.iter().sum();
+    let checksum: f64 = c.iter().map(|v| v.to_f64()).sum();
     println!("Checksum: {:.6}", checksum);
     println!("Time: {:.3}s", elapsed);
-    println!("RESULT:rust_matrix_multiply:{:.4}", elapsed);
+    let verified = !check || verify(n, &a, &b, &c);
+    (elapsed, checksum, verified)
+}
+
+/// Runs `multiply` `repeat` times and returns the mean time and whether
+/// every run passed verification.
+fn run<T: Element>(n: usize, repeat: u32, check: bool) -> (f64, bool) {
+    let mut verified = true;
+    let times: Vec<f64> = (0..repeat)
+        .map(|_| {
+            let (time, _, ok) = multiply::<T>(n, check);
+            verified &= ok;
+            time
+        })
+        .collect();
+    let mean = times.iter().sum::<f64>() / times.len() as f64;
+    if times.len() > 1 {
+        let var = times.iter().map(|t| (t - mean).powi(2)).sum::<f64>()
+            / (times.len() - 1) as f64;
+        println!(
+            "{} runs: {}  mean {:.3}s  stddev {:.3}s",
+            T::NAME,
+            times.len(),
+            mean,
+            var.sqrt()
+        );
+    }
+    (mean, verified)
+}
+
+fn main() {
+    let args = parse_args();
+
+    let verified = match args.dtype {
+        Dtype::F64 => {
+            let (elapsed, verified) = run::<f64>(args.n, args.repeat, args.verify);
+            println!("RESULT:rust_matrix_multiply:{:.4}", elapsed);
+            verified
+        }
+        Dtype::F32 => {
+            let (elapsed, verified) = run::<f32>(args.n, args.repeat, args.verify);
+            println!("RESULT:rust_matrix_multiply_f32:{:.4}", elapsed);
+            verified
+        }
+        Dtype::Both => {
+            let (t32, ok32) = run::<f32>(args.n, args.repeat, args.verify);
+            let (t64, ok64) = run::<f64>(args.n, args.repeat, args.verify);
+            let flops = 2.0 * (args.n as f64).powi(3);
+            println!(
+                "f32: {:.3}s ({:.2} GFLOP/s)  f64: {:.3}s ({:.2} GFLOP/s)  f32/f64 throughput: {:.2}x",
+                t32,
+                flops / t32 / 1e9,
+                t64,
+                flops / t64 / 1e9,
+                t64 / t32
+            );
+            println!("RESULT:rust_matrix_multiply_f32:{:.4}", t32);
+            println!("RESULT:rust_matrix_multiply:{:.4}", t64);
+            ok32 && ok64
+        }
+    };
+    if !verified {
+        std::process::exit(1);
+    }
 }
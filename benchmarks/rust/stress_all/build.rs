@@ -0,0 +1,11 @@
+fn main() {
+    #[cfg(feature = "proto")]
+    {
+        println!("cargo:rerun-if-changed=proto/stress_sample.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+        prost_build::Config::new()
+            .protoc_executable(protoc)
+            .compile_protos(&["proto/stress_sample.proto"], &["proto"])
+            .expect("compile stress_sample.proto");
+    }
+}
@@ -0,0 +1,60 @@
+/// Metrics where a drop is a regression. The rest of the summary (duration,
+/// utilization, per-worker op totals) depends on the run's configuration and
+/// is only reported.
+const HIGHER_IS_BETTER: [&str; 6] = [
+    "cpu_ops_s_avg",
+    "mem_ops_s_avg",
+    "disk_mb_s_avg",
+    "disk_iops_avg",
+    "cpu_freq_avg_khz",
+    "turbo_utilization_pct",
+];
+
+/// Top-level numeric fields of a `--summary-json` file, in file order.
+/// Arrays and other non-numeric values are skipped.
+pub fn parse_metrics(text: &str) -> Vec<(String, f64)> {
+    text.lines()
+        .filter_map(|line| {
+            let (key, value) = line.trim().trim_end_matches(',').split_once(':')?;
+            let key = key.trim().strip_prefix('"')?.strip_suffix('"')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Prints the change of every metric present in both runs and returns the
+/// names of those that dropped by more than `threshold_pct`. Metrics the
+/// summary reports as -1 (unavailable) in either run are skipped.
+pub fn compare(baseline: &str, current: &str, threshold_pct: f64) -> Vec<String> {
+    let baseline = parse_metrics(baseline);
+    let mut regressions = Vec::new();
+    eprintln!("BASELINE comparison (regression threshold {:.1}%):", threshold_pct);
+    for (key, cur) in parse_metrics(current) {
+        let Some(&(_, base)) = baseline.iter().find(|(k, _)| *k == key) else {
+            continue;
+        };
+        if base < 0.0 || cur < 0.0 {
+            continue;
+        }
+        let change = if base > 0.0 {
+            format!("{:+.1}%", 100.0 * (cur - base) / base)
+        } else {
+            "n/a".to_string()
+        };
+        let regressed = HIGHER_IS_BETTER.contains(&key.as_str())
+            && base > 0.0
+            && 100.0 * (base - cur) / base > threshold_pct;
+        eprintln!(
+            "  {:<24} {:>14.2} -> {:>14.2}  {:>8}{}",
+            key,
+            base,
+            cur,
+            change,
+            if regressed { "  REGRESSION" } else { "" }
+        );
+        if regressed {
+            regressions.push(key);
+        }
+    }
+    regressions
+}
@@ -0,0 +1,784 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use clap_complete::Shell;
+use stress_lib::{
+    CpuWorkload, CpuYieldStrategy, DiskAccessPattern, DiskCompress, DiskIoType, DiskWritePattern,
+    IoPattern, IoPriority, LatencyModel, MemAllocStrategy,
+};
+
+use super::{ChartMode, CpuPhase, GpuBackend, GRAPH_REFRESH_MS};
+use crate::tui_layout::{self, TuiSection};
+
+/// Command line as given. `parse_args` validates it and stores it in
+/// `Args`, next to the values it converts or resolves.
+#[derive(Parser, Debug)]
+#[command(name = "stress_all", about = "Rust Stress All (CPU/RAM/Disk)")]
+pub struct Cli {
+    /// Run for N seconds or minutes (0 = until Ctrl+C).
+    #[arg(long, value_name = "sec|Xm", default_value = "0", value_parser = parse_duration)]
+    pub duration: u64,
+    /// CPU worker threads (default: all cores).
+    #[arg(long, value_name = "N", default_value_t = 0, hide_default_value = true)]
+    pub cpu_workers: usize,
+    /// Run busy-waiting CPU workers for --duration (default: 10s) without the
+    /// TUI or other workers, then print `CPU Score: N` (median ops/s relative
+    /// to one Cortex-A53 core at 1 GHz) with a 95% confidence interval.
+    #[arg(long)]
+    pub cpu_benchmark_only: bool,
+    /// Run a fixed LCG workload on the CPU worker count twice, as independent
+    /// per-thread shares and as small tasks on a rayon work-stealing pool,
+    /// print a comparison table and exit. Needs the `work_stealing` feature.
+    #[arg(long)]
+    pub cpu_work_stealing: bool,
+    /// Run the LCG worker pinned to each logical CPU in turn for about a
+    /// second, print ops/s per CPU, group CPUs into performance tiers (e.g.
+    /// P- and E-cores on hybrid parts) and exit.
+    #[arg(long)]
+    pub stress_profile_cpu_topology: bool,
+    /// Split intensity by weight: CPU workers = cores x cpu / (cpu+mem),
+    /// memory = RAM x mem / (cpu+mem). E.g. 1:0 pure compute, 0:1 pure
+    /// memory, 1:1 balanced, 4:1 CPU-bound. --cpu-workers / --mem-mb take
+    /// precedence.
+    #[arg(long, value_name = "cpu:mem", value_parser = parse_cpu_mem_ratio)]
+    pub cpu_mem_ratio: Option<(u64, u64)>,
+    /// CPU worker kernel: lcg, fp, sha256, cache-thrash, memcpy, avx512,
+    /// synthetic-mix or branch. memcpy copies a buffer per op and shows copy
+    /// GB/s in the cpy section; avx512 runs 512-bit FMA loops (AVX2 or the
+    /// LCG where unsupported); synthetic-mix interleaves integer, float and
+    /// memory ops in the --cpu-mix-* ratios (default 50/30/20); branch takes
+    /// unpredictable data-dependent branches (see --perf-counters).
+    #[arg(long, value_name = "WORKLOAD", default_value = "lcg", value_parser = parse_cpu_workload)]
+    pub cpu_workload: CpuWorkload,
+    /// Buffer size in KB copied by the memcpy workload (default: 1024).
+    #[arg(long = "cpu-memcpy-size", value_name = "KB", value_parser = clap::value_parser!(u64).range(1..))]
+    pub cpu_memcpy_kb: Option<u64>,
+    /// Read L1/L2/L3 sizes from sysfs, print them in the banner and size the
+    /// cache-thrash working set to twice the largest cache instead of a
+    /// fixed 64 MB.
+    #[arg(long)]
+    pub cpu_cache_size_detect: bool,
+    /// Count branch instructions and mispredictions with hardware perf
+    /// counters (all threads, user space) and show the miss rate in the cpu
+    /// section. Needs a PMU (often missing in VMs) and
+    /// kernel.perf_event_paranoid <= 2.
+    #[arg(long)]
+    pub perf_counters: bool,
+    /// Percent of synthetic-mix ops that are integer LCG steps. The three
+    /// --cpu-mix-* flags must sum to 100; omitted ones count as 0. They
+    /// are an error unless --cpu-workload, --cpu-phase-list or
+    /// --phase-file runs synthetic-mix.
+    #[arg(long, value_name = "pct", value_parser = clap::value_parser!(u32).range(0..=100))]
+    pub cpu_mix_integer: Option<u32>,
+    /// Percent of synthetic-mix ops that are floating-point sin/cos.
+    #[arg(long, value_name = "pct", value_parser = clap::value_parser!(u32).range(0..=100))]
+    pub cpu_mix_float: Option<u32>,
+    /// Percent of synthetic-mix ops that are random reads from a 256 KB hot
+    /// set.
+    #[arg(long, value_name = "pct", value_parser = clap::value_parser!(u32).range(0..=100))]
+    pub cpu_mix_memory: Option<u32>,
+    /// Run N extra threads multiplying --cpu-matrix-n sized f64 matrices in
+    /// a loop (FPU, SIMD and caches at once); shows MAT products/s and
+    /// GFLOP/s in the cpu section.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub cpu_matrix_workers: usize,
+    /// Matrix dimension for --cpu-matrix-workers.
+    #[arg(long, value_name = "N", default_value_t = 256, value_parser = clap::value_parser!(u64).range(1..=8192))]
+    pub cpu_matrix_n: u64,
+    /// Cycle CPU workers through workloads, e.g. lcg:30s,fp:30s,cache-thrash:1m.
+    /// Ends the run after the last phase unless --cpu-phase-loop is set.
+    #[arg(long = "cpu-phase-list", value_name = "workload:dur,...", value_parser = parse_phase_list)]
+    pub cpu_phases: Option<PhaseList>,
+    /// Repeat --cpu-phase-list forever.
+    #[arg(long)]
+    pub cpu_phase_loop: bool,
+    /// Run `[[phase]]` entries back to back. Stops after the last phase.
+    /// Only these keys are supported: description, duration, cpu_workers,
+    /// cpu_workload, mem, disk. They are the settings that can change
+    /// between phases while workers keep running; every other flag comes
+    /// from the command line and holds for the whole run, and unset keys
+    /// use the command line too.
+    #[arg(long, value_name = "path")]
+    pub phase_file: Option<PathBuf>,
+    /// Run the script's lines in order and exit: `stress <dur> [flags...]`
+    /// starts stress_all with those flags for <dur>, `sleep <dur>` waits.
+    /// Prints each run's elapsed time and a table comparing their summaries.
+    #[arg(long, value_name = "path")]
+    pub stress_script: Option<PathBuf>,
+    /// Memory target in MB (default: ~60% of RAM, capped).
+    #[arg(
+        long,
+        value_name = "MB",
+        default_value_t = 0,
+        hide_default_value = true
+    )]
+    pub mem_mb: u64,
+    /// Disk file size in GB.
+    #[arg(long, value_name = "GB", default_value_t = 1.0)]
+    pub disk_gb: f64,
+    /// Directory for disk stress file (default: system temp).
+    #[arg(long, value_name = "path")]
+    pub temp_dir: Option<PathBuf>,
+    /// Write the same offsets, sizes and data to a second file at <path> and
+    /// compare per-path throughput.
+    #[arg(long, value_name = "path")]
+    pub disk_mirror: Option<PathBuf>,
+    /// Keep the disk stress file(s) after the run.
+    #[arg(long)]
+    pub no_cleanup: bool,
+    /// Stop the run as soon as any worker reports an I/O error, print it and
+    /// exit with status 1 (for CI).
+    #[arg(long)]
+    pub exit_after_first_error: bool,
+    /// Write directly to a block device (O_DIRECT) sized by BLKGETSIZE64.
+    /// DESTROYS ALL DATA ON <dev>; requires --i-understand-this-destroys-data.
+    /// --disk-io-type read also writes: it prefills the device before reading.
+    /// Cannot be combined with --disk-compress (compressed writes are not
+    /// block-aligned).
+    #[arg(long, value_name = "dev")]
+    pub disk_raw_device: Option<PathBuf>,
+    /// Confirms --disk-raw-device.
+    #[arg(long = "i-understand-this-destroys-data")]
+    pub destroy_confirmed: bool,
+    /// fsync the disk file every N writes (0 = never).
+    #[arg(long, value_name = "ops", default_value_t = 0)]
+    pub disk_fsync_interval: u64,
+    /// Stall the disk worker for --disk-stall-duration-ms after every N reads
+    /// and writes, like a hung device (0 = never). Shows STALL in the TUI
+    /// and disk_stall_count in the CSV.
+    #[arg(long, value_name = "ops", default_value_t = 0)]
+    pub disk_stall_interval: u64,
+    /// Length of each --disk-stall-interval stall.
+    #[arg(long, value_name = "ms", default_value_t = 1000)]
+    pub disk_stall_duration_ms: u64,
+    /// Count writes slower than this many microseconds as latency SLA
+    /// violations; shows "SLA viol N%" of timed disk writes in the TUI and
+    /// disk_sla_violation_pct in the CSV.
+    #[arg(long, value_name = "us", value_parser = clap::value_parser!(u64).range(1..))]
+    pub disk_latency_sla: Option<u64>,
+    /// Stop the run and exit with status 4 once SLA violations exceed this
+    /// percentage of the disk writes timed in one sample (100 = never).
+    /// Samples with fewer than 100 timed writes are merged with the next.
+    #[arg(long, value_name = "pct", default_value_t = 100.0, value_parser = parse_pct)]
+    pub disk_sla_abort_pct: f64,
+    /// Drop the Linux page cache (sync, then `3` into
+    /// /proc/sys/vm/drop_caches) before disk workers start in read or mixed
+    /// mode, including at each phase that restarts them. Needs root; skipped
+    /// with a warning otherwise.
+    #[arg(long)]
+    pub disk_cache_flush: bool,
+    /// Also drop the page cache every N seconds during the run (0 = never).
+    /// Needs root.
+    #[arg(long, value_name = "s", default_value_t = 0)]
+    pub disk_cache_flush_interval: u64,
+    /// Replay a trace file (lines: `W|R offset size [time_s]`, `#` comments)
+    /// in a loop instead of random writes.
+    #[arg(long, value_name = "path")]
+    pub disk_trace: Option<PathBuf>,
+    /// Sleep a simulated device latency before each I/O: hdd (1-12ms by seek
+    /// distance), ssd (50-200us) or nvme (10-50us).
+    #[arg(long, value_name = "hdd|ssd|nvme", value_parser = parse_latency_model)]
+    pub disk_latency_model: Option<LatencyModel>,
+    /// I/O scheduling class of the disk worker (ioprio_set): realtime, high,
+    /// normal, low or idle. realtime usually needs root.
+    #[arg(
+        long,
+        alias = "disk-worker-priority",
+        value_name = "CLASS",
+        value_parser = parse_io_priority
+    )]
+    pub disk_io_priority: Option<IoPriority>,
+    /// Cap each disk worker at N reads/writes per second (token bucket with a
+    /// burst of 2N), e.g. to mimic a slow cloud storage tier (0 = unlimited).
+    #[arg(long, value_name = "iops", default_value_t = 0)]
+    pub disk_io_throttle: u64,
+    /// Nice level of the disk worker thread, from -20 (highest priority) to
+    /// 19 (lowest). Negative levels need root or CAP_SYS_NICE.
+    #[arg(
+        long,
+        value_name = "-20..19",
+        allow_negative_numbers = true,
+        value_parser = clap::value_parser!(i32).range(-20..=19)
+    )]
+    pub disk_worker_nice: Option<i32>,
+    /// Read each block, XOR it with random bytes and write it back instead of
+    /// plain random writes.
+    #[arg(long)]
+    pub disk_rmw: bool,
+    /// Issue random writes (4KB-1MB) through POSIX AIO with N requests in
+    /// flight (0 = synchronous). glibc Linux on x86_64 and aarch64 only.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub disk_aio_depth: usize,
+    /// Disk write pattern: random or zip-sequentially. zip-sequentially fills
+    /// the file front to back, then switches to random overwrites.
+    #[arg(long, value_name = "PATTERN", default_value = "random", value_parser = parse_io_pattern)]
+    pub io_pattern: IoPattern,
+    /// Where random disk blocks land: uniform, hotspot (Zipf-distributed
+    /// 4KB slots; a few hot slots take most accesses, like OLTP) or
+    /// sequential (each block after the previous one, wrapping).
+    #[arg(
+        long,
+        value_name = "uniform|hotspot|sequential",
+        default_value = "uniform",
+        value_parser = parse_disk_access_pattern
+    )]
+    pub disk_access_pattern: DiskAccessPattern,
+    /// Zipf exponent for --disk-access-pattern hotspot; larger is more
+    /// skewed. On a 100MB file the hottest 1% of slots take about half the
+    /// accesses at 1.0 and about 80% at 1.2.
+    #[arg(long, value_name = "s", default_value_t = 1.2, value_parser = parse_zipf_s)]
+    pub zipf_s: f64,
+    /// Random reads, writes or both. read uses --disk-read-file, else creates
+    /// and fills the usual file first; read and mixed ignore --disk-rmw,
+    /// --disk-aio-depth and --io-pattern.
+    #[arg(long, value_name = "read|write|mixed", default_value = "write", value_parser = parse_disk_io_type)]
+    pub disk_io_type: DiskIoType,
+    /// Share of reads in mixed mode; values above 100 are treated as 100.
+    #[arg(long, value_name = "0-100", default_value_t = 50)]
+    pub disk_read_pct: u32,
+    /// Existing file read by --disk-io-type read (opened read-only, never
+    /// removed).
+    #[arg(long, value_name = "path")]
+    pub disk_read_file: Option<PathBuf>,
+    /// fallocate the whole disk file before the first write so writes
+    /// overwrite allocated blocks, not sparse holes; prints how long it took.
+    #[arg(long)]
+    pub disk_pre_allocate: bool,
+    /// Compress each write buffer before writing it: none, lz4 or zstd (needs
+    /// the `lz4` / `zstd` feature). Reports RAW MB/s (logical) and PHYS MB/s
+    /// (bytes written). Not applied to AIO.
+    #[arg(long, value_name = "none|lz4|zstd", default_value = "none", value_parser = parse_disk_compress)]
+    pub disk_compress: DiskCompress,
+    /// Write buffer content: zero or ones (fully compressible), random
+    /// (incompressible) or pattern (a repeating sequence, see
+    /// --disk-pattern-hex). Comparing MB/s between zero and random shows
+    /// whether the device compresses transparently. Default: a block-number
+    /// byte per 4 KiB page, zeros elsewhere.
+    #[arg(long, value_name = "zero|ones|random|pattern", value_parser = parse_disk_write_pattern)]
+    pub disk_write_pattern: Option<DiskWritePattern>,
+    /// Bytes repeated by --disk-write-pattern pattern, as hex (1-64 bytes,
+    /// e.g. deadbeef; default 000102...3f).
+    #[arg(long, value_name = "hex", value_parser = parse_pattern_hex)]
+    pub disk_pattern_hex: Option<PatternHex>,
+    /// Disable disk stress.
+    #[arg(long)]
+    pub no_disk: bool,
+    /// Run N client/echo pairs over Unix domain sockets in the temp dir.
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub net_workers_unix: usize,
+    /// Run N sender/receiver pairs over Unix datagram sockets in the temp
+    /// dir; reports DGRAM/s and EAGAIN drops/s (CSV: udp_dgram_s,
+    /// udp_drops_s).
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub net_udp_workers: usize,
+    /// Run N threads that connect to a loopback TCP listener, exchange a byte
+    /// and close, in a loop; reports SOCK conn/s and TIME_WAIT sockets
+    /// (/proc/net/sockstat).
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub socket_stress: usize,
+    /// Run --socket-stress over IPv6 loopback (::1) instead of 127.0.0.1;
+    /// falls back to IPv4 with a warning if ::1 cannot be bound.
+    #[arg(long)]
+    pub net_ipv6: bool,
+    /// Disable memory stress.
+    #[arg(long)]
+    pub no_mem: bool,
+    /// Show NUMA page migrations/s and hit ratio from /proc/vmstat.
+    #[arg(long = "mem-numa-balancing-monitor")]
+    pub mem_numa_monitor: bool,
+    /// Back the memory target with 2MB huge pages (MAP_HUGETLB) as far as
+    /// HugePages_Free allows; falls back to regular pages.
+    #[arg(long)]
+    pub mem_huge_pages: bool,
+    /// Allocate the memory target zeroed without touching it, then time one
+    /// write pass faulting every page in (cold memory). By default pages are
+    /// touched as they are allocated (prefaulted). Either way the time is
+    /// reported as mem_fault_duration_s.
+    #[arg(long)]
+    pub mem_zero: bool,
+    /// How memory workers allocate: malloc (Rust allocator, default), calloc
+    /// (lazily zeroed pages) or mmap (anonymous, THP-advised). The fault time
+    /// in mem_fault_duration_s is measured per strategy.
+    #[arg(
+        long,
+        value_name = "malloc|calloc|mmap",
+        default_value = "malloc",
+        value_parser = parse_mem_alloc_strategy
+    )]
+    pub prealloc_strategy: MemAllocStrategy,
+    /// Allocate/free like a generational GC heap instead of touching a fixed
+    /// buffer set.
+    #[arg(long)]
+    pub mem_gc_pattern: bool,
+    /// GC pattern young object size (default: 4).
+    #[arg(long, value_name = "KB")]
+    pub mem_young_size: Option<u64>,
+    /// GC pattern young objects per burst (default: 1024).
+    #[arg(long, value_name = "N")]
+    pub mem_young_count: Option<usize>,
+    /// GC pattern old object size (default: 64).
+    #[arg(long, value_name = "MB")]
+    pub mem_old_size: Option<u64>,
+    /// Sample interval in ms (min: 10).
+    #[arg(long, value_name = "ms", default_value_t = 1000)]
+    pub sample_ms: u64,
+    /// Redraw the charts at most this often. Samples between redraws still
+    /// fill the history and a live status line updates in place.
+    #[arg(long, value_name = "ms", default_value_t = GRAPH_REFRESH_MS)]
+    pub graph_refresh_ms: u64,
+    /// Redraw the charts at most N times a second (default: 4); the same
+    /// limit as --graph-refresh-ms, in frames. Sampling keeps its own
+    /// --sample-ms rate, so fast sampling no longer makes the screen flicker.
+    #[arg(long, value_name = "N", conflicts_with = "graph_refresh_ms", value_parser = clap::value_parser!(u64).range(1..=100))]
+    pub tui_fps: Option<u64>,
+    /// Double the interval (up to 5s) after 5 samples with every metric
+    /// within 2%, halve it when one moves >5%.
+    #[arg(long)]
+    pub adaptive_sample: bool,
+    /// Lower bound for --adaptive-sample (default: --sample-ms).
+    #[arg(
+        long,
+        value_name = "ms",
+        default_value_t = 0,
+        hide_default_value = true
+    )]
+    pub min_sample_ms: u64,
+    /// Append samples to CSV file.
+    #[arg(long = "csv", value_name = "path")]
+    pub csv_path: Option<PathBuf>,
+    /// Append each sample to <path> as a length-delimited protobuf
+    /// `StressSample` (proto/stress_sample.proto) with the CSV columns and a
+    /// timestamp_ns. Needs the `proto` feature.
+    #[arg(long, value_name = "path", value_parser = parse_output_proto)]
+    pub output_proto: Option<PathBuf>,
+    /// At the end of the run, write per-subsystem op latency histograms
+    /// (disk_write, net_rtt, sock_connect, mem_op; nanoseconds) to <path> as
+    /// an HdrHistogram interval log, readable by hdrlog. Memory ops are
+    /// sampled 1 in 64. Needs the `latency_histogram` feature.
+    #[arg(long, value_name = "path", value_parser = parse_latency_histogram_export)]
+    pub latency_histogram_export: Option<PathBuf>,
+    /// Significant decimal digits the latency histograms keep (HdrHistogram
+    /// precision; 1-5).
+    #[arg(long, value_name = "N", default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=5))]
+    pub latency_histogram_buckets: u8,
+    /// Export spans for sampled disk I/O operations and CPU bursts to the
+    /// OTLP HTTP collector at <url> (e.g. http://localhost:4318). Needs the
+    /// `otlp` feature.
+    #[arg(long, value_name = "url", value_parser = parse_otlp_endpoint)]
+    pub otlp_endpoint: Option<String>,
+    /// Fraction of disk I/Os and CPU bursts exported as spans (0.0-1.0).
+    #[arg(long, value_name = "rate", default_value_t = 0.01, value_parser = parse_otlp_sample_rate)]
+    pub otlp_sample_rate: f64,
+    /// Write end-of-run statistics (incl. CPU utilization histogram) as JSON.
+    #[arg(long, value_name = "path")]
+    pub summary_json: Option<PathBuf>,
+    /// Write the --summary-json statistics to <path> for a later
+    /// --compare-baseline.
+    #[arg(long, value_name = "path")]
+    pub save_baseline: Option<PathBuf>,
+    /// Compare the run against a saved baseline at the end, printing each
+    /// metric's change. Exits with status 3 when a throughput metric
+    /// regressed.
+    #[arg(long, value_name = "path")]
+    pub compare_baseline: Option<PathBuf>,
+    /// Drop that counts as a regression.
+    #[arg(long, value_name = "pct", default_value_t = 10.0)]
+    pub regression_threshold_pct: f64,
+    /// Rewrite <path> (pid and unix time) every sample so an external
+    /// watchdog can detect hangs by its mtime. Removed on clean exit.
+    #[arg(long, value_name = "path")]
+    pub heartbeat_file: Option<PathBuf>,
+    /// Graph width (default: terminal width - 25).
+    #[arg(long, value_name = "N", default_value_t = 0, hide_default_value = true)]
+    pub graph_width: usize,
+    /// Graph height (4-12).
+    #[arg(long, value_name = "N", default_value_t = 8)]
+    pub graph_height: usize,
+    /// Time span covered by the charts (default: one second per column).
+    /// History grows to cover it at fast rates.
+    #[arg(long = "graph-window", value_name = "sec|Xm", default_value = "0", value_parser = parse_duration, hide_default_value = true)]
+    pub graph_window_s: u64,
+    /// Show each chart as a time series (ts), a histogram of the window's
+    /// samples (hist), or both.
+    #[arg(long, value_name = "ts|hist|both", default_value = "ts", value_parser = parse_chart_mode)]
+    pub chart_mode: ChartMode,
+    /// Comma-separated TUI sections in render order; unlisted ones are
+    /// hidden. Sections: cpu, cpy (memcpy GB/s), mem, disk, net, gpu, ctx
+    /// (context switches/s). Default: gpu,cpu,cpy,mem,disk,net.
+    #[arg(long, value_name = "sections", value_parser = parse_tui_layout)]
+    pub tui_layout: Option<Layout>,
+    /// Show an exponential moving average instead of the window average in
+    /// chart headers (default alpha: 0.2).
+    #[arg(long, value_name = "0.0-1.0", value_parser = parse_ema_alpha)]
+    pub ema_alpha: Option<f64>,
+    /// Append cpu_pct_ema, mem_pct_ema and disk_mb_s_ema CSV columns.
+    #[arg(long)]
+    pub csv_ema: bool,
+    /// List every thermal zone (type and temperature).
+    #[arg(long)]
+    pub show_all_thermals: bool,
+    /// Read GPU stats from a status file (nvidia-smi output).
+    #[arg(long = "gpu-status", value_name = "path")]
+    pub gpu_status_path: Option<PathBuf>,
+    /// GPU stats source. nvml loads libnvidia-ml at runtime (requires the
+    /// `nvml` feature) and falls back to --gpu-status when unavailable.
+    #[arg(long, value_name = "file|nvml", default_value = "file", value_parser = parse_gpu_backend)]
+    pub gpu_backend: GpuBackend,
+    /// Keep the GPU busy with a SAXPY kernel through CUDA (libcuda), or
+    /// OpenCL when CUDA is missing, and show GFLOP/s in the GPU section.
+    /// Requires the `gpu_compute` feature.
+    #[arg(long)]
+    pub gpu_compute_stress: bool,
+    /// Throttle CPU workers to N ops/s in total (token bucket per worker);
+    /// at least 1.
+    #[arg(long, value_name = "N", value_parser = parse_ops_target)]
+    pub cpu_ops_target: Option<f64>,
+    /// Spin CPU workers without the occasional sleep (maximum heat and power
+    /// draw).
+    #[arg(long)]
+    pub cpu_busy_wait: bool,
+    /// Yield the CPU (sched_yield) after every N ops instead.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub cpu_yield_after_ops: Option<u64>,
+    /// What CPU workers do after each burst (about 0.1ms of work). none
+    /// spins (same as --cpu-busy-wait): ~100% CPU, almost no context
+    /// switches. yield calls sched_yield: still ~100% CPU, but roughly 30x the
+    /// context switch rate as workers re-enter the run queue every burst.
+    /// park sleeps until the next sample wakes the worker: one burst per
+    /// sample, so CPU stays near idle with a couple of switches per worker
+    /// per sample. sleep naps 200us after about 1 burst in 1200: ~100% CPU
+    /// with few extra switches.
+    #[arg(long, value_name = "none|yield|park|sleep", default_value = "sleep", value_parser = parse_cpu_yield_strategy)]
+    pub cpu_worker_yield_strategy: CpuYieldStrategy,
+    /// Limit CPU package power to W watts through the RAPL powercap
+    /// interface for the run (needs root); the original limit is restored on
+    /// exit. Must lie between 1 W and the package's maximum power.
+    #[arg(long, value_name = "W")]
+    pub cpu_energy_cap: Option<f64>,
+    /// Stack size of CPU worker threads (default: OS default, usually 8MB).
+    #[arg(long, value_name = "bytes", value_parser = clap::value_parser!(u64).range(4096..))]
+    pub thread_stack_size: Option<u64>,
+    /// Pin CPU workers to the CPUs of NUMA node N.
+    #[arg(long, value_name = "N")]
+    pub cpu_numa_node: Option<usize>,
+    /// Pin CPU workers to one logical CPU per physical core (skips
+    /// hyperthread siblings).
+    #[arg(long)]
+    pub cpu_isolate_ht: bool,
+    /// Pin each CPU worker to one CPU and bind its memory to that CPU's NUMA
+    /// node (local-only allocations).
+    #[arg(long)]
+    pub cpu_affinity_auto_numa: bool,
+    /// Bind memory worker allocations to NUMA node N.
+    #[arg(long, value_name = "N")]
+    pub mem_numa_node: Option<usize>,
+    /// Memory workers, each targeting 1/N of the memory target.
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    pub mem_worker_count: u64,
+    /// Throttle memory workers to GB/s in total (token bucket per worker);
+    /// each op then writes every cache line of a 64KB span. At least 0.001.
+    #[arg(long, value_name = "GB/s", value_parser = parse_bandwidth_target)]
+    pub mem_bandwidth_target: Option<f64>,
+    /// Flip a random bit at a random location of the memory target this
+    /// many times a second in total, for testing code that must survive
+    /// memory errors (0 = disabled, else 0.000001-1000000). Not applied to
+    /// --mem-gc-pattern.
+    #[arg(long, value_name = "rate", default_value_t = 0.0, value_parser = parse_error_injection_rate)]
+    pub mem_error_injection: f64,
+    /// Software-prefetch each random memory access this many accesses ahead
+    /// (x86 `_mm_prefetch`; 0 = off). Not applied to --mem-bandwidth-target.
+    #[arg(long, value_name = "distance", default_value_t = 0)]
+    pub mem_prefetch: usize,
+    /// Run busy-waiting memory workers for --duration without prefetch,
+    /// then as long again with --mem-prefetch, without the TUI or other
+    /// workers, and print the speedup. Without --mem-prefetch, sweeps
+    /// distances 1, 2, 4, 8, 16 and 32 and reports the best. Passes last
+    /// 10s (5s when sweeping) unless --duration is set.
+    #[arg(long)]
+    pub mem_prefetch_compare: bool,
+    /// Bind memory worker i to NUMA node i mod nodes.
+    #[arg(long)]
+    pub mem_numa_interleave: bool,
+    /// Pin memory worker i to the i-th CPU of a list like 0,4,8-11 (one CPU
+    /// per --mem-worker-count worker). With --mem-numa-interleave the
+    /// worker keeps its node's memory, giving explicit cross-node access.
+    #[arg(long, value_name = "cpu-list", value_parser = parse_cpu_list)]
+    pub mem_worker_bind_cpu: Option<CpuList>,
+    /// Before the run, sweep access strides 8B..4KB over a 256 MB buffer and
+    /// print MB/s per stride.
+    #[arg(long)]
+    pub mem_stride_test: bool,
+    /// Time spent at each stride.
+    #[arg(long, value_name = "sec", default_value_t = 2)]
+    pub stride_test_duration_s: u64,
+    /// Run a pointer-chasing worker over a 256 MB randomly linked list of
+    /// cache lines and show the random access latency ("MEM lat") in the TUI.
+    #[arg(long)]
+    pub mem_latency_test: bool,
+    /// Before the run, pointer-chase working sets of 1..64 MB for 1s each and
+    /// chart ns per access; the steps mark the L1/L2/L3/DRAM boundaries.
+    #[arg(long)]
+    pub mem_latency_sweep: bool,
+    /// Stop once CPU% >= N for --stop-sustain-samples samples.
+    #[arg(long, value_name = "N")]
+    pub stop_on_cpu_pct: Option<f64>,
+    /// Stop once MEM% >= N for --stop-sustain-samples samples.
+    #[arg(long, value_name = "N")]
+    pub stop_on_mem_pct: Option<f64>,
+    /// Consecutive samples needed to trigger a stop.
+    #[arg(long, value_name = "K", default_value_t = 3)]
+    pub stop_sustain_samples: usize,
+    /// Run `sh -c <command>` when a --stop-on-* threshold trips or a worker
+    /// reports an error, with STRESS_ALERT_TYPE (cpu_pct, mem_pct,
+    /// worker_error) and STRESS_ALERT_VALUE (the metric, or the error count)
+    /// set. Runs at most once per alert type.
+    #[arg(long, value_name = "command")]
+    pub alert_cmd: Option<String>,
+    /// Check which metric readers work on this machine and exit.
+    #[arg(long)]
+    pub self_test: bool,
+    /// Log extra diagnostics (e.g. interval changes) to stderr (same as
+    /// --log-level debug).
+    #[arg(long)]
+    pub verbose: bool,
+    /// Diagnostics written to stderr: trace, debug, info, warn or error
+    /// (default: RUST_LOG, else info). debug adds worker start/stop and
+    /// seeds, trace every disk I/O size and offset.
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<log::LevelFilter>,
+    /// Print detected hardware (OS, CPU, RAM, NUMA, thermal zones,
+    /// filesystem, cgroup limits) and the resolved configuration before
+    /// starting.
+    #[arg(long)]
+    pub verbose_startup: bool,
+    /// Print a completion script for <shell> (bash, zsh, fish, elvish,
+    /// powershell) to stdout and exit.
+    #[arg(long, value_name = "shell")]
+    pub completions: Option<Shell>,
+}
+
+/// `--cpu-phase-list` value. A newtype so clap takes the whole list as one
+/// value instead of treating the field as repeatable.
+#[derive(Clone, Debug)]
+pub struct PhaseList(pub Vec<CpuPhase>);
+
+/// `--tui-layout` value; see [`PhaseList`].
+#[derive(Clone, Debug)]
+pub struct Layout(pub Vec<TuiSection>);
+
+/// `--mem-worker-bind-cpu` value; see [`PhaseList`].
+#[derive(Clone, Debug)]
+pub struct CpuList(pub Vec<usize>);
+
+/// `--disk-pattern-hex` value (one value of bytes, not a list).
+#[derive(Clone, Debug)]
+pub struct PatternHex(pub Vec<u8>);
+
+/// `N` or `Ns` seconds, or `Nm` minutes.
+pub fn parse_duration(s: &str) -> Result<u64, String> {
+    let (num, scale) = if let Some(stripped) = s.strip_suffix('m') {
+        (stripped, 60)
+    } else if let Some(stripped) = s.strip_suffix('s') {
+        (stripped, 1)
+    } else {
+        (s, 1)
+    };
+    num.parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(scale))
+        .ok_or_else(|| format!("invalid duration (want N, Ns or Nm): {}", s))
+}
+
+fn parse_cpu_workload(s: &str) -> Result<CpuWorkload, String> {
+    CpuWorkload::parse(s).ok_or_else(|| {
+        let names: Vec<&str> = CpuWorkload::ALL.iter().map(|w| w.name()).collect();
+        format!("unknown workload (want {})", names.join("|"))
+    })
+}
+
+fn parse_cpu_mem_ratio(s: &str) -> Result<(u64, u64), String> {
+    let invalid = || format!("want cpu:mem with a non-zero sum: {}", s);
+    let (cpu, mem) = s.split_once(':').ok_or_else(invalid)?;
+    let ratio = (
+        cpu.parse::<u64>().map_err(|_| invalid())?,
+        mem.parse::<u64>().map_err(|_| invalid())?,
+    );
+    if ratio.0 + ratio.1 == 0 {
+        return Err(invalid());
+    }
+    Ok(ratio)
+}
+
+fn parse_phase_list(s: &str) -> Result<PhaseList, String> {
+    s.split(',')
+        .filter(|p| !p.trim().is_empty())
+        .map(|p| {
+            let (name, dur) = p
+                .trim()
+                .split_once(':')
+                .ok_or_else(|| format!("want workload:duration: {}", p))?;
+            let duration_s = parse_duration(dur)?;
+            if duration_s == 0 {
+                return Err(format!("zero duration: {}", p));
+            }
+            Ok(CpuPhase {
+                workload: parse_cpu_workload(name)?,
+                duration_s,
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(PhaseList)
+}
+
+fn parse_cpu_list(s: &str) -> Result<CpuList, String> {
+    match super::parse_cpu_list(s) {
+        Some(cpus) if !cpus.is_empty() => Ok(CpuList(cpus)),
+        _ => Err("want a CPU list like 0,2,4-7".to_string()),
+    }
+}
+
+fn parse_mem_alloc_strategy(s: &str) -> Result<MemAllocStrategy, String> {
+    MemAllocStrategy::parse(s).ok_or_else(|| "want malloc, calloc or mmap".to_string())
+}
+
+fn parse_latency_model(s: &str) -> Result<LatencyModel, String> {
+    LatencyModel::parse(s).ok_or_else(|| "want hdd, ssd or nvme".to_string())
+}
+
+fn parse_io_priority(s: &str) -> Result<IoPriority, String> {
+    IoPriority::parse(s).ok_or_else(|| "want realtime, high, normal, low or idle".to_string())
+}
+
+fn parse_io_pattern(s: &str) -> Result<IoPattern, String> {
+    IoPattern::parse(s).ok_or_else(|| "want random or zip-sequentially".to_string())
+}
+
+fn parse_disk_access_pattern(s: &str) -> Result<DiskAccessPattern, String> {
+    DiskAccessPattern::parse(s).ok_or_else(|| "want uniform, hotspot or sequential".to_string())
+}
+
+fn parse_zipf_s(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|v| *v > 0.0 && v.is_finite())
+        .ok_or_else(|| "want a positive exponent".to_string())
+}
+
+fn parse_ops_target(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|v| *v >= 1.0 && v.is_finite())
+        .ok_or_else(|| "want a finite rate of at least 1 op/s".to_string())
+}
+
+fn parse_bandwidth_target(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|v| *v >= 0.001 && v.is_finite())
+        .ok_or_else(|| "want a finite bandwidth of at least 0.001 GB/s".to_string())
+}
+
+fn parse_pct(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|v| *v > 0.0 && *v <= 100.0)
+        .ok_or_else(|| "want a percentage in (0, 100]".to_string())
+}
+
+fn parse_error_injection_rate(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|v| *v == 0.0 || (1e-6..=1e6).contains(v))
+        .ok_or_else(|| "want 0 or a rate in [0.000001, 1000000] flips/s".to_string())
+}
+
+fn parse_disk_io_type(s: &str) -> Result<DiskIoType, String> {
+    DiskIoType::parse(s).ok_or_else(|| "want read, write or mixed".to_string())
+}
+
+fn parse_disk_compress(s: &str) -> Result<DiskCompress, String> {
+    let compress = DiskCompress::parse(s).ok_or_else(|| "want none, lz4 or zstd".to_string())?;
+    if !compress.available() {
+        return Err(format!("requires building with the `{}` feature", s));
+    }
+    Ok(compress)
+}
+
+fn parse_disk_write_pattern(s: &str) -> Result<DiskWritePattern, String> {
+    DiskWritePattern::parse(s).ok_or_else(|| "want zero, ones, random or pattern".to_string())
+}
+
+fn parse_pattern_hex(s: &str) -> Result<PatternHex, String> {
+    let hex = s.strip_prefix("0x").unwrap_or(s);
+    if hex.is_empty() || !hex.len().is_multiple_of(2) || hex.len() / 2 > DiskWritePattern::PATTERN_MAX {
+        return Err(format!(
+            "want 1-{} bytes as an even number of hex digits",
+            DiskWritePattern::PATTERN_MAX
+        ));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("invalid hex: {}", s))
+        })
+        .collect::<Result<_, _>>()
+        .map(PatternHex)
+}
+
+fn parse_output_proto(s: &str) -> Result<PathBuf, String> {
+    if !cfg!(feature = "proto") {
+        return Err("requires building with the `proto` feature".to_string());
+    }
+    Ok(PathBuf::from(s))
+}
+
+fn parse_latency_histogram_export(s: &str) -> Result<PathBuf, String> {
+    if !cfg!(feature = "latency_histogram") {
+        return Err("requires building with the `latency_histogram` feature".to_string());
+    }
+    Ok(PathBuf::from(s))
+}
+
+fn parse_otlp_endpoint(s: &str) -> Result<String, String> {
+    if !cfg!(feature = "otlp") {
+        return Err("requires building with the `otlp` feature".to_string());
+    }
+    if !s.starts_with("http://") && !s.starts_with("https://") {
+        return Err("want an http:// or https:// URL".to_string());
+    }
+    Ok(s.to_string())
+}
+
+fn parse_otlp_sample_rate(s: &str) -> Result<f64, String> {
+    s.parse::<f64>()
+        .ok()
+        .filter(|r| (0.0..=1.0).contains(r))
+        .ok_or_else(|| "want a rate between 0.0 and 1.0".to_string())
+}
+
+fn parse_chart_mode(s: &str) -> Result<ChartMode, String> {
+    ChartMode::parse(s).ok_or_else(|| "want ts, hist or both".to_string())
+}
+
+fn parse_tui_layout(s: &str) -> Result<Layout, String> {
+    tui_layout::parse_layout(s).map(Layout)
+}
+
+fn parse_cpu_yield_strategy(s: &str) -> Result<CpuYieldStrategy, String> {
+    CpuYieldStrategy::parse(s).ok_or_else(|| "want none, yield, park or sleep".to_string())
+}
+
+fn parse_gpu_backend(s: &str) -> Result<GpuBackend, String> {
+    match s {
+        "file" => Ok(GpuBackend::File),
+        "nvml" => Ok(GpuBackend::Nvml),
+        _ => Err("want file or nvml".to_string()),
+    }
+}
+
+fn parse_ema_alpha(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(alpha) if alpha > 0.0 && alpha <= 1.0 => Ok(alpha),
+        _ => Err("want a number in (0.0, 1.0]".to_string()),
+    }
+}
@@ -0,0 +1,121 @@
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use stress_lib::{CpuWorker, CpuWorkload, CPU_MIX_DEFAULT, MEMCPY_DEFAULT_BYTES};
+
+use super::{format_rate, Args, GLOBAL_STOP};
+
+/// Length of `--cpu-benchmark-only` when `--duration` is not given.
+const DEFAULT_BENCH_S: u64 = 10;
+/// Samples discarded while workers spin up and clocks ramp.
+const WARMUP_SAMPLES: usize = 1;
+
+/// Approximate ops/s of one Cortex-A53 core at 1 GHz, per workload, so a
+/// score of 1.0 means "one such core". Ops differ between workloads, so
+/// scores are only comparable for the same `--cpu-workload`. Memcpy ops are
+/// copies of the default buffer size; avx512 ops are 64 f64 multiply-adds
+/// (LCG steps on the scalar fallback); synthetic-mix assumes the default
+/// 50/30/20 mix.
+fn reference_ops_s(workload: CpuWorkload) -> f64 {
+    match workload {
+        CpuWorkload::Lcg => 90e6,
+        CpuWorkload::Fp => 15e6,
+        CpuWorkload::Sha256 => 0.5e6,
+        CpuWorkload::CacheThrash => 12e6,
+        CpuWorkload::Memcpy => 2.5e3,
+        CpuWorkload::Avx512 => 16e6,
+        CpuWorkload::SyntheticMix => 20e6,
+        CpuWorkload::Branch => 10e6,
+    }
+}
+
+/// Runs busy-waiting CPU workers with no TUI, samples total ops/s once a
+/// second and prints `CPU Score: N` from the median, with a 95% confidence
+/// interval. Returns the process exit code.
+pub fn run(args: &Args, workers: usize) -> i32 {
+    let workers = workers.max(1);
+    let duration_s = if args.cli.duration > 0 {
+        args.cli.duration
+    } else {
+        DEFAULT_BENCH_S
+    };
+    eprintln!(
+        "CPU benchmark: {} workers, workload {}, {}s",
+        workers,
+        args.cli.cpu_workload.name(),
+        duration_s
+    );
+    let memcpy_bytes = args.cli
+        .cpu_memcpy_kb
+        .map_or(MEMCPY_DEFAULT_BYTES, |kb| kb as usize * 1024);
+    let handles: Vec<_> = (0..workers)
+        .map(|_| {
+            let [int, fp, mem] = args.cpu_mix.unwrap_or(CPU_MIX_DEFAULT);
+            CpuWorker::new()
+                .workload(args.cli.cpu_workload)
+                .memcpy_bytes(memcpy_bytes)
+                .mix(int, fp, mem)
+                .busy_wait()
+                .spawn()
+        })
+        .collect();
+    let total_ops = || handles.iter().map(|h| h.ops_count()).sum::<u64>();
+
+    let interval = Duration::from_secs(1);
+    let start = Instant::now();
+    let mut next = start + interval;
+    let mut prev_ops = total_ops();
+    let mut prev_t = start;
+    let mut rates = Vec::new();
+    while rates.len() < duration_s as usize + WARMUP_SAMPLES && !GLOBAL_STOP.load(Ordering::Relaxed)
+    {
+        thread::sleep(next.saturating_duration_since(Instant::now()));
+        next += interval;
+        let (ops, now) = (total_ops(), Instant::now());
+        rates.push(ops.saturating_sub(prev_ops) as f64 / (now - prev_t).as_secs_f64());
+        (prev_ops, prev_t) = (ops, now);
+    }
+    for h in &handles {
+        h.stop();
+    }
+    for h in handles {
+        let _ = h.join();
+    }
+
+    let mut rates = rates.split_off(WARMUP_SAMPLES.min(rates.len()));
+    if rates.is_empty() {
+        eprintln!("CPU benchmark: interrupted before the first sample");
+        return 1;
+    }
+    rates.sort_by(f64::total_cmp);
+    let n = rates.len();
+    let median = if n % 2 == 1 {
+        rates[n / 2]
+    } else {
+        (rates[n / 2 - 1] + rates[n / 2]) / 2.0
+    };
+    // Distribution-free interval for the median: order statistics
+    // n/2 -/+ 1.96 * sqrt(n) / 2 (clamped for short runs).
+    let half_width = 0.98 * (n as f64).sqrt();
+    let lo = ((n as f64 / 2.0 - half_width).floor().max(0.0)) as usize;
+    let hi = ((n as f64 / 2.0 + half_width).ceil() as usize).min(n - 1);
+    let mut reference = reference_ops_s(args.cli.cpu_workload);
+    if args.cli.cpu_workload == CpuWorkload::Memcpy {
+        reference *= MEMCPY_DEFAULT_BYTES as f64 / memcpy_bytes as f64;
+    }
+    eprintln!(
+        "CPU ops/s: median {} over {} samples (min {}, max {})",
+        format_rate(median, "ops/s"),
+        n,
+        format_rate(rates[0], "ops/s"),
+        format_rate(rates[n - 1], "ops/s")
+    );
+    println!(
+        "CPU Score: {:.1} (95% CI {:.1}-{:.1})",
+        median / reference,
+        rates[lo] / reference,
+        rates[hi] / reference
+    );
+    0
+}
@@ -0,0 +1,101 @@
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use stress_lib::{CpuWorker, CpuWorkload};
+
+use super::{format_cpu_list, format_rate, GLOBAL_STOP};
+
+/// Untimed run on each CPU while its clock ramps up.
+const WARMUP: Duration = Duration::from_millis(200);
+/// Timed run on each CPU.
+const MEASURE: Duration = Duration::from_secs(1);
+/// A drop of more than this fraction between neighbouring CPUs (sorted by
+/// ops/s) starts a new tier; run-to-run noise on one core type stays well
+/// under it, while P- and E-cores differ by 30% or more.
+const TIER_GAP: f64 = 0.15;
+
+/// Busy LCG ops/s of one worker pinned to `cpu`, or `None` if interrupted.
+fn measure(cpu: usize) -> Option<f64> {
+    let handle = CpuWorker::new()
+        .workload(CpuWorkload::Lcg)
+        .affinity(vec![cpu])
+        .busy_wait()
+        .spawn();
+    let stopped = || GLOBAL_STOP.load(Ordering::Relaxed);
+    thread::sleep(WARMUP);
+    let (start_ops, start) = (handle.ops_count(), Instant::now());
+    let end = start + MEASURE;
+    while !stopped() && Instant::now() < end {
+        thread::sleep(Duration::from_millis(50).min(end.saturating_duration_since(Instant::now())));
+    }
+    let rate = handle.ops_count().saturating_sub(start_ops) as f64 / start.elapsed().as_secs_f64();
+    handle.stop();
+    let _ = handle.join();
+    (!stopped()).then_some(rate)
+}
+
+/// Runs the LCG worker pinned to each of `cpus` in turn, prints ops/s per
+/// CPU, then groups CPUs into performance tiers by throughput, so P- and
+/// E-cores show up without architecture-specific detection. Returns the
+/// process exit code.
+pub fn run(cpus: &[usize]) -> i32 {
+    eprintln!(
+        "CPU topology profile: {} CPUs, {:.1}s each",
+        cpus.len(),
+        (WARMUP + MEASURE).as_secs_f64()
+    );
+    let mut rates = Vec::with_capacity(cpus.len());
+    for &cpu in cpus {
+        let Some(rate) = measure(cpu) else {
+            eprintln!("CPU topology profile: interrupted");
+            return 1;
+        };
+        eprintln!("CPU {:>3}: {}", cpu, format_rate(rate, "ops/s"));
+        rates.push((cpu, rate));
+    }
+
+    let mut sorted = rates.clone();
+    sorted.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut tiers: Vec<Vec<(usize, f64)>> = Vec::new();
+    for (i, &(cpu, rate)) in sorted.iter().enumerate() {
+        let new_tier = i == 0 || rate < sorted[i - 1].1 * (1.0 - TIER_GAP);
+        if new_tier {
+            tiers.push(Vec::new());
+        }
+        if let Some(tier) = tiers.last_mut() {
+            tier.push((cpu, rate));
+        }
+    }
+
+    let fastest = sorted.first().map_or(0.0, |&(_, rate)| rate);
+    println!("{:<6} {:>5} {:>14} {:>9}  cpus", "tier", "count", "avg ops/s", "relative");
+    for (i, tier) in tiers.iter().enumerate() {
+        let mut members: Vec<usize> = tier.iter().map(|&(cpu, _)| cpu).collect();
+        members.sort_unstable();
+        let avg = tier.iter().map(|&(_, rate)| rate).sum::<f64>() / tier.len() as f64;
+        println!(
+            "{:<6} {:>5} {:>14} {:>8.2}x  {}",
+            i + 1,
+            tier.len(),
+            format_rate(avg, "ops/s"),
+            avg / fastest.max(f64::MIN_POSITIVE),
+            format_cpu_list(&members)
+        );
+    }
+    let map: Vec<String> = rates
+        .iter()
+        .map(|&(cpu, _)| {
+            let tier = tiers
+                .iter()
+                .position(|t| t.iter().any(|&(c, _)| c == cpu))
+                .map_or(0, |t| t + 1);
+            format!("{}:{}", cpu, tier)
+        })
+        .collect();
+    println!("Cluster map (cpu:tier): {}", map.join(" "));
+    if tiers.len() == 1 {
+        println!("All CPUs perform within {:.0}%: no performance/efficiency split", TIER_GAP * 100.0);
+    }
+    0
+}
@@ -0,0 +1,94 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread::{self, JoinHandle};
+
+/// `--gpu-compute-stress`: a thread launching the SAXPY kernel back to back
+/// on GPU 0 through the CUDA driver, or the first OpenCL GPU without CUDA.
+pub struct GpuCompute {
+    /// "cuda" or "opencl".
+    pub backend: &'static str,
+    pub device: String,
+    stop: Arc<AtomicBool>,
+    flops: Arc<AtomicU64>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl GpuCompute {
+    /// Sets up the kernel on the worker thread (CUDA contexts are
+    /// per-thread) and starts launching. Errors say what was missing.
+    pub fn start() -> Result<Self, String> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flops = Arc::new(AtomicU64::new(0));
+        let (tx, rx) = mpsc::channel();
+        let thread = {
+            let (stop, flops) = (Arc::clone(&stop), Arc::clone(&flops));
+            thread::spawn(move || {
+                let mut kernel = match open_kernel() {
+                    Ok(k) => k,
+                    Err(e) => {
+                        let _ = tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = tx.send(Ok((kernel.backend(), kernel.device())));
+                while !stop.load(Ordering::Relaxed) {
+                    if let Err(e) = kernel.launch() {
+                        log::error!("GPU compute: {}", e);
+                        break;
+                    }
+                    flops.fetch_add(kernel.flops_per_launch(), Ordering::Relaxed);
+                }
+            })
+        };
+        let (backend, device) = rx
+            .recv()
+            .map_err(|_| "GPU compute thread exited during setup".to_string())??;
+        Ok(Self {
+            backend,
+            device,
+            stop,
+            flops,
+            thread: Some(thread),
+        })
+    }
+
+    /// Floating-point operations completed so far.
+    pub fn flops(&self) -> u64 {
+        self.flops.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for GpuCompute {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(t) = self.thread.take() {
+            let _ = t.join();
+        }
+    }
+}
+
+#[cfg(feature = "gpu_compute")]
+fn open_kernel() -> Result<Box<dyn Kernel>, String> {
+    use crate::gpu_kernels::{Cuda, OpenCl};
+    match Cuda::open() {
+        Ok(k) => Ok(Box::new(k)),
+        Err(cuda) => match OpenCl::open() {
+            Ok(k) => Ok(Box::new(k)),
+            Err(opencl) => Err(format!("CUDA: {}; OpenCL: {}", cuda, opencl)),
+        },
+    }
+}
+
+#[cfg(not(feature = "gpu_compute"))]
+fn open_kernel() -> Result<Box<dyn Kernel>, String> {
+    Err("built without the `gpu_compute` feature".to_string())
+}
+
+/// A compute backend owned by the launch thread.
+pub(crate) trait Kernel {
+    fn backend(&self) -> &'static str;
+    fn device(&self) -> String;
+    fn flops_per_launch(&self) -> u64;
+    /// Runs one launch to completion.
+    fn launch(&mut self) -> Result<(), String>;
+}
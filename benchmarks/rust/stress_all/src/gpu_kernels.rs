@@ -0,0 +1,570 @@
+use std::ffi::{c_char, c_void, CStr};
+
+use libloading::Library;
+
+use crate::gpu_compute::Kernel;
+
+/// Elements per launch and FMA steps per element: 2^20 x 4096 x 2 flops is
+/// about 8.6 GFLOP, a few milliseconds on a current GPU.
+const ELEMENTS: usize = 1 << 20;
+const ITERS: u32 = 4096;
+const BLOCK: u32 = 256;
+/// Two flops (one FMA) per element per iteration.
+const FLOPS_PER_LAUNCH: u64 = 2 * ELEMENTS as u64 * ITERS as u64;
+/// Bounded fixed point for y = a*y + x.
+const ALPHA: f32 = 0.999;
+
+const CUDA_LIBS: [&str; 2] = ["libcuda.so.1", "libcuda.so"];
+const OPENCL_LIBS: [&str; 2] = ["libOpenCL.so.1", "libOpenCL.so"];
+
+/// Iterated SAXPY, y[i] = a*y[i] + x[i] `iters` times, as PTX for the CUDA
+/// driver to JIT (no nvcc needed at build time).
+const SAXPY_PTX: &str = "\
+.version 6.0
+.target sm_50
+.address_size 64
+
+.visible .entry saxpy_loop(
+    .param .u64 p_x,
+    .param .u64 p_y,
+    .param .f32 p_a,
+    .param .u32 p_n,
+    .param .u32 p_iters
+)
+{
+    .reg .pred %p<3>;
+    .reg .b32 %r<8>;
+    .reg .f32 %f<4>;
+    .reg .b64 %rd<8>;
+
+    ld.param.u64 %rd1, [p_x];
+    ld.param.u64 %rd2, [p_y];
+    ld.param.f32 %f1, [p_a];
+    ld.param.u32 %r1, [p_n];
+    ld.param.u32 %r2, [p_iters];
+    mov.u32 %r3, %ctaid.x;
+    mov.u32 %r4, %ntid.x;
+    mov.u32 %r5, %tid.x;
+    mad.lo.s32 %r6, %r3, %r4, %r5;
+    setp.ge.u32 %p1, %r6, %r1;
+    @%p1 bra DONE;
+    cvta.to.global.u64 %rd3, %rd1;
+    cvta.to.global.u64 %rd4, %rd2;
+    mul.wide.u32 %rd5, %r6, 4;
+    add.s64 %rd6, %rd3, %rd5;
+    add.s64 %rd7, %rd4, %rd5;
+    ld.global.f32 %f2, [%rd6];
+    ld.global.f32 %f3, [%rd7];
+    mov.u32 %r7, 0;
+LOOP:
+    fma.rn.f32 %f3, %f1, %f3, %f2;
+    add.u32 %r7, %r7, 1;
+    setp.lt.u32 %p2, %r7, %r2;
+    @%p2 bra LOOP;
+    st.global.f32 [%rd7], %f3;
+DONE:
+    ret;
+}
+\0";
+
+/// The same kernel in OpenCL C.
+const SAXPY_CL: &str = "\
+__kernel void saxpy_loop(__global const float *x, __global float *y,
+                         float a, uint n, uint iters) {
+    uint i = get_global_id(0);
+    if (i >= n) return;
+    float xi = x[i], yi = y[i];
+    for (uint k = 0; k < iters; k++) yi = fma(a, yi, xi);
+    y[i] = yi;
+}
+";
+
+fn load_first(names: &[&str]) -> Result<Library, String> {
+    names
+        .iter()
+        .find_map(|name| unsafe { Library::new(name).ok() })
+        .ok_or_else(|| format!("{} not found", names[0]))
+}
+
+fn host_inputs() -> (Vec<f32>, Vec<f32>) {
+    let x = (0..ELEMENTS).map(|i| (i as f32 * 0.001).sin()).collect();
+    (x, vec![0.0f32; ELEMENTS])
+}
+
+/// Reads a NUL-terminated name out of `buf`.
+fn c_name(buf: &[u8]) -> String {
+    CStr::from_bytes_until_nul(buf)
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+type CuResult = i32;
+type CuDevicePtr = u64;
+
+pub(crate) struct Cuda {
+    device_name: String,
+    ctx: *mut c_void,
+    func: *mut c_void,
+    x: CuDevicePtr,
+    y: CuDevicePtr,
+    launch_kernel: unsafe extern "C" fn(
+        *mut c_void,
+        u32,
+        u32,
+        u32,
+        u32,
+        u32,
+        u32,
+        u32,
+        *mut c_void,
+        *mut *mut c_void,
+        *mut *mut c_void,
+    ) -> CuResult,
+    synchronize: unsafe extern "C" fn() -> CuResult,
+    mem_free: unsafe extern "C" fn(CuDevicePtr) -> CuResult,
+    ctx_destroy: unsafe extern "C" fn(*mut c_void) -> CuResult,
+    _lib: Library,
+}
+
+impl Cuda {
+    pub(crate) fn open() -> Result<Self, String> {
+        let lib = load_first(&CUDA_LIBS)?;
+        let missing = |sym: &str| format!("{} missing from libcuda", sym);
+        unsafe {
+            let init = *lib
+                .get::<unsafe extern "C" fn(u32) -> CuResult>(b"cuInit\0")
+                .map_err(|_| missing("cuInit"))?;
+            let device_get = *lib
+                .get::<unsafe extern "C" fn(*mut i32, i32) -> CuResult>(b"cuDeviceGet\0")
+                .map_err(|_| missing("cuDeviceGet"))?;
+            let device_get_name = *lib
+                .get::<unsafe extern "C" fn(*mut c_char, i32, i32) -> CuResult>(
+                    b"cuDeviceGetName\0",
+                )
+                .map_err(|_| missing("cuDeviceGetName"))?;
+            let ctx_create = *lib
+                .get::<unsafe extern "C" fn(*mut *mut c_void, u32, i32) -> CuResult>(
+                    b"cuCtxCreate_v2\0",
+                )
+                .map_err(|_| missing("cuCtxCreate_v2"))?;
+            let module_load_data = *lib
+                .get::<unsafe extern "C" fn(*mut *mut c_void, *const c_void) -> CuResult>(
+                    b"cuModuleLoadData\0",
+                )
+                .map_err(|_| missing("cuModuleLoadData"))?;
+            let module_get_function = *lib
+                .get::<unsafe extern "C" fn(*mut *mut c_void, *mut c_void, *const c_char) -> CuResult>(
+                    b"cuModuleGetFunction\0",
+                )
+                .map_err(|_| missing("cuModuleGetFunction"))?;
+            let mem_alloc = *lib
+                .get::<unsafe extern "C" fn(*mut CuDevicePtr, usize) -> CuResult>(b"cuMemAlloc_v2\0")
+                .map_err(|_| missing("cuMemAlloc_v2"))?;
+            let memcpy_htod = *lib
+                .get::<unsafe extern "C" fn(CuDevicePtr, *const c_void, usize) -> CuResult>(
+                    b"cuMemcpyHtoD_v2\0",
+                )
+                .map_err(|_| missing("cuMemcpyHtoD_v2"))?;
+            let launch_kernel = *lib.get(b"cuLaunchKernel\0").map_err(|_| missing("cuLaunchKernel"))?;
+            let synchronize = *lib
+                .get(b"cuCtxSynchronize\0")
+                .map_err(|_| missing("cuCtxSynchronize"))?;
+            let mem_free = *lib.get(b"cuMemFree_v2\0").map_err(|_| missing("cuMemFree_v2"))?;
+            let ctx_destroy = *lib
+                .get::<unsafe extern "C" fn(*mut c_void) -> CuResult>(b"cuCtxDestroy_v2\0")
+                .map_err(|_| missing("cuCtxDestroy_v2"))?;
+
+            let check = |what: &str, rc: CuResult| {
+                if rc == 0 {
+                    Ok(())
+                } else {
+                    Err(format!("{} failed (CUresult {})", what, rc))
+                }
+            };
+            check("cuInit", init(0))?;
+            let mut dev = 0i32;
+            check("cuDeviceGet", device_get(&mut dev, 0))?;
+            let mut name = [0u8; 256];
+            check(
+                "cuDeviceGetName",
+                device_get_name(name.as_mut_ptr() as *mut c_char, name.len() as i32, dev),
+            )?;
+            let mut ctx = std::ptr::null_mut();
+            check("cuCtxCreate", ctx_create(&mut ctx, 0, dev))?;
+            // From here on the context owns everything; destroying it
+            // releases the module and buffers on the error paths too.
+            let setup = || -> Result<(*mut c_void, CuDevicePtr, CuDevicePtr), String> {
+                let mut module = std::ptr::null_mut();
+                check(
+                    "cuModuleLoadData",
+                    module_load_data(&mut module, SAXPY_PTX.as_ptr() as *const c_void),
+                )?;
+                let mut func = std::ptr::null_mut();
+                check(
+                    "cuModuleGetFunction",
+                    module_get_function(&mut func, module, c"saxpy_loop".as_ptr()),
+                )?;
+                let bytes = ELEMENTS * std::mem::size_of::<f32>();
+                let (x_host, y_host) = host_inputs();
+                let (mut x, mut y) = (0, 0);
+                check("cuMemAlloc", mem_alloc(&mut x, bytes))?;
+                check("cuMemAlloc", mem_alloc(&mut y, bytes))?;
+                check("cuMemcpyHtoD", memcpy_htod(x, x_host.as_ptr() as *const c_void, bytes))?;
+                check("cuMemcpyHtoD", memcpy_htod(y, y_host.as_ptr() as *const c_void, bytes))?;
+                Ok((func, x, y))
+            };
+            let (func, x, y) = setup().inspect_err(|_| {
+                ctx_destroy(ctx);
+            })?;
+            Ok(Self {
+                device_name: c_name(&name),
+                ctx,
+                func,
+                x,
+                y,
+                launch_kernel,
+                synchronize,
+                mem_free,
+                ctx_destroy,
+                _lib: lib,
+            })
+        }
+    }
+}
+
+impl Kernel for Cuda {
+    fn backend(&self) -> &'static str {
+        "cuda"
+    }
+
+    fn flops_per_launch(&self) -> u64 {
+        FLOPS_PER_LAUNCH
+    }
+
+    fn device(&self) -> String {
+        self.device_name.clone()
+    }
+
+    fn launch(&mut self) -> Result<(), String> {
+        let (mut x, mut y, mut a, mut n, mut iters) =
+            (self.x, self.y, ALPHA, ELEMENTS as u32, ITERS);
+        let mut params: [*mut c_void; 5] = [
+            &mut x as *mut _ as *mut c_void,
+            &mut y as *mut _ as *mut c_void,
+            &mut a as *mut _ as *mut c_void,
+            &mut n as *mut _ as *mut c_void,
+            &mut iters as *mut _ as *mut c_void,
+        ];
+        let grid = (ELEMENTS as u32).div_ceil(BLOCK);
+        let rc = unsafe {
+            (self.launch_kernel)(
+                self.func,
+                grid,
+                1,
+                1,
+                BLOCK,
+                1,
+                1,
+                0,
+                std::ptr::null_mut(),
+                params.as_mut_ptr(),
+                std::ptr::null_mut(),
+            )
+        };
+        if rc != 0 {
+            return Err(format!("cuLaunchKernel failed (CUresult {})", rc));
+        }
+        match unsafe { (self.synchronize)() } {
+            0 => Ok(()),
+            rc => Err(format!("cuCtxSynchronize failed (CUresult {})", rc)),
+        }
+    }
+}
+
+impl Drop for Cuda {
+    fn drop(&mut self) {
+        unsafe {
+            (self.mem_free)(self.x);
+            (self.mem_free)(self.y);
+            (self.ctx_destroy)(self.ctx);
+        }
+    }
+}
+
+type ClInt = i32;
+type ClHandle = *mut c_void;
+
+const CL_SUCCESS: ClInt = 0;
+const CL_DEVICE_TYPE_GPU: u64 = 1 << 2;
+const CL_DEVICE_NAME: u32 = 0x102B;
+const CL_MEM_READ_WRITE: u64 = 1 << 0;
+const CL_MEM_COPY_HOST_PTR: u64 = 1 << 5;
+
+pub(crate) struct OpenCl {
+    device_name: String,
+    context: ClHandle,
+    queue: ClHandle,
+    program: ClHandle,
+    kernel: ClHandle,
+    x: ClHandle,
+    y: ClHandle,
+    enqueue_nd_range: unsafe extern "C" fn(
+        ClHandle,
+        ClHandle,
+        u32,
+        *const usize,
+        *const usize,
+        *const usize,
+        u32,
+        *const ClHandle,
+        *mut ClHandle,
+    ) -> ClInt,
+    finish: unsafe extern "C" fn(ClHandle) -> ClInt,
+    release: [unsafe extern "C" fn(ClHandle) -> ClInt; 5],
+    _lib: Library,
+}
+
+impl OpenCl {
+    pub(crate) fn open() -> Result<Self, String> {
+        let lib = load_first(&OPENCL_LIBS)?;
+        let missing = |sym: &str| format!("{} missing from libOpenCL", sym);
+        unsafe {
+            let get_platform_ids = *lib
+                .get::<unsafe extern "C" fn(u32, *mut ClHandle, *mut u32) -> ClInt>(
+                    b"clGetPlatformIDs\0",
+                )
+                .map_err(|_| missing("clGetPlatformIDs"))?;
+            let get_device_ids = *lib
+                .get::<unsafe extern "C" fn(ClHandle, u64, u32, *mut ClHandle, *mut u32) -> ClInt>(
+                    b"clGetDeviceIDs\0",
+                )
+                .map_err(|_| missing("clGetDeviceIDs"))?;
+            let get_device_info = *lib
+                .get::<unsafe extern "C" fn(ClHandle, u32, usize, *mut c_void, *mut usize) -> ClInt>(
+                    b"clGetDeviceInfo\0",
+                )
+                .map_err(|_| missing("clGetDeviceInfo"))?;
+            let create_context = *lib
+                .get::<unsafe extern "C" fn(
+                    *const isize,
+                    u32,
+                    *const ClHandle,
+                    *const c_void,
+                    *mut c_void,
+                    *mut ClInt,
+                ) -> ClHandle>(b"clCreateContext\0")
+                .map_err(|_| missing("clCreateContext"))?;
+            let create_queue = *lib
+                .get::<unsafe extern "C" fn(ClHandle, ClHandle, u64, *mut ClInt) -> ClHandle>(
+                    b"clCreateCommandQueue\0",
+                )
+                .map_err(|_| missing("clCreateCommandQueue"))?;
+            let create_program = *lib
+                .get::<unsafe extern "C" fn(
+                    ClHandle,
+                    u32,
+                    *const *const c_char,
+                    *const usize,
+                    *mut ClInt,
+                ) -> ClHandle>(b"clCreateProgramWithSource\0")
+                .map_err(|_| missing("clCreateProgramWithSource"))?;
+            let build_program = *lib
+                .get::<unsafe extern "C" fn(
+                    ClHandle,
+                    u32,
+                    *const ClHandle,
+                    *const c_char,
+                    *const c_void,
+                    *mut c_void,
+                ) -> ClInt>(b"clBuildProgram\0")
+                .map_err(|_| missing("clBuildProgram"))?;
+            let create_kernel = *lib
+                .get::<unsafe extern "C" fn(ClHandle, *const c_char, *mut ClInt) -> ClHandle>(
+                    b"clCreateKernel\0",
+                )
+                .map_err(|_| missing("clCreateKernel"))?;
+            let create_buffer = *lib
+                .get::<unsafe extern "C" fn(ClHandle, u64, usize, *mut c_void, *mut ClInt) -> ClHandle>(
+                    b"clCreateBuffer\0",
+                )
+                .map_err(|_| missing("clCreateBuffer"))?;
+            let set_kernel_arg = *lib
+                .get::<unsafe extern "C" fn(ClHandle, u32, usize, *const c_void) -> ClInt>(
+                    b"clSetKernelArg\0",
+                )
+                .map_err(|_| missing("clSetKernelArg"))?;
+            let enqueue_nd_range = *lib
+                .get(b"clEnqueueNDRangeKernel\0")
+                .map_err(|_| missing("clEnqueueNDRangeKernel"))?;
+            let finish = *lib.get(b"clFinish\0").map_err(|_| missing("clFinish"))?;
+            let mut release = Vec::new();
+            for sym in [
+                &b"clReleaseMemObject\0"[..],
+                b"clReleaseKernel\0",
+                b"clReleaseProgram\0",
+                b"clReleaseCommandQueue\0",
+                b"clReleaseContext\0",
+            ] {
+                release.push(*lib.get(sym).map_err(|_| missing("clRelease*"))?);
+            }
+            let release: [unsafe extern "C" fn(ClHandle) -> ClInt; 5] =
+                release.try_into().map_err(|_| missing("clRelease*"))?;
+            let [release_mem, release_kernel, release_program, release_queue, release_context] =
+                release;
+
+            let check = |what: &str, rc: ClInt| {
+                if rc == CL_SUCCESS {
+                    Ok(())
+                } else {
+                    Err(format!("{} failed (cl error {})", what, rc))
+                }
+            };
+            let mut n = 0u32;
+            check("clGetPlatformIDs", get_platform_ids(0, std::ptr::null_mut(), &mut n))?;
+            let mut platforms = vec![std::ptr::null_mut(); n as usize];
+            check(
+                "clGetPlatformIDs",
+                get_platform_ids(n, platforms.as_mut_ptr(), std::ptr::null_mut()),
+            )?;
+            let device = platforms
+                .iter()
+                .find_map(|&p| {
+                    let mut dev = std::ptr::null_mut();
+                    let rc = get_device_ids(p, CL_DEVICE_TYPE_GPU, 1, &mut dev, std::ptr::null_mut());
+                    (rc == CL_SUCCESS && !dev.is_null()).then_some(dev)
+                })
+                .ok_or_else(|| "no OpenCL GPU device".to_string())?;
+            let mut name = [0u8; 256];
+            get_device_info(
+                device,
+                CL_DEVICE_NAME,
+                name.len(),
+                name.as_mut_ptr() as *mut c_void,
+                std::ptr::null_mut(),
+            );
+
+            let mut err = CL_SUCCESS;
+            let context = create_context(
+                std::ptr::null(),
+                1,
+                &device,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+                &mut err,
+            );
+            check("clCreateContext", err)?;
+            let mut cl = Self {
+                device_name: c_name(&name),
+                context,
+                queue: std::ptr::null_mut(),
+                program: std::ptr::null_mut(),
+                kernel: std::ptr::null_mut(),
+                x: std::ptr::null_mut(),
+                y: std::ptr::null_mut(),
+                enqueue_nd_range,
+                finish,
+                release: [release_mem, release_kernel, release_program, release_queue, release_context],
+                _lib: lib,
+            };
+            // `cl` releases whatever was created if a later step fails.
+            cl.queue = create_queue(context, device, 0, &mut err);
+            check("clCreateCommandQueue", err)?;
+            let src = SAXPY_CL.as_ptr() as *const c_char;
+            let len = SAXPY_CL.len();
+            cl.program = create_program(context, 1, &src, &len, &mut err);
+            check("clCreateProgramWithSource", err)?;
+            check(
+                "clBuildProgram",
+                build_program(
+                    cl.program,
+                    1,
+                    &device,
+                    std::ptr::null(),
+                    std::ptr::null(),
+                    std::ptr::null_mut(),
+                ),
+            )?;
+            cl.kernel = create_kernel(cl.program, c"saxpy_loop".as_ptr(), &mut err);
+            check("clCreateKernel", err)?;
+            let bytes = ELEMENTS * std::mem::size_of::<f32>();
+            let (mut x_host, mut y_host) = host_inputs();
+            let flags = CL_MEM_READ_WRITE | CL_MEM_COPY_HOST_PTR;
+            cl.x = create_buffer(context, flags, bytes, x_host.as_mut_ptr() as *mut c_void, &mut err);
+            check("clCreateBuffer", err)?;
+            cl.y = create_buffer(context, flags, bytes, y_host.as_mut_ptr() as *mut c_void, &mut err);
+            check("clCreateBuffer", err)?;
+            let (a, n, iters) = (ALPHA, ELEMENTS as u32, ITERS);
+            let args: [(usize, *const c_void); 5] = [
+                (size_of::<ClHandle>(), &cl.x as *const _ as *const c_void),
+                (size_of::<ClHandle>(), &cl.y as *const _ as *const c_void),
+                (size_of::<f32>(), &a as *const _ as *const c_void),
+                (size_of::<u32>(), &n as *const _ as *const c_void),
+                (size_of::<u32>(), &iters as *const _ as *const c_void),
+            ];
+            for (i, (size, value)) in args.into_iter().enumerate() {
+                check("clSetKernelArg", set_kernel_arg(cl.kernel, i as u32, size, value))?;
+            }
+            Ok(cl)
+        }
+    }
+}
+
+impl Kernel for OpenCl {
+    fn backend(&self) -> &'static str {
+        "opencl"
+    }
+
+    fn flops_per_launch(&self) -> u64 {
+        FLOPS_PER_LAUNCH
+    }
+
+    fn device(&self) -> String {
+        self.device_name.clone()
+    }
+
+    fn launch(&mut self) -> Result<(), String> {
+        let global = ELEMENTS.next_multiple_of(BLOCK as usize);
+        let local = BLOCK as usize;
+        let rc = unsafe {
+            (self.enqueue_nd_range)(
+                self.queue,
+                self.kernel,
+                1,
+                std::ptr::null(),
+                &global,
+                &local,
+                0,
+                std::ptr::null(),
+                std::ptr::null_mut(),
+            )
+        };
+        if rc != CL_SUCCESS {
+            return Err(format!("clEnqueueNDRangeKernel failed (cl error {})", rc));
+        }
+        match unsafe { (self.finish)(self.queue) } {
+            CL_SUCCESS => Ok(()),
+            rc => Err(format!("clFinish failed (cl error {})", rc)),
+        }
+    }
+}
+
+impl Drop for OpenCl {
+    fn drop(&mut self) {
+        let [release_mem, release_kernel, release_program, release_queue, release_context] =
+            self.release;
+        unsafe {
+            for (handle, release) in [
+                (self.x, release_mem),
+                (self.y, release_mem),
+                (self.kernel, release_kernel),
+                (self.program, release_program),
+                (self.queue, release_queue),
+                (self.context, release_context),
+            ] {
+                if !handle.is_null() {
+                    release(handle);
+                }
+            }
+        }
+    }
+}
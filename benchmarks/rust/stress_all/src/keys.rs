@@ -0,0 +1,123 @@
+use std::io::{self, IsTerminal};
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Termios {
+    c_iflag: u32,
+    c_oflag: u32,
+    c_cflag: u32,
+    c_lflag: u32,
+    c_line: u8,
+    c_cc: [u8; 32],
+    c_ispeed: u32,
+    c_ospeed: u32,
+}
+
+extern "C" {
+    fn tcgetattr(fd: i32, termios: *mut Termios) -> i32;
+    fn tcsetattr(fd: i32, action: i32, termios: *const Termios) -> i32;
+    fn fcntl(fd: i32, cmd: i32, ...) -> i32;
+    fn read(fd: i32, buf: *mut u8, count: usize) -> isize;
+}
+
+const STDIN: i32 = 0;
+const TCSANOW: i32 = 0;
+const ICANON: u32 = 0o2;
+const ECHO: u32 = 0o10;
+const VTIME: usize = 5;
+const VMIN: usize = 6;
+const F_GETFL: i32 = 3;
+const F_SETFL: i32 = 4;
+const O_NONBLOCK: i32 = 0o4000;
+
+/// Status line listing the TUI key bindings.
+pub const HELP: &str = "Keys: p pause  r resume  +/- CPU worker  m/M mem target +/-10%  d disk on/off";
+
+/// One TUI keystroke.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Pause,
+    Resume,
+    AddCpu,
+    RemoveCpu,
+    MemUp,
+    MemDown,
+    ToggleDisk,
+}
+
+impl Key {
+    fn from_byte(b: u8) -> Option<Self> {
+        match b {
+            b'p' => Some(Key::Pause),
+            b'r' => Some(Key::Resume),
+            b'+' | b'=' => Some(Key::AddCpu),
+            b'-' => Some(Key::RemoveCpu),
+            b'm' => Some(Key::MemUp),
+            b'M' => Some(Key::MemDown),
+            b'd' => Some(Key::ToggleDisk),
+            _ => None,
+        }
+    }
+}
+
+/// Stdin in non-canonical, no-echo, non-blocking mode while the TUI runs, so
+/// single keystrokes arrive without Enter and reading never stalls the render
+/// loop. Ctrl+C still raises SIGINT. The terminal is restored on drop.
+pub struct KeyReader {
+    saved: Termios,
+    saved_flags: i32,
+}
+
+impl KeyReader {
+    /// `None` if stdin is not a terminal or cannot be switched.
+    pub fn enable() -> Option<Self> {
+        if !io::stdin().is_terminal() {
+            return None;
+        }
+        unsafe {
+            let mut saved = std::mem::zeroed::<Termios>();
+            if tcgetattr(STDIN, &mut saved) != 0 {
+                return None;
+            }
+            let saved_flags = fcntl(STDIN, F_GETFL);
+            if saved_flags < 0 {
+                return None;
+            }
+            let mut raw = saved;
+            raw.c_lflag &= !(ICANON | ECHO);
+            raw.c_cc[VMIN] = 0;
+            raw.c_cc[VTIME] = 0;
+            if tcsetattr(STDIN, TCSANOW, &raw) != 0 {
+                return None;
+            }
+            if fcntl(STDIN, F_SETFL, saved_flags | O_NONBLOCK) < 0 {
+                tcsetattr(STDIN, TCSANOW, &saved);
+                return None;
+            }
+            Some(Self { saved, saved_flags })
+        }
+    }
+
+    /// Keys pressed since the last call, oldest first. Unbound bytes are
+    /// dropped.
+    pub fn poll(&mut self) -> Vec<Key> {
+        let mut buf = [0u8; 64];
+        let mut keys = Vec::new();
+        loop {
+            let n = unsafe { read(STDIN, buf.as_mut_ptr(), buf.len()) };
+            if n <= 0 {
+                return keys;
+            }
+            keys.extend(buf[..n as usize].iter().filter_map(|&b| Key::from_byte(b)));
+        }
+    }
+}
+
+impl Drop for KeyReader {
+    fn drop(&mut self) {
+        unsafe {
+            fcntl(STDIN, F_SETFL, self.saved_flags);
+            tcsetattr(STDIN, TCSANOW, &self.saved);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use hdrhistogram::serialization::interval_log::{IntervalLogWriterBuilder, Tag};
+use hdrhistogram::serialization::V2DeflateSerializer;
+use hdrhistogram::Histogram;
+use stress_lib::WorkerHandle;
+
+/// `--latency-histogram-export`: per-subsystem latency histograms merged
+/// from every worker that ran, including ones retired mid-run.
+pub struct LatencyExport {
+    start: SystemTime,
+    /// (tag, histogram) in first-seen order.
+    histograms: Vec<(&'static str, Histogram<u64>)>,
+}
+
+impl LatencyExport {
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            start,
+            histograms: Vec::new(),
+        }
+    }
+
+    /// Merges the histograms of `handles` under `tag`. Call before the
+    /// handles are joined.
+    pub fn absorb<T>(&mut self, tag: &'static str, handles: &[WorkerHandle<T>]) {
+        for h in handles.iter().filter_map(WorkerHandle::latency_histogram) {
+            match self.histograms.iter_mut().find(|(t, _)| *t == tag) {
+                Some((_, total)) => {
+                    if let Err(e) = total.add(&h) {
+                        log::warn!("Latency histogram {}: cannot merge: {:?}", tag, e);
+                    }
+                }
+                None => self.histograms.push((tag, h)),
+            }
+        }
+    }
+
+    /// Writes one interval per subsystem, covering the whole run, as an
+    /// HdrHistogram interval log (V2 compressed), readable by `hdrlog` and
+    /// HistogramLogProcessor. Values are nanoseconds.
+    pub fn write(&self, path: &Path, duration: Duration) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let mut serializer = V2DeflateSerializer::new();
+        let mut log = IntervalLogWriterBuilder::new()
+            .add_comment("stress_all per-subsystem op latency, in nanoseconds")
+            .with_start_time(self.start)
+            .with_base_time(self.start)
+            .with_max_value_divisor(1e6)
+            .begin_log_with(&mut out, &mut serializer)?;
+        for (tag, h) in &self.histograms {
+            log.write_histogram(h, Duration::ZERO, duration, Tag::new(tag))
+                .map_err(|e| io::Error::other(e.to_string()))?;
+        }
+        drop(log);
+        out.flush()
+    }
+
+    /// `tag count p50/p99/max` per subsystem, in microseconds.
+    pub fn summary(&self) -> Vec<String> {
+        self.histograms
+            .iter()
+            .map(|(tag, h)| {
+                format!(
+                    "{} {} ops  p50 {:.1}us  p99 {:.1}us  max {:.1}us",
+                    tag,
+                    h.len(),
+                    h.value_at_quantile(0.5) as f64 / 1e3,
+                    h.value_at_quantile(0.99) as f64 / 1e3,
+                    h.max() as f64 / 1e3
+                )
+            })
+            .collect()
+    }
+}
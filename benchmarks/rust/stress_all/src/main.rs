@@ -1,22 +1,92 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
 use std::env;
 use std::fs::{File, OpenOptions};
-use std::hash::{Hash, Hasher};
-use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
-use std::path::PathBuf;
-use std::collections::hash_map::DefaultHasher;
+use std::io::{self, IsTerminal, Read, Write};
+use std::net::TcpListener;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
+    atomic::{AtomicBool, Ordering},
     Arc,
 };
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use clap::{CommandFactory, Parser};
+use stress_lib::{
+    block_device_size, cache_levels, latency_sweep, seed_from_time, stride_test, tcp_accept_server, CacheSizes, CpuWorker, CPU_MIX_DEFAULT, CpuWorkerStats,
+    CpuWorkload, CpuWorkloadControl, CpuYieldStrategy, DiskAccessPattern, DiskCompress, DiskIoType, DiskTrace, DiskWorker, DiskWritePattern, GcPattern, IoPattern,
+    LatencyWorker, MatrixWorker, MemWorker, NetWorker, SimdWidth, WorkerHandle, MEMCPY_DEFAULT_BYTES,
+};
+
+mod baseline;
+mod cli;
+mod cpu_bench;
+mod cpu_topology;
+mod gpu_compute;
+#[cfg(feature = "gpu_compute")]
+mod gpu_kernels;
+mod keys;
+#[cfg(feature = "latency_histogram")]
+mod latency_export;
+mod mem_prefetch;
+#[cfg(feature = "nvml")]
+mod nvml;
+mod phase_file;
+#[cfg(feature = "otlp")]
+mod otlp_out;
+#[cfg(feature = "proto")]
+mod proto_out;
+mod perf;
+mod rapl;
+mod startup_report;
+mod stress_script;
+mod summary;
+mod tui_layout;
+#[cfg(feature = "work_stealing")]
+mod work_stealing;
+
+use cli::Cli;
+use keys::{Key, KeyReader};
+#[cfg(feature = "latency_histogram")]
+use latency_export::LatencyExport;
+use phase_file::PhaseSpec;
+use summary::RunSummary;
+use tui_layout::{SampleState, TuiSection};
+
+/// Stands in for the export when histograms are not built in.
+#[cfg(not(feature = "latency_histogram"))]
+type LatencyExport = ();
+
 static GLOBAL_STOP: AtomicBool = AtomicBool::new(false);
 
+const MIN_SAMPLE_MS: u64 = 10;
+/// Default chart redraw interval: 4 frames per second.
+const GRAPH_REFRESH_MS: u64 = 250;
+const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+/// Thermal throttle events/s that, sustained for `THROTTLE_WARN_SAMPLES`
+/// samples, raise the throttle warning.
+const THROTTLE_WARN_RATE: f64 = 1.0;
+const THROTTLE_WARN_SAMPLES: usize = 5;
+/// Buffer swept by `--mem-stride-test`; well past last-level cache sizes.
+const STRIDE_TEST_MB: u64 = 256;
+/// List spanned by `--mem-latency-test`, so hops mostly miss in cache.
+const LATENCY_TEST_MB: u64 = 256;
+/// Time `--mem-latency-sweep` spends at each working-set size.
+const LATENCY_SWEEP_S: u64 = 1;
+/// Width of the `--mem-latency-sweep` bar chart.
+const LATENCY_CHART_WIDTH: usize = 40;
+/// Timed writes a `--disk-sla-abort-pct` window must hold before it is
+/// judged; samples with fewer carry over into the next one.
+const SLA_WINDOW_MIN_OPS: u64 = 100;
+
 type SigHandler = extern "C" fn(i32);
 
 extern "C" {
     fn signal(sig: i32, handler: SigHandler) -> SigHandler;
+    fn geteuid() -> u32;
+    fn sync();
 }
 
 extern "C" fn handle_sig(_sig: i32) {
@@ -33,179 +103,87 @@ fn install_signal_handlers() {
     }
 }
 
-struct Args {
+#[derive(Clone, Copy, Debug)]
+struct CpuPhase {
+    workload: CpuWorkload,
     duration_s: u64,
-    cpu_workers: usize,
-    mem_mb: u64,
-    disk_gb: f64,
-    temp_dir: Option<PathBuf>,
-    enable_disk: bool,
-    enable_mem: bool,
-    sample_ms: u64,
-    csv_path: Option<PathBuf>,
-    graph_width: usize,
-    graph_height: usize,
-    gpu_status_path: Option<PathBuf>,
-}
-
-fn print_usage() {
-    eprintln!(
-        "Rust Stress All (CPU/RAM/Disk)\n\
-Usage: stress_all [options]\n\n\
-Options:\n\
-  --duration <sec|Xm>   Run for N seconds or minutes (0 = until Ctrl+C).\n\
-  --cpu-workers <N>     CPU worker threads (default: all cores).\n\
-  --mem-mb <MB>         Memory target in MB (default: ~60% of RAM, capped).\n\
-  --disk-gb <GB>        Disk file size in GB (default: 1.0).\n\
-  --temp-dir <path>     Directory for disk stress file (default: system temp).\n\
-  --no-disk             Disable disk stress.\n\
-  --no-mem              Disable memory stress.\n\
-  --sample-ms <ms>      Sample interval in ms (default: 1000).\n\
-  --csv <path>          Append samples to CSV file.\n\
-  --graph-width <N>     Graph width (default: terminal width - 25).\n\
-  --graph-height <N>    Graph height (default: 6).\n\
-  --gpu-status <path>   Read GPU stats from a status file (nvidia-smi output).\n\
-  -h, --help            Show this help.\n"
-    );
-}
-
-fn parse_u64(s: &str, name: &str) -> u64 {
-    s.parse::<u64>().unwrap_or_else(|_| {
-        eprintln!("Invalid {}: {}", name, s);
-        std::process::exit(2);
-    })
 }
 
-fn parse_f64(s: &str, name: &str) -> f64 {
-    s.parse::<f64>().unwrap_or_else(|_| {
-        eprintln!("Invalid {}: {}", name, s);
-        std::process::exit(2);
-    })
+/// What each TUI chart shows (`--chart-mode`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ChartMode {
+    /// Time series of the window.
+    Ts,
+    /// Distribution of the window's samples.
+    Hist,
+    /// Time series with the histogram below it.
+    Both,
 }
 
-fn parse_duration(s: &str) -> u64 {
-    if let Some(stripped) = s.strip_suffix('m') {
-        parse_u64(stripped, "duration") * 60
-    } else if let Some(stripped) = s.strip_suffix('s') {
-        parse_u64(stripped, "duration")
-    } else {
-        parse_u64(s, "duration")
+impl ChartMode {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ts" => Some(ChartMode::Ts),
+            "hist" => Some(ChartMode::Hist),
+            "both" => Some(ChartMode::Both),
+            _ => None,
+        }
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum GpuBackend {
+    File,
+    Nvml,
+}
+
+#[derive(Debug)]
+struct Args {
+    /// Integer/float/memory percentages for the synthetic-mix workload.
+    cpu_mix: Option<[u32; 3]>,
+    cpu_matrix_n: usize,
+    cpu_phases: Vec<CpuPhase>,
+    cpu_ops_target: Option<f64>,
+    thread_stack_size: Option<usize>,
+    cpu_yield_strategy: CpuYieldStrategy,
+    /// `--disk-stall-interval` ops and stall length; `None` when disabled.
+    disk_stall: Option<(u64, Duration)>,
+    disk_latency_sla: Option<Duration>,
+    disk_cache_flush_interval: Option<Duration>,
+    disk_io_throttle: Option<u64>,
+    disk_write_pattern: Option<DiskWritePattern>,
+    enable_disk: bool,
+    enable_mem: bool,
+    mem_gc_pattern: Option<GcPattern>,
+    mem_bandwidth_target: Option<f64>,
+    /// Bit flips/s across all memory workers (`--mem-error-injection`).
+    mem_error_injection: Option<f64>,
+    /// Histogram significant digits when exporting latency histograms.
+    latency_sigfig: Option<u8>,
+    regression_threshold_pct: f64,
+    graph_refresh_ms: u64,
+    graph_width: usize,
+    graph_height: usize,
+    /// Explicit `--tui-layout`; `None` uses `tui_layout::DEFAULT_LAYOUT`.
+    tui_layout: Option<Vec<TuiSection>>,
+    mem_worker_count: usize,
+    mem_worker_bind_cpu: Option<Vec<usize>>,
+    stride_test_duration_s: u64,
+    stop_sustain_samples: usize,
+    /// The validated command line. Flags used as given are read from here;
+    /// the fields above hold the ones `parse_args` converts or resolves.
+    cli: Cli,
+}
+
 fn parse_args() -> Args {
-    let mut duration_s = 0u64;
-    let mut cpu_workers = 0usize;
-    let mut mem_mb = 0u64;
-    let mut disk_gb = 1.0f64;
-    let mut temp_dir = None;
-    let mut enable_disk = true;
-    let mut enable_mem = true;
-    let mut sample_ms = 1000u64;
-    let mut csv_path = None;
-    let mut graph_width = 0usize;
-    let mut graph_height = 8usize;
-    let mut gpu_status_path = None;
-
-    let mut it = env::args().skip(1);
-    while let Some(arg) = it.next() {
-        match arg.as_str() {
-            "--duration" => {
-                if let Some(v) = it.next() {
-                    duration_s = parse_duration(&v);
-                } else {
-                    eprintln!("--duration requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--cpu-workers" => {
-                if let Some(v) = it.next() {
-                    cpu_workers = parse_u64(&v, "cpu-workers") as usize;
-                } else {
-                    eprintln!("--cpu-workers requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--mem-mb" => {
-                if let Some(v) = it.next() {
-                    mem_mb = parse_u64(&v, "mem-mb");
-                } else {
-                    eprintln!("--mem-mb requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--disk-gb" => {
-                if let Some(v) = it.next() {
-                    disk_gb = parse_f64(&v, "disk-gb");
-                } else {
-                    eprintln!("--disk-gb requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--temp-dir" => {
-                if let Some(v) = it.next() {
-                    temp_dir = Some(PathBuf::from(v));
-                } else {
-                    eprintln!("--temp-dir requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--no-disk" => enable_disk = false,
-            "--no-mem" => enable_mem = false,
-            "--sample-ms" => {
-                if let Some(v) = it.next() {
-                    sample_ms = parse_u64(&v, "sample-ms");
-                } else {
-                    eprintln!("--sample-ms requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--csv" => {
-                if let Some(v) = it.next() {
-                    csv_path = Some(PathBuf::from(v));
-                } else {
-                    eprintln!("--csv requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--graph-width" => {
-                if let Some(v) = it.next() {
-                    graph_width = parse_u64(&v, "graph-width") as usize;
-                } else {
-                    eprintln!("--graph-width requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--graph-height" => {
-                if let Some(v) = it.next() {
-                    graph_height = parse_u64(&v, "graph-height") as usize;
-                } else {
-                    eprintln!("--graph-height requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "--gpu-status" => {
-                if let Some(v) = it.next() {
-                    gpu_status_path = Some(PathBuf::from(v));
-                } else {
-                    eprintln!("--gpu-status requires a value");
-                    std::process::exit(2);
-                }
-            }
-            "-h" | "--help" => {
-                print_usage();
-                std::process::exit(0);
-            }
-            _ => {
-                eprintln!("Unknown option: {}", arg);
-                print_usage();
-                std::process::exit(2);
-            }
-        }
+    let mut cli = Cli::parse();
+    if let Some(shell) = cli.completions {
+        clap_complete::generate(shell, &mut Cli::command(), "stress_all", &mut io::stdout());
+        std::process::exit(0);
     }
 
-    let graph_width = if graph_width > 0 {
-        graph_width
+    let graph_width = if cli.graph_width > 0 {
+        cli.graph_width
     } else {
         let cols = env::var("COLUMNS")
             .ok()
@@ -214,21 +192,152 @@ fn parse_args() -> Args {
         let w = if cols > 25 { cols - 25 } else { 20 };
         w.clamp(20, 120)
     };
-    let graph_height = graph_height.clamp(4, 12);
+    let graph_height = cli.graph_height.clamp(4, 12);
+
+    if let Some(bytes) = cli.thread_stack_size.filter(|b| *b < 65536) {
+        eprintln!(
+            "--thread-stack-size {} is below 64KiB; workers may overflow",
+            bytes
+        );
+    }
+    let disk_write_pattern = match (cli.disk_write_pattern.clone(), cli.disk_pattern_hex.as_ref()) {
+        (Some(DiskWritePattern::Pattern(_)), Some(hex)) => Some(DiskWritePattern::Pattern(hex.0.clone())),
+        (_, Some(_)) => {
+            eprintln!("--disk-pattern-hex requires --disk-write-pattern pattern");
+            std::process::exit(2);
+        }
+        (pattern, None) => pattern,
+    };
+    if cli.cpu_busy_wait && cli.cpu_yield_after_ops.is_some() {
+        eprintln!("--cpu-busy-wait and --cpu-yield-after-ops are mutually exclusive");
+        std::process::exit(2);
+    }
+    if cli.cpu_worker_yield_strategy != CpuYieldStrategy::Sleep
+        && (cli.cpu_busy_wait || cli.cpu_yield_after_ops.is_some())
+    {
+        eprintln!(
+            "--cpu-worker-yield-strategy cannot be combined with --cpu-busy-wait or --cpu-yield-after-ops"
+        );
+        std::process::exit(2);
+    }
+    if cli.cpu_work_stealing && !cfg!(feature = "work_stealing") {
+        eprintln!("--cpu-work-stealing requires building with the `work_stealing` feature");
+        std::process::exit(2);
+    }
+    let mix = [cli.cpu_mix_integer, cli.cpu_mix_float, cli.cpu_mix_memory];
+    let cpu_mix = mix.iter().any(Option::is_some).then(|| mix.map(|p| p.unwrap_or(0)));
+    if let Some([int, fp, mem]) = cpu_mix.filter(|m| m.iter().sum::<u32>() != 100) {
+        eprintln!(
+            "--cpu-mix-integer/--cpu-mix-float/--cpu-mix-memory must sum to 100 (got {}+{}+{})",
+            int, fp, mem
+        );
+        std::process::exit(2);
+    }
+    let mut enable_mem = !cli.no_mem;
+    if cli.cpu_mem_ratio.is_some_and(|(_, mem_w)| mem_w == 0) && cli.mem_mb == 0 {
+        enable_mem = false;
+    }
+    if cli.mem_numa_interleave && cli.mem_numa_node.is_some() {
+        eprintln!("--mem-numa-interleave and --mem-numa-node are mutually exclusive");
+        std::process::exit(2);
+    }
+    if let Some(cpus) = cli.mem_worker_bind_cpu.as_ref() {
+        if cpus.0.len() as u64 != cli.mem_worker_count {
+            eprintln!(
+                "--mem-worker-bind-cpu lists {} CPUs but --mem-worker-count is {}",
+                cpus.0.len(),
+                cli.mem_worker_count
+            );
+            std::process::exit(2);
+        }
+    }
+
+    if let Some(dev) = cli.disk_raw_device.as_ref() {
+        if cli.temp_dir.is_some() {
+            eprintln!("--disk-raw-device and --temp-dir are mutually exclusive");
+            std::process::exit(2);
+        }
+        if cli.disk_trace.is_some() {
+            eprintln!("--disk-raw-device cannot replay --disk-trace (O_DIRECT needs aligned ops)");
+            std::process::exit(2);
+        }
+        if cli.disk_compress != DiskCompress::None {
+            eprintln!("--disk-raw-device cannot use --disk-compress (O_DIRECT needs aligned writes)");
+            std::process::exit(2);
+        }
+        if !cli.destroy_confirmed {
+            eprintln!(
+                "WARNING: --disk-raw-device overwrites {} and destroys all data on it.\n\
+                 Re-run with --i-understand-this-destroys-data to proceed.",
+                dev.display()
+            );
+            std::process::exit(2);
+        }
+    }
+    if cli.disk_aio_depth > 0 && !DiskWorker::aio_supported() {
+        eprintln!("--disk-aio-depth needs POSIX AIO on glibc Linux (x86_64 or aarch64)");
+        std::process::exit(2);
+    }
+    if cli.disk_read_file.is_some() && cli.disk_io_type != DiskIoType::Read {
+        eprintln!("--disk-read-file requires --disk-io-type read");
+        std::process::exit(2);
+    }
+    if cli.disk_read_file.is_some() && cli.disk_raw_device.is_some() {
+        eprintln!("--disk-read-file and --disk-raw-device are mutually exclusive");
+        std::process::exit(2);
+    }
+
+    if let Some(Err(e)) = cli.cpu_energy_cap.map(rapl::PowerCap::check) {
+        eprintln!("Invalid --cpu-energy-cap: {}", e);
+        std::process::exit(2);
+    }
+    cli.disk_read_pct = cli.disk_read_pct.min(100);
+
+    let defaults = GcPattern::default();
+    let gc_pattern = GcPattern {
+        young_size_kb: cli.mem_young_size.unwrap_or(defaults.young_size_kb),
+        young_count: cli.mem_young_count.unwrap_or(defaults.young_count),
+        old_size_mb: cli.mem_old_size.unwrap_or(defaults.old_size_mb),
+    };
 
     Args {
-        duration_s,
-        cpu_workers,
-        mem_mb,
-        disk_gb,
-        temp_dir,
-        enable_disk,
+        cpu_mix,
+        cpu_matrix_n: cli.cpu_matrix_n as usize,
+        cpu_phases: cli.cpu_phases.as_ref().map(|p| p.0.clone()).unwrap_or_default(),
+        cpu_ops_target: cli.cpu_ops_target,
+        thread_stack_size: cli.thread_stack_size.map(|b| b as usize),
+        cpu_yield_strategy: if cli.cpu_busy_wait {
+            CpuYieldStrategy::None
+        } else {
+            cli.cpu_worker_yield_strategy
+        },
+        disk_cache_flush_interval: (cli.disk_cache_flush_interval > 0)
+            .then(|| Duration::from_secs(cli.disk_cache_flush_interval)),
+        disk_stall: (cli.disk_stall_interval > 0).then(|| {
+            (cli.disk_stall_interval, Duration::from_millis(cli.disk_stall_duration_ms))
+        }),
+        disk_latency_sla: cli.disk_latency_sla.map(Duration::from_micros),
+        disk_io_throttle: (cli.disk_io_throttle > 0).then_some(cli.disk_io_throttle),
+        disk_write_pattern,
+        enable_disk: !cli.no_disk,
         enable_mem,
-        sample_ms,
-        csv_path,
+        mem_gc_pattern: cli.mem_gc_pattern.then_some(gc_pattern),
+        mem_bandwidth_target: cli.mem_bandwidth_target,
+        mem_error_injection: Some(cli.mem_error_injection).filter(|r| *r > 0.0),
+        latency_sigfig: cli
+            .latency_histogram_export
+            .is_some()
+            .then_some(cli.latency_histogram_buckets),
+        regression_threshold_pct: cli.regression_threshold_pct.max(0.0),
+        graph_refresh_ms: cli.tui_fps.map_or(cli.graph_refresh_ms, |fps| 1000 / fps),
         graph_width,
         graph_height,
-        gpu_status_path,
+        tui_layout: cli.tui_layout.as_ref().map(|l| l.0.clone()),
+        mem_worker_count: cli.mem_worker_count as usize,
+        mem_worker_bind_cpu: cli.mem_worker_bind_cpu.as_ref().map(|l| l.0.clone()),
+        stride_test_duration_s: cli.stride_test_duration_s.max(1),
+        stop_sustain_samples: cli.stop_sustain_samples.max(1),
+        cli,
     }
 }
 
@@ -284,11 +393,43 @@ fn read_mem_used_kb() -> Option<(u64, u64)> {
     Some((total, used))
 }
 
+fn read_vmstat_counters(keys: &[&str]) -> Option<Vec<u64>> {
+    let mut buf = String::new();
+    File::open("/proc/vmstat").ok()?.read_to_string(&mut buf).ok()?;
+    let mut values = vec![None; keys.len()];
+    for line in buf.lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(name), Some(value)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        if let Some(i) = keys.iter().position(|k| *k == name) {
+            values[i] = value.parse::<u64>().ok();
+        }
+    }
+    values.into_iter().collect()
+}
+
+fn read_numa_stats() -> Option<(u64, u64, u64)> {
+    let v = read_vmstat_counters(&["numa_pages_migrated", "numa_hit", "numa_miss"])?;
+    Some((v[0], v[1], v[2]))
+}
+
+/// Cumulative (minor, major) page faults. `pgfault` counts both kinds.
+fn read_vmstat_pgfault() -> Option<(u64, u64)> {
+    let v = read_vmstat_counters(&["pgfault", "pgmajfault"])?;
+    Some((v[0].saturating_sub(v[1]), v[1]))
+}
+
 fn read_mem_total_mb() -> u64 {
     read_mem_used_kb().map(|(t, _)| t / 1024).unwrap_or(0)
 }
 
 fn read_cpu_freq_khz() -> Option<u64> {
+    read_cpufreq_avg_khz("scaling_cur_freq")
+}
+
+/// Averages `cpufreq/<attr>` over all CPUs that expose it.
+fn read_cpufreq_avg_khz(attr: &str) -> Option<u64> {
     let mut total = 0u64;
     let mut count = 0u64;
     let entries = std::fs::read_dir("/sys/devices/system/cpu").ok()?;
@@ -298,7 +439,7 @@ fn read_cpu_freq_khz() -> Option<u64> {
         if !name.starts_with("cpu") || !name[3..].chars().all(|c| c.is_ascii_digit()) {
             continue;
         }
-        let path = entry.path().join("cpufreq/scaling_cur_freq");
+        let path = entry.path().join("cpufreq").join(attr);
         if let Ok(mut f) = File::open(path) {
             let mut s = String::new();
             if f.read_to_string(&mut s).is_ok() {
@@ -309,41 +450,90 @@ fn read_cpu_freq_khz() -> Option<u64> {
             }
         }
     }
-    if count == 0 {
-        None
-    } else {
-        Some(total / count)
-    }
+    total.checked_div(count)
 }
 
-fn read_temp_c() -> Option<f64> {
-    let entries = std::fs::read_dir("/sys/class/thermal").ok()?;
-    let mut max_milli = 0i64;
-    let mut found = false;
+struct ThermalZone {
+    name: String,
+    type_: String,
+    temp_c: f64,
+}
+
+fn read_thermal_zones() -> Vec<ThermalZone> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/thermal") else {
+        return Vec::new();
+    };
+    let mut zones = Vec::new();
     for entry in entries.flatten() {
-        let name = entry.file_name();
-        let name = name.to_string_lossy();
+        let name = entry.file_name().to_string_lossy().into_owned();
         if !name.starts_with("thermal_zone") {
             continue;
         }
-        let path = entry.path().join("temp");
-        if let Ok(mut f) = File::open(path) {
-            let mut s = String::new();
-            if f.read_to_string(&mut s).is_ok() {
-                if let Ok(v) = s.trim().parse::<i64>() {
-                    if v > max_milli {
-                        max_milli = v;
-                    }
-                    found = true;
-                }
+        let Ok(temp) = std::fs::read_to_string(entry.path().join("temp")) else {
+            continue;
+        };
+        let Ok(milli) = temp.trim().parse::<i64>() else {
+            continue;
+        };
+        let type_ = std::fs::read_to_string(entry.path().join("type"))
+            .map(|s| s.trim().to_string())
+            .unwrap_or_else(|_| name.clone());
+        zones.push(ThermalZone {
+            name,
+            type_,
+            temp_c: milli as f64 / 1000.0,
+        });
+    }
+    zones.sort_by(|a, b| a.name.cmp(&b.name));
+    zones
+}
+
+/// The hottest thermal zone.
+fn read_temp_c() -> Option<(f64, String)> {
+    read_thermal_zones()
+        .into_iter()
+        .max_by(|a, b| a.temp_c.total_cmp(&b.temp_c))
+        .map(|z| (z.temp_c, z.type_))
+}
+
+/// Per-core temperatures from hwmon (`coretemp`'s `Core N` labels), indexed
+/// by core number; `None` where a core's input is missing or unreadable.
+/// Multi-socket machines list each package's cores in hwmon order.
+fn read_per_core_temp() -> Vec<Option<f64>> {
+    let Ok(entries) = std::fs::read_dir("/sys/class/hwmon") else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    dirs.sort();
+    let mut temps = Vec::new();
+    for dir in dirs {
+        let Ok(files) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut package: Vec<Option<f64>> = Vec::new();
+        for file in files.flatten() {
+            let name = file.file_name().to_string_lossy().into_owned();
+            let Some(sensor) = name.strip_suffix("_label") else {
+                continue;
+            };
+            let Ok(label) = std::fs::read_to_string(file.path()) else {
+                continue;
+            };
+            let core = label.trim().strip_prefix("Core ").and_then(|n| n.parse::<usize>().ok());
+            let Some(core) = core else {
+                continue;
+            };
+            let milli = std::fs::read_to_string(dir.join(format!("{}_input", sensor)))
+                .ok()
+                .and_then(|s| s.trim().parse::<i64>().ok());
+            if package.len() <= core {
+                package.resize(core + 1, None);
             }
+            package[core] = milli.map(|m| m as f64 / 1000.0);
         }
+        temps.extend(package);
     }
-    if !found {
-        None
-    } else {
-        Some(max_milli as f64 / 1000.0)
-    }
+    temps
 }
 
 fn read_loadavg() -> Option<(f64, f64, f64)> {
@@ -356,6 +546,206 @@ fn read_loadavg() -> Option<(f64, f64, f64)> {
     Some((one, five, fifteen))
 }
 
+/// Cumulative (hardware IRQs, softirqs) across all CPUs, from
+/// `/proc/interrupts` and `/proc/softirqs`.
+fn read_irq_stats() -> Option<(u64, u64)> {
+    let irqs = std::fs::read_to_string("/proc/interrupts").ok()?;
+    let softirqs = std::fs::read_to_string("/proc/softirqs").ok()?;
+    Some((sum_per_cpu_counts(&irqs), sum_per_cpu_counts(&softirqs)))
+}
+
+/// Thermal throttle events on cpu0's core since boot (Intel
+/// `thermal_throttle/core_throttle_count`).
+fn read_throttle_events() -> Option<u64> {
+    std::fs::read_to_string("/sys/devices/system/cpu/cpu0/thermal_throttle/core_throttle_count")
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// TCP sockets in `TIME_WAIT` (`tw` on the `TCP:` line of `/proc/net/sockstat`).
+fn read_sockstat_timewait() -> Option<u64> {
+    let text = std::fs::read_to_string("/proc/net/sockstat").ok()?;
+    let fields: Vec<&str> = text
+        .lines()
+        .find_map(|l| l.strip_prefix("TCP:"))?
+        .split_whitespace()
+        .collect();
+    fields
+        .chunks(2)
+        .find(|kv| kv[0] == "tw")
+        .and_then(|kv| kv.get(1)?.parse().ok())
+}
+
+/// Cumulative context switches since boot (`ctxt` in `/proc/stat`).
+fn read_context_switches() -> Option<u64> {
+    let text = std::fs::read_to_string("/proc/stat").ok()?;
+    text.lines()
+        .find_map(|l| l.strip_prefix("ctxt "))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+/// Block device (e.g. `sda`, `nvme0n1`) behind `path`: the device itself for
+/// a block special file, else the one holding its filesystem. Partitions
+/// resolve to their disk, which owns the request queue. `path` may not exist
+/// yet; its closest existing ancestor is used.
+fn block_device_name(path: &Path) -> Option<String> {
+    let meta = path.ancestors().find_map(|p| std::fs::metadata(p).ok())?;
+    let dev = if meta.file_type().is_block_device() {
+        meta.rdev()
+    } else {
+        meta.dev()
+    };
+    // Same split as glibc's major()/minor().
+    let major = ((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0xfff);
+    let minor = ((dev >> 12) & 0xffff_ff00) | (dev & 0xff);
+    let sys = std::fs::canonicalize(format!("/sys/dev/block/{}:{}", major, minor)).ok()?;
+    let disk = if sys.join("partition").exists() {
+        sys.parent()?
+    } else {
+        &sys
+    };
+    Some(disk.file_name()?.to_string_lossy().into_owned())
+}
+
+/// Active I/O scheduler of a block device: the bracketed entry of
+/// `/sys/block/<device>/queue/scheduler` (e.g. `mq-deadline`, `none`, `bfq`).
+fn read_io_scheduler(device: &str) -> Option<String> {
+    let text = std::fs::read_to_string(format!("/sys/block/{}/queue/scheduler", device)).ok()?;
+    let active = text.split_whitespace().find(|s| s.starts_with('['))?;
+    Some(active.trim_matches(|c| c == '[' || c == ']').to_string())
+}
+
+/// Request queue depth of a block device (`queue/nr_requests`).
+fn read_nr_requests(device: &str) -> Option<u64> {
+    std::fs::read_to_string(format!("/sys/block/{}/queue/nr_requests", device))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+/// Sums the per-CPU count columns of a `/proc/interrupts`-style table,
+/// skipping the CPU header and the global ERR/MIS error counters.
+fn sum_per_cpu_counts(text: &str) -> u64 {
+    let mut total = 0u64;
+    for line in text.lines().skip(1) {
+        let Some((label, rest)) = line.split_once(':') else {
+            continue;
+        };
+        if matches!(label.trim(), "ERR" | "MIS") {
+            continue;
+        }
+        total += rest
+            .split_whitespace()
+            .map_while(|v| v.parse::<u64>().ok())
+            .sum::<u64>();
+    }
+    total
+}
+
+fn parse_cpu_list(s: &str) -> Option<Vec<usize>> {
+    let mut cpus = Vec::new();
+    for part in s.trim().split(',') {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        if let Some((lo, hi)) = part.split_once('-') {
+            let lo = lo.trim().parse::<usize>().ok()?;
+            let hi = hi.trim().parse::<usize>().ok()?;
+            if hi < lo {
+                return None;
+            }
+            cpus.extend(lo..=hi);
+        } else {
+            cpus.push(part.parse::<usize>().ok()?);
+        }
+    }
+    Some(cpus)
+}
+
+fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < cpus.len() {
+        let start = cpus[i];
+        let mut end = start;
+        while i + 1 < cpus.len() && cpus[i + 1] == end + 1 {
+            end = cpus[i + 1];
+            i += 1;
+        }
+        if start == end {
+            out.push(format!("{}", start));
+        } else {
+            out.push(format!("{}-{}", start, end));
+        }
+        i += 1;
+    }
+    out.join(",")
+}
+
+fn read_numa_node_cpus(node: usize) -> Option<Vec<usize>> {
+    let mut buf = String::new();
+    let path = format!("/sys/devices/system/node/node{}/cpulist", node);
+    File::open(path).ok()?.read_to_string(&mut buf).ok()?;
+    parse_cpu_list(&buf)
+}
+
+/// CPU lists of NUMA nodes 0, 1, ... up to the first missing node.
+fn read_numa_nodes() -> Vec<Vec<usize>> {
+    (0..).map_while(read_numa_node_cpus).collect()
+}
+
+fn read_online_cpus() -> Option<Vec<usize>> {
+    let mut buf = String::new();
+    File::open("/sys/devices/system/cpu/online")
+        .ok()?
+        .read_to_string(&mut buf)
+        .ok()?;
+    parse_cpu_list(&buf)
+}
+
+/// NUMA node of a logical CPU, from its `/sys/devices/system/cpu/cpuN/nodeX` link.
+fn read_cpu_node(cpu: usize) -> Option<usize> {
+    let dir = format!("/sys/devices/system/cpu/cpu{}", cpu);
+    std::fs::read_dir(dir).ok()?.flatten().find_map(|entry| {
+        let name = entry.file_name();
+        name.to_str()?.strip_prefix("node")?.parse::<usize>().ok()
+    })
+}
+
+/// First logical CPU of every physical core, plus the number of HT siblings
+/// left out.
+fn read_physical_core_cpus() -> Option<(Vec<usize>, usize)> {
+    let online = read_online_cpus()?;
+    let mut cores = Vec::new();
+    for &cpu in &online {
+        let mut siblings = String::new();
+        let path = format!(
+            "/sys/devices/system/cpu/cpu{}/topology/thread_siblings_list",
+            cpu
+        );
+        File::open(path).ok()?.read_to_string(&mut siblings).ok()?;
+        let first = parse_cpu_list(&siblings)?.into_iter().min().unwrap_or(cpu);
+        if first == cpu {
+            cores.push(cpu);
+        }
+    }
+    let skipped = online.len() - cores.len();
+    Some((cores, skipped))
+}
+
+/// Data and unified caches of CPU 0 as (name, bytes), smallest first, e.g.
+/// ("L1d", 49152). Empty if sysfs has no cache info.
+fn read_cache_levels() -> Vec<(String, u64)> {
+    let mut levels: Vec<(String, u64)> =
+        cache_levels().into_iter().map(|c| (c.name(), c.bytes)).collect();
+    levels.sort_by_key(|&(_, bytes)| bytes);
+    levels
+}
+
 fn read_gpu_status(path: &PathBuf) -> Option<(f64, f64, f64, f64, f64)> {
     let mut buf = String::new();
     File::open(path).ok()?.read_to_string(&mut buf).ok()?;
@@ -372,164 +762,331 @@ fn read_gpu_status(path: &PathBuf) -> Option<(f64, f64, f64, f64, f64)> {
     Some((util, mem_util, temp, sm_clock, mem_clock))
 }
 
-struct Rng(u64);
+struct GpuReader {
+    status_path: Option<PathBuf>,
+    #[cfg(feature = "nvml")]
+    nvml: Option<nvml::Nvml>,
+}
 
-impl Rng {
-    fn new(seed: u64) -> Self {
-        let seed = if seed == 0 { 0x9e3779b97f4a7c15 } else { seed };
-        Self(seed)
+impl GpuReader {
+    fn new(backend: GpuBackend, status_path: Option<PathBuf>) -> Self {
+        #[cfg(feature = "nvml")]
+        {
+            let nvml = if backend == GpuBackend::Nvml {
+                let nvml = nvml::Nvml::open();
+                if nvml.is_none() {
+                    log::warn!("GPU: NVML not available, falling back to --gpu-status");
+                }
+                nvml
+            } else {
+                None
+            };
+            Self { status_path, nvml }
+        }
+        #[cfg(not(feature = "nvml"))]
+        {
+            if backend == GpuBackend::Nvml {
+                log::warn!("GPU: built without the `nvml` feature, falling back to --gpu-status");
+            }
+            Self { status_path }
+        }
     }
 
-    fn next_u64(&mut self) -> u64 {
-        let mut x = self.0;
-        x ^= x << 13;
-        x ^= x >> 7;
-        x ^= x << 17;
-        self.0 = x;
-        x
+    fn enabled(&self) -> bool {
+        #[cfg(feature = "nvml")]
+        if self.nvml.is_some() {
+            return true;
+        }
+        self.status_path.is_some()
     }
 
-    fn next_u32(&mut self) -> u32 {
-        self.next_u64() as u32
+    fn read(&self) -> Option<(f64, f64, f64, f64, f64)> {
+        #[cfg(feature = "nvml")]
+        if let Some(nvml) = self.nvml.as_ref() {
+            return nvml.read();
+        }
+        self.status_path.as_ref().and_then(read_gpu_status)
     }
 }
 
-fn seed_from_time() -> u64 {
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_else(|_| Duration::from_nanos(0));
-    let mut hasher = DefaultHasher::new();
-    thread::current().id().hash(&mut hasher);
-    let tid = hasher.finish();
-    now.as_nanos() as u64 ^ tid
-}
-
-fn cpu_worker_ops(stop: Arc<AtomicBool>, ops: Arc<AtomicU64>) {
-    let mut rng = Rng::new(seed_from_time());
-    let mut x = 0u64;
-    while !stop.load(Ordering::Relaxed) {
-        let burst = 5000 + (rng.next_u32() % 15000) as u64;
-        for _ in 0..burst {
-            x = x
-                .wrapping_mul(1664525)
-                .wrapping_add(1013904223)
-                .rotate_left(5);
-        }
-        ops.fetch_add(burst, Ordering::Relaxed);
-        if rng.next_u32() % 1200 == 0 {
-            thread::sleep(Duration::from_micros(200));
-        }
-    }
-    std::hint::black_box(x);
-}
-
-fn mem_worker(stop: Arc<AtomicBool>, target_mb: u64, ops: Arc<AtomicU64>) {
-    let chunk = 8 * 1024 * 1024usize;
-    let target_bytes = target_mb * 1024 * 1024;
-    let mut buffers: Vec<Vec<u8>> = Vec::new();
-    let mut allocated = 0u64;
-    while allocated < target_bytes && !stop.load(Ordering::Relaxed) {
-        let size = (target_bytes - allocated).min(chunk as u64) as usize;
-        let mut buf = vec![0u8; size];
-        let mut i = 0usize;
-        while i < size {
-            buf[i] = 1;
-            i += 4096;
-        }
-        buffers.push(buf);
-        allocated += size as u64;
-    }
-
-    let mut rng = Rng::new(seed_from_time());
-    while !stop.load(Ordering::Relaxed) {
-        if buffers.is_empty() {
-            thread::sleep(Duration::from_millis(200));
-            continue;
+/// Triggers once a metric has been at or above `threshold` for `sustain`
+/// consecutive samples.
+struct StopCondition {
+    name: &'static str,
+    threshold: f64,
+    sustain: usize,
+    streak: usize,
+}
+
+impl StopCondition {
+    fn new(name: &'static str, threshold: f64, sustain: usize) -> Self {
+        Self {
+            name,
+            threshold,
+            sustain,
+            streak: 0,
         }
-        let idx = (rng.next_u32() as usize) % buffers.len();
-        let buf = &mut buffers[idx];
-        let off = (rng.next_u32() as usize) % buf.len();
-        buf[off] = buf[off].wrapping_add(1);
-        ops.fetch_add(1, Ordering::Relaxed);
-        if rng.next_u32() % 1000 == 0 {
-            thread::sleep(Duration::from_micros(200));
-        }
-    }
-    std::hint::black_box(buffers);
-}
-
-fn disk_worker(
-    stop: Arc<AtomicBool>,
-    bytes_written: Arc<AtomicU64>,
-    ops: Arc<AtomicU64>,
-    path: PathBuf,
-    max_bytes: u64,
-) {
-    let mut file = match OpenOptions::new().create(true).read(true).write(true).open(&path) {
-        Ok(f) => f,
-        Err(e) => {
-            eprintln!("Disk worker: cannot open {}: {}", path.display(), e);
+    }
+
+    fn check(&mut self, value: f64) -> bool {
+        if value >= self.threshold {
+            self.streak += 1;
+        } else {
+            self.streak = 0;
+        }
+        if self.streak >= self.sustain {
+            log::info!(
+                "STOP: {} {:.1} >= threshold {} for {} samples",
+                self.name, value, self.threshold, self.sustain
+            );
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Runs `--alert-cmd` in the background, once per alert type.
+struct Alerter {
+    cmd: String,
+    fired: Vec<&'static str>,
+}
+
+impl Alerter {
+    fn new(cmd: String) -> Self {
+        Self {
+            cmd,
+            fired: Vec::new(),
+        }
+    }
+
+    fn fire(&mut self, kind: &'static str, value: String) {
+        if self.fired.contains(&kind) {
             return;
         }
-    };
-    let max_bytes = max_bytes.max(4 * 1024 * 1024);
-    let _ = file.set_len(max_bytes);
-
-    let mut rng = Rng::new(seed_from_time());
-    let block_max = 4 * 1024 * 1024usize;
-    let block_min = 4 * 1024usize;
-    let mut buf = vec![0u8; block_max];
-    for i in (0..block_max).step_by(4096) {
-        buf[i] = (i / 4096) as u8;
-    }
-
-    while !stop.load(Ordering::Relaxed) {
-        let mut size = block_min + (rng.next_u32() as usize % (block_max - block_min + 1));
-        size &= !0xFFF;
-        if size == 0 {
-            size = block_min;
-        }
-        let max_off = max_bytes.saturating_sub(size as u64);
-        let offset = if max_off == 0 {
-            0
+        self.fired.push(kind);
+        let spawned = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&self.cmd)
+            .env("STRESS_ALERT_TYPE", kind)
+            .env("STRESS_ALERT_VALUE", value)
+            .spawn();
+        match spawned {
+            // Reap it off the sampling thread; a slow hook must not stall samples.
+            Ok(mut child) => {
+                thread::spawn(move || child.wait());
+            }
+            Err(e) => log::warn!("--alert-cmd: cannot run sh: {}", e),
+        }
+    }
+}
+
+/// Upper bound for `--adaptive-sample`, so Ctrl+C stays responsive.
+const MAX_ADAPTIVE_SAMPLE_MS: u64 = 5000;
+const ADAPTIVE_STABLE_SAMPLES: u32 = 5;
+
+/// Grows the sample interval while metrics are flat and shrinks it when they
+/// move (`--adaptive-sample`).
+struct AdaptiveSampler {
+    min: Duration,
+    max: Duration,
+    stable: u32,
+    prev: Option<Vec<f64>>,
+}
+
+impl AdaptiveSampler {
+    fn new(min: Duration, start: Duration) -> Self {
+        Self {
+            min,
+            max: start.max(Duration::from_millis(MAX_ADAPTIVE_SAMPLE_MS)),
+            stable: 0,
+            prev: None,
+        }
+    }
+
+    /// Returns the new interval if it should change.
+    fn update(&mut self, metrics: &[f64], sample: Duration) -> Option<Duration> {
+        let prev = self.prev.replace(metrics.to_vec())?;
+        let max_change = prev
+            .iter()
+            .zip(metrics)
+            .map(|(a, b)| (a - b).abs() / a.abs().max(b.abs()).max(1.0))
+            .fold(0.0, f64::max);
+        if max_change > 0.05 {
+            self.stable = 0;
+            let next = (sample / 2).max(self.min);
+            return (next != sample).then_some(next);
+        }
+        if max_change <= 0.02 {
+            self.stable += 1;
+            if self.stable >= ADAPTIVE_STABLE_SAMPLES {
+                self.stable = 0;
+                let next = (sample * 2).min(self.max);
+                return (next != sample).then_some(next);
+            }
         } else {
-            rng.next_u64() % max_off
-        };
-        if file.seek(SeekFrom::Start(offset)).is_err() {
-            break;
+            self.stable = 0;
         }
-        if file.write_all(&buf[..size]).is_err() {
-            break;
+        None
+    }
+}
+
+/// Recent one-off events (phase changes, warnings). Shown in the TUI header,
+/// or written to stderr when stdout is not a terminal.
+struct Notices {
+    lines: VecDeque<String>,
+    is_tty: bool,
+}
+
+impl Notices {
+    const MAX: usize = 3;
+
+    fn new(is_tty: bool) -> Self {
+        Self {
+            lines: VecDeque::new(),
+            is_tty,
+        }
+    }
+
+    fn push(&mut self, msg: String) {
+        if !self.is_tty {
+            log::info!("{msg}");
         }
-        bytes_written.fetch_add(size as u64, Ordering::Relaxed);
-        ops.fetch_add(1, Ordering::Relaxed);
-        if rng.next_u32() % 200 == 0 {
-            let _ = file.flush();
+        if self.lines.len() == Self::MAX {
+            self.lines.pop_front();
         }
+        self.lines.push_back(msg);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &String> {
+        self.lines.iter()
+    }
+}
+
+/// Stops and joins every worker in `handles`, leaving it empty. Returns
+/// what the workers returned.
+fn stop_all<T: Send + 'static>(handles: &mut Vec<WorkerHandle<T>>) -> Vec<T> {
+    for h in handles.iter() {
+        h.stop();
+    }
+    handles.drain(..).filter_map(WorkerHandle::join).collect()
+}
+
+/// Merges the latency histograms of `handles` into the
+/// `--latency-histogram-export` totals under `tag`; call it before the
+/// handles are stopped. A no-op without the `latency_histogram` feature.
+fn absorb_latency<T>(export: &mut Option<LatencyExport>, tag: &'static str, handles: &[WorkerHandle<T>]) {
+    #[cfg(feature = "latency_histogram")]
+    if let Some(export) = export.as_mut() {
+        export.absorb(tag, handles);
+    }
+    #[cfg(not(feature = "latency_histogram"))]
+    let _ = (export, tag, handles);
+}
+
+/// Pauses or resumes every worker in `handles`.
+fn set_paused<T: Send + 'static>(handles: &[WorkerHandle<T>], paused: bool) {
+    for h in handles {
+        if paused {
+            h.pause();
+        } else {
+            h.resume();
+        }
+    }
+}
+
+/// Writes dirty pages back, then drops the page cache, dentries and inodes
+/// so the next reads come from the device.
+fn drop_page_cache() -> std::io::Result<()> {
+    unsafe { sync() };
+    std::fs::write("/proc/sys/vm/drop_caches", "3")
+}
+
+fn touch_heartbeat(path: &PathBuf) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if let Err(e) = std::fs::write(path, format!("{} {}\n", std::process::id(), now)) {
+        log::warn!("Cannot write heartbeat {}: {}", path.display(), e);
+    }
+}
+
+fn total_ops<T>(handles: &[WorkerHandle<T>]) -> u64 {
+    handles.iter().map(WorkerHandle::ops_count).sum()
+}
+
+/// Per-type synthetic-mix ops summed over `handles`.
+fn total_mix_ops<T>(handles: &[WorkerHandle<T>]) -> [u64; 3] {
+    handles.iter().map(WorkerHandle::cpu_mix_ops).fold([0; 3], |acc, n| {
+        std::array::from_fn(|i| acc[i] + n[i])
+    })
+}
+
+fn total_bytes<T>(handles: &[WorkerHandle<T>]) -> u64 {
+    handles.iter().map(WorkerHandle::bytes_transferred).sum()
+}
+
+fn total_errors<T>(handles: &[WorkerHandle<T>]) -> u64 {
+    handles.iter().map(WorkerHandle::error_count).sum()
+}
+
+/// `read`, `write` or `mixed N% read`, for the banner and DSK chart.
+fn disk_io_label(args: &Args) -> String {
+    match args.cli.disk_io_type {
+        DiskIoType::Mixed => format!("mixed {}% read", args.cli.disk_read_pct),
+        io_type => io_type.name().to_string(),
     }
 }
 
-fn stats_window(hist: &[f64], head: usize, filled: usize) -> (f64, f64, f64) {
+/// Most recent error message of the first handle that reported one.
+fn first_worker_error<T>(handles: &[WorkerHandle<T>]) -> Option<String> {
+    handles
+        .iter()
+        .find(|h| h.error_count() > 0)
+        .map(|h| h.last_error().unwrap_or_default())
+}
+
+/// Summary of the filled part of a history ring buffer. Percentiles are
+/// nearest-rank; `stddev` is the population standard deviation.
+#[derive(Clone, Copy, Debug, Default)]
+struct RingBufferStats {
+    min: f64,
+    max: f64,
+    avg: f64,
+    p50: f64,
+    p95: f64,
+    p99: f64,
+    stddev: f64,
+}
+
+/// Stats of the `filled` samples ending just before `head` in `hist`.
+fn compute_stats(hist: &[f64], head: usize, filled: usize) -> RingBufferStats {
     if filled == 0 {
-        return (0.0, 0.0, 0.0);
+        return RingBufferStats::default();
     }
     let len = hist.len();
-    let mut min_v = f64::MAX;
-    let mut max_v = f64::MIN;
-    let mut sum = 0.0;
-    for i in 0..filled {
-        let idx = (head + len - filled + i) % len;
-        let v = hist[idx];
-        if v < min_v {
-            min_v = v;
-        }
-        if v > max_v {
-            max_v = v;
-        }
-        sum += v;
+    let mut window: Vec<f64> = (0..filled)
+        .map(|i| hist[(head + len - filled + i) % len])
+        .collect();
+    window.sort_by(f64::total_cmp);
+    let n = window.len() as f64;
+    let avg = window.iter().sum::<f64>() / n;
+    let var = window.iter().map(|v| (v - avg) * (v - avg)).sum::<f64>() / n;
+    let rank = |p: f64| window[((p * n).ceil() as usize).clamp(1, window.len()) - 1];
+    RingBufferStats {
+        min: window[0],
+        max: window[window.len() - 1],
+        avg,
+        p50: rank(0.50),
+        p95: rank(0.95),
+        p99: rank(0.99),
+        stddev: var.sqrt(),
     }
-    let avg = sum / filled as f64;
-    (min_v, avg, max_v)
+}
+
+fn ema_update(prev: f64, new: f64, alpha: f64) -> f64 {
+    prev + alpha * (new - prev)
 }
 
 fn format_rate(value: f64, unit: &str) -> String {
@@ -547,25 +1104,25 @@ fn format_rate(value: f64, unit: &str) -> String {
 
 fn build_chart(hist: &[f64], head: usize, width: usize, height: usize, max: f64) -> Vec<Vec<char>> {
     let max = if max <= 0.0 { 1.0 } else { max };
+    let len = hist.len();
     let mut grid = vec![vec![' '; width]; height];
     let mut prev_row: Option<usize> = None;
     for x in 0..width {
-        let v = hist[(head + x) % width];
-        let mut ratio = v / max;
-        if ratio < 0.0 {
-            ratio = 0.0;
-        }
-        if ratio > 1.0 {
-            ratio = 1.0;
-        }
+        // Each column averages its share of the history so longer histories
+        // (fast sample rates) still fit the chart width.
+        let from = x * len / width;
+        let to = ((x + 1) * len / width).max(from + 1);
+        let sum: f64 = (from..to).map(|i| hist[(head + i) % len]).sum();
+        let v = sum / (to - from) as f64;
+        let ratio = (v / max).clamp(0.0, 1.0);
         let y = ((height as f64 - 1.0) * ratio).round() as usize;
         let row = height - 1 - y;
         if let Some(prev) = prev_row {
             if prev != row {
                 let (from, to) = if prev < row { (prev, row) } else { (row, prev) };
-                for r in from..=to {
-                    if grid[r][x] == ' ' {
-                        grid[r][x] = '|';
+                for cells in grid.iter_mut().take(to + 1).skip(from) {
+                    if cells[x] == ' ' {
+                        cells[x] = '|';
                     }
                 }
             }
@@ -576,6 +1133,7 @@ fn build_chart(hist: &[f64], head: usize, width: usize, height: usize, max: f64)
     grid
 }
 
+#[allow(clippy::too_many_arguments)]
 fn render_chart(
     label: &str,
     unit: &str,
@@ -583,102 +1141,1053 @@ fn render_chart(
     hist: &[f64],
     head: usize,
     filled: usize,
+    stats: &RingBufferStats,
     max: f64,
     width: usize,
     height: usize,
+    ema: Option<f64>,
+    mode: ChartMode,
 ) -> Vec<String> {
     let max = if max <= 0.0 { 1.0 } else { max };
-    let (min_v, avg_v, max_v) = stats_window(hist, head, filled);
+    let (avg_label, avg_v) = match ema {
+        Some(v) => ("ema", v),
+        None => ("avg", stats.avg),
+    };
     let mut lines = Vec::new();
     lines.push(format!(
-        "{label} {current:6.1}{unit} (min {min_v:6.1} avg {avg_v:6.1} max {max_v:6.1})"
+        "{label} {current:6.1}{unit} (min {:6.1} {avg_label} {avg_v:6.1} max {:6.1} p50 {:6.1} p95 {:6.1} p99 {:6.1} sd {:5.1})",
+        stats.min, stats.max, stats.p50, stats.p95, stats.p99, stats.stddev
     ));
 
-    let grid = build_chart(hist, head, width, height, max);
-    for row in 0..height {
-        let axis_val = max * (height - 1 - row) as f64 / (height - 1) as f64;
-        let label = if row == 0 || row == height - 1 || row == height / 2 {
-            format!("{:>6.0}", axis_val)
-        } else {
-            "      ".to_string()
-        };
-        let line: String = grid[row].iter().collect();
-        lines.push(format!("{label}|{line}"));
+    if mode != ChartMode::Hist {
+        let grid = build_chart(hist, head, width, height, max);
+        for (row, cells) in grid.iter().enumerate() {
+            let axis_val = max * (height - 1 - row) as f64 / (height - 1) as f64;
+            let label = if row == 0 || row == height - 1 || row == height / 2 {
+                format!("{:>6.0}", axis_val)
+            } else {
+                "      ".to_string()
+            };
+            let line: String = cells.iter().collect();
+            lines.push(format!("{label}|{line}"));
+        }
+        lines.push(format!("      +{}", "-".repeat(width)));
+    }
+    if mode != ChartMode::Ts {
+        let len = hist.len();
+        let window: Vec<f64> = (0..filled)
+            .map(|i| hist[(head + len - filled + i) % len])
+            .collect();
+        lines.extend(render_histogram(label, &window, height, width));
     }
-    lines.push(format!("      +{}", "-".repeat(width)));
     lines
 }
 
+/// Horizontal bar chart of `data` split into `buckets` equal ranges between
+/// its min and max; bar length is proportional to the bucket count.
+fn render_histogram(label: &str, data: &[f64], buckets: usize, width: usize) -> Vec<String> {
+    let buckets = buckets.max(1);
+    let mut lines = vec![format!("{label} histogram ({} samples)", data.len())];
+    if data.is_empty() {
+        return lines;
+    }
+    let lo = data.iter().copied().fold(f64::MAX, f64::min);
+    let hi = data.iter().copied().fold(f64::MIN, f64::max);
+    let span = if hi > lo { hi - lo } else { 1.0 };
+    let mut counts = vec![0usize; buckets];
+    for &v in data {
+        let b = (((v - lo) / span) * buckets as f64) as usize;
+        counts[b.min(buckets - 1)] += 1;
+    }
+    let peak = counts.iter().copied().max().unwrap_or(1).max(1);
+    let bar_width = width.saturating_sub(8).max(1);
+    for (i, &count) in counts.iter().enumerate() {
+        let from = lo + span * i as f64 / buckets as f64;
+        let to = lo + span * (i + 1) as f64 / buckets as f64;
+        let bar = count * bar_width / peak;
+        lines.push(format!(
+            "{:>6.0}-{:<6.0}|{:<w$} {}",
+            from,
+            to,
+            "#".repeat(bar),
+            count,
+            w = bar_width
+        ));
+    }
+    lines
+}
+
+/// Calls every metric reader once and reports OK / NOT_AVAILABLE. Returns the
+/// process exit code: 1 if a reader that every Linux system provides failed.
+fn run_self_test(args: &Args) -> i32 {
+    let checks: Vec<(&str, bool, Option<String>)> = vec![
+        (
+            "read_cpu_times",
+            true,
+            read_cpu_times().map(|(t, i)| format!("total={} idle={}", t, i)),
+        ),
+        (
+            "read_mem_used_kb",
+            true,
+            read_mem_used_kb().map(|(t, u)| format!("total={}kB used={}kB", t, u)),
+        ),
+        (
+            "read_loadavg",
+            true,
+            read_loadavg().map(|(a, b, c)| format!("{:.2} {:.2} {:.2}", a, b, c)),
+        ),
+        (
+            "read_temp_c",
+            false,
+            read_temp_c().map(|(t, zone)| format!("{:.1}C ({})", t, zone)),
+        ),
+        (
+            "read_per_core_temp",
+            false,
+            Some(read_per_core_temp())
+                .filter(|t| !t.is_empty())
+                .map(|t| format!("{} cores", t.len())),
+        ),
+        (
+            "read_cpu_freq_khz",
+            false,
+            read_cpu_freq_khz().map(|f| format!("{}kHz", f)),
+        ),
+        (
+            "read_numa_node_cpus",
+            false,
+            read_numa_node_cpus(0).map(|c| format!("node0 cpus {}", format_cpu_list(&c))),
+        ),
+        (
+            "read_physical_core_cpus",
+            false,
+            read_physical_core_cpus()
+                .map(|(c, skipped)| format!("{} cores, {} HT siblings", c.len(), skipped)),
+        ),
+        (
+            "read_numa_stats",
+            false,
+            read_numa_stats().map(|(m, h, x)| format!("migrated={} hit={} miss={}", m, h, x)),
+        ),
+        (
+            "read_irq_stats",
+            false,
+            read_irq_stats().map(|(irq, soft)| format!("irq={} softirq={}", irq, soft)),
+        ),
+        (
+            "read_vmstat_pgfault",
+            false,
+            read_vmstat_pgfault().map(|(minor, major)| format!("minor={} major={}", minor, major)),
+        ),
+        (
+            "read_sockstat_timewait",
+            false,
+            read_sockstat_timewait().map(|tw| format!("tw={}", tw)),
+        ),
+        (
+            "read_io_scheduler",
+            false,
+            block_device_name(&env::temp_dir()).and_then(|dev| {
+                let sched = read_io_scheduler(&dev)?;
+                Some(match read_nr_requests(&dev) {
+                    Some(n) => format!("{}: {} nr_requests={}", dev, sched, n),
+                    None => format!("{}: {}", dev, sched),
+                })
+            }),
+        ),
+        (
+            "gpu",
+            false,
+            GpuReader::new(args.cli.gpu_backend, args.cli.gpu_status_path.clone())
+                .read()
+                .map(|g| format!("util={:.0}% temp={:.0}C", g.0, g.2)),
+        ),
+    ];
+
+    let mut failed = false;
+    for (name, required, result) in checks {
+        match result {
+            Some(v) => println!("OK             {:<24} {}", name, v),
+            None if required => {
+                println!("ERROR          {:<24} required on Linux", name);
+                failed = true;
+            }
+            None => println!("NOT_AVAILABLE  {}", name),
+        }
+    }
+    if failed {
+        1
+    } else {
+        0
+    }
+}
+
+/// Sends `log` output to stderr: `--log-level`, else `RUST_LOG`, else info
+/// (debug with `--verbose`). Info lines print bare, like the status output
+/// they replaced.
+fn init_logger(args: &Args) {
+    let default = if args.cli.verbose { "debug" } else { "info" };
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(default));
+    if let Some(level) = args.cli.log_level {
+        builder.filter_level(level);
+    }
+    builder
+        .format(|buf, record| match record.level() {
+            log::Level::Info => writeln!(buf, "{}", record.args()),
+            level => writeln!(buf, "[{} {}] {}", level, record.target(), record.args()),
+        })
+        .init();
+}
+
 fn main() {
     install_signal_handlers();
     let args = parse_args();
+    init_logger(&args);
+    if args.cli.self_test {
+        std::process::exit(run_self_test(&args));
+    }
+    if let Some(path) = args.cli.stress_script.as_ref() {
+        std::process::exit(stress_script::run(path));
+    }
+    if args.cli.verbose_startup {
+        let disk_path = args.cli.disk_raw_device.clone().unwrap_or_else(|| {
+            let dir = args.cli.temp_dir.clone();
+            dir.unwrap_or_else(|| env::temp_dir().join("stress_rust"))
+        });
+        startup_report::print(&args, &disk_path);
+    }
+
+    let phase_specs: Vec<PhaseSpec> = match args.cli.phase_file.as_ref() {
+        Some(path) => {
+            if !args.cpu_phases.is_empty() {
+                eprintln!("--phase-file cannot be combined with --cpu-phase-list");
+                std::process::exit(2);
+            }
+            let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("Cannot read phase file {}: {}", path.display(), e);
+                std::process::exit(2);
+            });
+            phase_file::parse(&text).unwrap_or_else(|e| {
+                eprintln!("Invalid phase file {}: {}", path.display(), e);
+                std::process::exit(2);
+            })
+        }
+        None => Vec::new(),
+    };
+    let mix_used = args.cli.cpu_workload == CpuWorkload::SyntheticMix
+        || args.cpu_phases.iter().any(|p| p.workload == CpuWorkload::SyntheticMix)
+        || phase_specs.iter().any(|p| p.cpu_workload == Some(CpuWorkload::SyntheticMix));
+    if args.cpu_mix.is_some() && !mix_used {
+        eprintln!("--cpu-mix-* need --cpu-workload synthetic-mix (or a phase that runs it)");
+        std::process::exit(2);
+    }
+    let disk_wanted = args.enable_disk || phase_specs.iter().any(|p| p.disk == Some(true));
+    let mem_wanted = args.enable_mem || phase_specs.iter().any(|p| p.mem == Some(true));
+    let memcpy_wanted = args.cli.cpu_workload == CpuWorkload::Memcpy
+        || args.cpu_phases.iter().any(|p| p.workload == CpuWorkload::Memcpy)
+        || phase_specs.iter().any(|p| p.cpu_workload == Some(CpuWorkload::Memcpy));
+    let gpu = GpuReader::new(args.cli.gpu_backend, args.cli.gpu_status_path.clone());
+    for section in args.tui_layout.iter().flatten() {
+        let missing = match section {
+            TuiSection::Cpy if !memcpy_wanted => Some("--cpu-workload memcpy"),
+            TuiSection::Mem if !mem_wanted => Some("memory workers (drop --no-mem)"),
+            TuiSection::Disk if !disk_wanted => Some("disk workers (drop --no-disk)"),
+            TuiSection::Net
+                if args.cli.net_workers_unix == 0
+                    && args.cli.net_udp_workers == 0
+                    && args.cli.socket_stress == 0 =>
+            {
+                Some("--net-workers-unix, --net-udp-workers or --socket-stress")
+            }
+            TuiSection::Gpu if !gpu.enabled() && !args.cli.gpu_compute_stress => {
+                Some("--gpu-status, --gpu-backend nvml or --gpu-compute-stress")
+            }
+            _ => None,
+        };
+        if let Some(need) = missing {
+            eprintln!("--tui-layout section '{}' requires {}", section.name(), need);
+            std::process::exit(2);
+        }
+    }
+    // Read up front so a bad path fails before the run, not after it.
+    let baseline_json = args.cli.compare_baseline.as_ref().map(|path| {
+        let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("Cannot read baseline {}: {}", path.display(), e);
+            std::process::exit(2);
+        });
+        if baseline::parse_metrics(&text).is_empty() {
+            eprintln!("Invalid baseline {}: no metrics", path.display());
+            std::process::exit(2);
+        }
+        text
+    });
+
+    let cpu_numa_cpus = args.cli.cpu_numa_node.map(|node| match read_numa_node_cpus(node) {
+        Some(cpus) if !cpus.is_empty() => cpus,
+        Some(_) => {
+            eprintln!("NUMA node {} has no CPUs", node);
+            std::process::exit(2);
+        }
+        None => {
+            eprintln!("NUMA node {} not found", node);
+            std::process::exit(2);
+        }
+    });
+    let mem_numa_cpus = args.cli.mem_numa_node.map(|node| {
+        read_numa_node_cpus(node).unwrap_or_else(|| {
+            eprintln!("NUMA node {} not found", node);
+            std::process::exit(2);
+        })
+    });
+
+    let mut banner = Vec::new();
+    if args.cli.mem_stride_test {
+        eprintln!(
+            "MEM stride test: {} MB buffer, {}s per stride",
+            STRIDE_TEST_MB, args.stride_test_duration_s
+        );
+        eprintln!("{:>8}  {:>10}", "stride", "MB/s");
+        let results = stride_test(STRIDE_TEST_MB, Duration::from_secs(args.stride_test_duration_s));
+        for (stride, mb_s) in &results {
+            eprintln!("{:>7}B  {:>10.1}", stride, mb_s);
+        }
+        let cells: Vec<String> =
+            results.iter().map(|(stride, mb_s)| format!("{}B {:.0}", stride, mb_s)).collect();
+        banner.push(format!("MEM stride MB/s: {}", cells.join("  ")));
+    }
+    if args.cli.mem_latency_sweep {
+        let caches = read_cache_levels();
+        let sizes: Vec<String> =
+            caches.iter().map(|(name, bytes)| format!("{} {}K", name, bytes / 1024)).collect();
+        eprintln!(
+            "MEM latency sweep: {}s per working set (caches: {})",
+            LATENCY_SWEEP_S,
+            if sizes.is_empty() { "unknown".to_string() } else { sizes.join(", ") }
+        );
+        let results = latency_sweep(Duration::from_secs(LATENCY_SWEEP_S));
+        let max_ns = results.iter().map(|&(_, ns)| ns).fold(0.0, f64::max);
+        eprintln!("{:>6}  {:>8}  {:<5} ", "size", "ns", "fits");
+        for &(mb, ns) in &results {
+            let fits = caches
+                .iter()
+                .find(|&&(_, bytes)| bytes >= mb * 1024 * 1024)
+                .map_or("DRAM", |(name, _)| name.as_str());
+            let bar = if max_ns > 0.0 {
+                ((ns / max_ns) * LATENCY_CHART_WIDTH as f64).round() as usize
+            } else {
+                0
+            };
+            eprintln!("{:>4}MB  {:>8.1}  {:<5} {}", mb, ns, fits, "#".repeat(bar.max(1)));
+        }
+        let cells: Vec<String> =
+            results.iter().map(|(mb, ns)| format!("{}MB {:.1}ns", mb, ns)).collect();
+        banner.push(format!("MEM latency: {}", cells.join("  ")));
+    }
+    let mut cpu_affinity = cpu_numa_cpus.clone();
+    if args.cli.cpu_isolate_ht {
+        let Some((cores, skipped)) = read_physical_core_cpus() else {
+            eprintln!("Cannot read CPU topology for --cpu-isolate-ht");
+            std::process::exit(2);
+        };
+        // On one node, only that node's siblings are skipped.
+        let (cores, skipped): (Vec<usize>, usize) = match cpu_numa_cpus.as_ref() {
+            Some(node_cpus) => {
+                let cores: Vec<usize> =
+                    cores.into_iter().filter(|c| node_cpus.contains(c)).collect();
+                let skipped = node_cpus.len().saturating_sub(cores.len());
+                (cores, skipped)
+            }
+            None => (cores, skipped),
+        };
+        if cores.is_empty() {
+            eprintln!("--cpu-isolate-ht leaves no CPUs on --cpu-numa-node");
+            std::process::exit(2);
+        }
+        banner.push(format!(
+            "Using {} physical cores (skipping {} HT siblings)",
+            cores.len(),
+            skipped
+        ));
+        cpu_affinity = Some(cores);
+    }
 
-    let cpu_workers = if args.cpu_workers == 0 {
+    let available_cpus = if let Some(cpus) = cpu_affinity.as_ref() {
+        cpus.len()
+    } else {
         thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    };
+    let cpu_workers = if args.cli.cpu_workers > 0 {
+        args.cli.cpu_workers
+    } else if let Some((cpu_w, mem_w)) = args.cli.cpu_mem_ratio {
+        // Any non-zero CPU weight gets at least one worker.
+        let n = (available_cpus as u64 * cpu_w / (cpu_w + mem_w)) as usize;
+        if cpu_w > 0 { n.max(1) } else { 0 }
+    } else {
+        available_cpus
+    };
+    if args.cli.cpu_benchmark_only {
+        std::process::exit(cpu_bench::run(&args, cpu_workers));
+    }
+    if args.cli.stress_profile_cpu_topology {
+        let cpus = cpu_affinity
+            .clone()
+            .or_else(read_online_cpus)
+            .unwrap_or_else(|| (0..available_cpus).collect());
+        std::process::exit(cpu_topology::run(&cpus));
+    }
+    #[cfg(feature = "work_stealing")]
+    if args.cli.cpu_work_stealing {
+        std::process::exit(work_stealing::run(cpu_workers));
+    }
+
+    if let (Some(node), Some(cpus)) = (args.cli.cpu_numa_node, cpu_numa_cpus.as_ref()) {
+        banner.push(format!("CPU affinity: node {} cpus {}", node, format_cpu_list(cpus)));
+    }
+    if let Some(node) = args.cli.mem_numa_node {
+        banner.push(format!("MEM binding: node {}", node));
+    }
+    if let Some(p) = args.mem_gc_pattern {
+        banner.push(format!(
+            "MEM GC pattern: young {} x {} KB, old {} MB",
+            p.young_count, p.young_size_kb, p.old_size_mb
+        ));
+    }
+
+    let mem_total_mb = read_mem_total_mb();
+    let mut mem_target = if args.cli.mem_mb > 0 {
+        args.cli.mem_mb
+    } else if let Some((cpu_w, mem_w)) = args.cli.cpu_mem_ratio.filter(|_| mem_total_mb > 0) {
+        mem_total_mb * mem_w / (cpu_w + mem_w)
+    } else if mem_total_mb > 0 {
+        (mem_total_mb as f64 * 0.60) as u64
+    } else {
+        512
+    };
+    mem_target = mem_target.clamp(256, 16384);
+    if args.cli.mem_prefetch_compare {
+        std::process::exit(mem_prefetch::run(&args, mem_target));
+    }
+    if let Some((cpu_w, mem_w)) = args.cli.cpu_mem_ratio {
+        let mem = if args.enable_mem { format!("{} MB", mem_target) } else { "off".into() };
+        banner.push(format!(
+            "CPU:MEM ratio {}:{} -> {} CPU workers, MEM {}",
+            cpu_w, mem_w, cpu_workers, mem
+        ));
+    }
+
+    let disk_bytes = if let Some(dev) = args.cli.disk_raw_device.as_ref().filter(|_| disk_wanted) {
+        block_device_size(dev).unwrap_or_else(|| {
+            eprintln!("Cannot read block device size of {}", dev.display());
+            std::process::exit(2);
+        })
+    } else if disk_wanted {
+        let gb = if args.cli.disk_gb <= 0.0 { 1.0 } else { args.cli.disk_gb };
+        (gb * 1024.0 * 1024.0 * 1024.0) as u64
+    } else {
+        0
+    };
+
+    let disk_trace = args.cli.disk_trace.as_ref().filter(|_| disk_wanted).map(|path| {
+        let text = std::fs::read_to_string(path).unwrap_or_else(|e| {
+            eprintln!("Cannot read disk trace {}: {}", path.display(), e);
+            std::process::exit(2);
+        });
+        let trace = DiskTrace::parse(&text, disk_bytes).unwrap_or_else(|e| {
+            eprintln!("Invalid disk trace {}: {}", path.display(), e);
+            std::process::exit(2);
+        });
+        Arc::new(trace)
+    });
+    if let (Some(path), Some(trace)) = (args.cli.disk_trace.as_ref(), disk_trace.as_ref()) {
+        banner.push(format!("DSK trace: {} ({} ops)", path.display(), trace.ops.len()));
+    }
+    if let Some(model) = args.cli.disk_latency_model.filter(|_| args.enable_disk) {
+        banner.push(format!("DSK latency model: {}", model.name()));
+    }
+    if let Some(prio) = args.cli.disk_io_priority.filter(|_| args.enable_disk) {
+        banner.push(format!("DSK I/O priority: {}", prio.name()));
+    }
+    if let Some(iops) = args.disk_io_throttle.filter(|_| args.enable_disk) {
+        banner.push(format!("DSK IOPS cap: {} per worker (burst {})", iops, 2 * iops));
+    }
+    if let Some(nice) = args.cli.disk_worker_nice.filter(|_| args.enable_disk) {
+        banner.push(format!("DSK worker nice: {}", nice));
+        if nice < 0 && unsafe { geteuid() } != 0 {
+            log::warn!(
+                "--disk-worker-nice {} may fail with EPERM without root or CAP_SYS_NICE",
+                nice
+            );
+        }
+    }
+    if args.cli.disk_rmw && args.enable_disk {
+        banner.push("DSK mode: read-modify-write".to_string());
+    }
+    if args.cli.disk_aio_depth > 0 && args.enable_disk {
+        banner.push(format!("DSK POSIX AIO: depth {}", args.cli.disk_aio_depth));
+    }
+    if args.cli.io_pattern != IoPattern::Random && args.enable_disk {
+        banner.push(format!("DSK pattern: {}", args.cli.io_pattern.name()));
+    }
+    if args.cli.disk_io_type != DiskIoType::Write && args.enable_disk {
+        banner.push(format!("DSK I/O type: {}", disk_io_label(&args)));
+    }
+    match args.cli.disk_access_pattern {
+        _ if !args.enable_disk => {}
+        DiskAccessPattern::Uniform => {}
+        DiskAccessPattern::Hotspot => {
+            banner.push(format!("DSK access: hotspot (Zipf s={})", args.cli.zipf_s));
+        }
+        pattern => banner.push(format!("DSK access: {}", pattern.name())),
+    }
+    if args.cli.disk_compress != DiskCompress::None && args.enable_disk {
+        banner.push(format!("DSK compress: {}", args.cli.disk_compress.name()));
+    }
+    match args.disk_write_pattern.as_ref().filter(|_| args.enable_disk) {
+        Some(DiskWritePattern::Pattern(bytes)) => {
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            banner.push(format!("DSK write pattern: pattern 0x{} ({} bytes)", hex, bytes.len()));
+        }
+        Some(pattern) => banner.push(format!("DSK write pattern: {}", pattern.name())),
+        None => {}
+    }
+
+    if let Some(path) = args.cli.heartbeat_file.as_ref() {
+        banner.push(format!("Heartbeat: {}", path.display()));
+    }
+
+    let first_workload = args
+        .cpu_phases
+        .first()
+        .map(|p| p.workload)
+        .or_else(|| phase_specs.first().and_then(|p| p.cpu_workload))
+        .unwrap_or(args.cli.cpu_workload);
+    let cpu_control = CpuWorkloadControl::new(first_workload);
+    if !args.cpu_phases.is_empty() {
+        let list: Vec<String> = args
+            .cpu_phases
+            .iter()
+            .map(|p| format!("{}:{}s", p.workload.name(), p.duration_s))
+            .collect();
+        banner.push(format!(
+            "CPU phases: {}{}",
+            list.join(" → "),
+            if args.cli.cpu_phase_loop { " (loop)" } else { "" }
+        ));
+    } else if args.cli.cpu_workload != CpuWorkload::Lcg {
+        banner.push(format!("CPU workload: {}", args.cli.cpu_workload.name()));
+    }
+    if args.cli.cpu_workload == CpuWorkload::SyntheticMix
+        || args.cpu_phases.iter().any(|p| p.workload == CpuWorkload::SyntheticMix)
+        || phase_specs.iter().any(|p| p.cpu_workload == Some(CpuWorkload::SyntheticMix))
+    {
+        let [int, fp, mem] = args.cpu_mix.unwrap_or(CPU_MIX_DEFAULT);
+        banner.push(format!("CPU mix: integer {}%  float {}%  memory {}%", int, fp, mem));
+    }
+    if memcpy_wanted {
+        banner.push(format!(
+            "CPU memcpy size: {} KB",
+            args.cli.cpu_memcpy_kb.unwrap_or(MEMCPY_DEFAULT_BYTES as u64 / 1024)
+        ));
+    }
+    let cache_sizes = args.cli.cpu_cache_size_detect.then(|| {
+        let Some(caches) = CacheSizes::detect() else {
+            eprintln!("Cannot read cache sizes from sysfs for --cpu-cache-size-detect");
+            std::process::exit(2);
+        };
+        banner.push(format!(
+            "CPU caches: L1d {}K  L2 {}K  L3 {}K (cache-thrash working set {} MB)",
+            caches.l1_kb,
+            caches.l2_kb,
+            caches.l3_kb,
+            caches.thrash_bytes() / (1024 * 1024)
+        ));
+        caches
+    });
+    let simd_width = SimdWidth::detect();
+    if args.cli.cpu_workload == CpuWorkload::Avx512
+        || args.cpu_phases.iter().any(|p| p.workload == CpuWorkload::Avx512)
+        || phase_specs.iter().any(|p| p.cpu_workload == Some(CpuWorkload::Avx512))
+    {
+        banner.push(format!(
+            "CPU SIMD: {} ({}-bit)",
+            simd_width.name(),
+            simd_width.bits()
+        ));
+    }
+
+    // Worker i runs on auto_numa_cpus[i % len] and allocates from its node.
+    let auto_numa_cpus: Option<Vec<usize>> = args.cli.cpu_affinity_auto_numa.then(|| {
+        let cpus = cpu_affinity.clone().or_else(read_online_cpus).unwrap_or_default();
+        if cpus.is_empty() {
+            eprintln!("Cannot read online CPUs for --cpu-affinity-auto-numa");
+            std::process::exit(2);
+        }
+        cpus
+    });
+    let auto_numa = |i: usize| {
+        auto_numa_cpus.as_ref().map(|cpus| {
+            let cpu = cpus[i % cpus.len()];
+            (cpu, read_cpu_node(cpu).unwrap_or(0))
+        })
+    };
+    if auto_numa_cpus.is_some() {
+        let list: Vec<String> = (0..cpu_workers)
+            .filter_map(auto_numa)
+            .enumerate()
+            .map(|(i, (cpu, node))| format!("w{}→cpu{}/node{}", i, cpu, node))
+            .collect();
+        banner.push(format!("CPU auto-NUMA: {}", list.join(" ")));
+    }
+
+    let per_worker_ops = args.cpu_ops_target.map(|t| t / cpu_workers.max(1) as f64);
+    if let (Some(total), Some(each)) = (args.cpu_ops_target, per_worker_ops) {
+        banner.push(format!(
+            "CPU ops target: {} ({} per worker)",
+            format_rate(total, "ops/s"),
+            format_rate(each, "ops/s")
+        ));
+    }
+
+    if let Some(ops) = args.cli.cpu_yield_after_ops {
+        banner.push(format!("CPU mode: yield every {} ops", ops));
     } else {
-        args.cpu_workers
+        match args.cpu_yield_strategy {
+            CpuYieldStrategy::None => banner.push("CPU mode: busy-wait (no sleeps)".to_string()),
+            CpuYieldStrategy::Yield => banner.push("CPU mode: yield after every burst".to_string()),
+            CpuYieldStrategy::Park => {
+                banner.push("CPU mode: park after every burst, woken each sample".to_string())
+            }
+            CpuYieldStrategy::Sleep => {}
+        }
+    }
+    if let Some(bytes) = args.thread_stack_size {
+        banner.push(format!(
+            "CPU thread stacks: {} x {} KB = {:.1} MB virtual",
+            cpu_workers,
+            bytes / 1024,
+            (cpu_workers * bytes) as f64 / (1024.0 * 1024.0)
+        ));
+    }
+
+    #[cfg(feature = "otlp")]
+    let otlp_provider = args.cli.otlp_endpoint.as_ref().and_then(|url| {
+        match otlp_out::install(url) {
+            Ok(provider) => {
+                banner.push(format!(
+                    "OTLP: exporting {:.1}% of disk I/Os and CPU bursts to {}",
+                    args.cli.otlp_sample_rate * 100.0,
+                    url
+                ));
+                Some(provider)
+            }
+            Err(e) => {
+                log::error!("Cannot set up OTLP export to {}: {}", url, e);
+                None
+            }
+        }
+    });
+    #[cfg(feature = "otlp")]
+    let span_rate = if otlp_provider.is_some() {
+        args.cli.otlp_sample_rate
+    } else {
+        0.0
+    };
+    #[cfg(not(feature = "otlp"))]
+    let span_rate = 0.0;
+
+    let spawn_cpu = |i: usize| {
+        let mut worker = CpuWorker::new()
+            .workload_control(cpu_control.clone())
+            .otel_sample_rate(span_rate);
+        if let Some(kb) = args.cli.cpu_memcpy_kb {
+            worker = worker.memcpy_bytes(kb as usize * 1024);
+        }
+        if let Some(caches) = cache_sizes {
+            worker = worker.cache_sizes(caches);
+        }
+        if let Some([int, fp, mem]) = args.cpu_mix {
+            worker = worker.mix(int, fp, mem);
+        }
+        if let Some(bytes) = args.thread_stack_size {
+            worker = worker.stack_size(bytes);
+        }
+        if let Some(ops) = args.cli.cpu_yield_after_ops {
+            worker = worker.yield_after_ops(ops);
+        } else {
+            worker = worker.yield_strategy(args.cpu_yield_strategy);
+        }
+        if let Some(rate) = per_worker_ops {
+            worker = worker.ops_target(rate);
+        }
+        if let Some((cpu, node)) = auto_numa(i) {
+            worker = worker.affinity(vec![cpu]).mem_node(node);
+        } else if let Some(cpus) = cpu_affinity.as_ref() {
+            worker = worker.affinity(cpus.clone());
+        }
+        worker.spawn()
+    };
+    let mem_workers = args.mem_worker_count;
+    let mem_nodes = if args.cli.mem_numa_interleave {
+        read_numa_nodes()
+    } else {
+        Vec::new()
+    };
+    if args.cli.mem_numa_interleave && mem_nodes.is_empty() {
+        eprintln!("Cannot read NUMA nodes for --mem-numa-interleave");
+        std::process::exit(2);
+    }
+    if let Some(gb_s) = args.mem_bandwidth_target.filter(|_| args.enable_mem) {
+        banner.push(format!(
+            "MEM bandwidth target: {:.2} GB/s ({:.2} GB/s per worker)",
+            gb_s,
+            gb_s / mem_workers as f64
+        ));
+    }
+    if let Some(rate) = args.mem_error_injection.filter(|_| args.enable_mem) {
+        banner.push(format!("MEM error injection: {} bit flips/s", rate));
+    }
+    if args.cli.mem_prefetch > 0 && args.enable_mem {
+        banner.push(format!("MEM prefetch distance: {} accesses", args.cli.mem_prefetch));
+    }
+    if args.enable_mem && args.mem_gc_pattern.is_none() {
+        banner.push(format!("MEM alloc: {}", args.cli.prealloc_strategy.name()));
+    }
+    if mem_workers > 1 {
+        banner.push(format!(
+            "MEM workers: {} x {} MB",
+            mem_workers,
+            mem_target / mem_workers as u64
+        ));
+    }
+    if !mem_nodes.is_empty() {
+        let list: Vec<String> = (0..mem_workers)
+            .map(|i| format!("w{}→node{}", i, i % mem_nodes.len()))
+            .collect();
+        banner.push(format!("MEM interleave: {}", list.join(" ")));
+    }
+
+    if let Some(cpus) = args.mem_worker_bind_cpu.as_ref().filter(|_| args.enable_mem) {
+        if let Some(&cpu) = read_online_cpus().and_then(|online| {
+            cpus.iter().find(|cpu| !online.contains(cpu))
+        }) {
+            eprintln!("CPU {} is not online (--mem-worker-bind-cpu)", cpu);
+            std::process::exit(2);
+        }
+        let list: Vec<String> =
+            cpus.iter().enumerate().map(|(i, cpu)| format!("w{}→cpu{}", i, cpu)).collect();
+        banner.push(format!("MEM worker CPUs: {}", list.join(" ")));
+    }
+
+    // Adjusted at runtime by the TUI's m/M keys.
+    let mem_live_target = Cell::new(mem_target);
+    let spawn_mem = |i: usize| {
+        let mut worker = MemWorker::new(mem_live_target.get() / mem_workers as u64);
+        if args.cli.mem_huge_pages {
+            worker = worker.huge_pages();
+        }
+        if args.cli.mem_zero {
+            worker = worker.zero_pages();
+        }
+        worker = worker
+            .alloc_strategy(args.cli.prealloc_strategy)
+            .prefetch_distance(args.cli.mem_prefetch);
+        if let Some(gb_s) = args.mem_bandwidth_target {
+            worker = worker.bandwidth_target(gb_s * GIB / mem_workers as f64);
+        }
+        if let Some(rate) = args.mem_error_injection {
+            worker = worker.error_injection(rate / mem_workers as f64);
+        }
+        if let Some(pattern) = args.mem_gc_pattern {
+            worker = worker.gc_pattern(pattern);
+        }
+        if let Some(sigfig) = args.latency_sigfig {
+            worker = worker.latency_histogram(sigfig);
+        }
+        if let (Some(node), Some(cpus)) = (args.cli.mem_numa_node, mem_numa_cpus.clone()) {
+            worker = worker.numa_node(node, cpus);
+        } else if !mem_nodes.is_empty() {
+            let node = i % mem_nodes.len();
+            worker = worker.numa_node(node, mem_nodes[node].clone());
+        }
+        if let Some(&cpu) = args.mem_worker_bind_cpu.as_ref().and_then(|cpus| cpus.get(i)) {
+            worker = worker.bind_cpu(cpu);
+        }
+        worker.spawn()
     };
 
-    let mem_total_mb = read_mem_total_mb();
-    let mut mem_target = if args.mem_mb > 0 {
-        args.mem_mb
-    } else if mem_total_mb > 0 {
-        (mem_total_mb as f64 * 0.60) as u64
-    } else {
-        512
-    };
-    if mem_target < 256 {
-        mem_target = 256;
+    let stress_dir = args.cli
+        .temp_dir
+        .clone()
+        .unwrap_or_else(|| env::temp_dir().join("stress_rust"));
+    let disk_path = args.cli
+        .disk_read_file
+        .clone()
+        .or_else(|| args.cli.disk_raw_device.clone())
+        .unwrap_or_else(|| stress_dir.join("stress_rust.dat"));
+    if let Some(dev) = args.cli.disk_raw_device.as_ref().filter(|_| disk_wanted) {
+        banner.push(format!(
+            "DSK RAW DEVICE {} ({:.1} GB, O_DIRECT) - existing data will be destroyed",
+            dev.display(),
+            disk_bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+        ));
     }
-    if mem_target > 16384 {
-        mem_target = 16384;
+    if let Some(mirror) = args.cli.disk_mirror.as_ref().filter(|_| disk_wanted) {
+        banner.push(format!("MIRROR {} <-> {}", disk_path.display(), mirror.display()));
     }
-
-    let disk_bytes = if args.enable_disk {
-        let gb = if args.disk_gb <= 0.0 { 1.0 } else { args.disk_gb };
-        (gb * 1024.0 * 1024.0 * 1024.0) as u64
-    } else {
-        0
+    // Read once: the scheduler only changes if someone writes to sysfs.
+    let disk_queue = disk_wanted
+        .then(|| block_device_name(&disk_path))
+        .flatten()
+        .and_then(|dev| {
+            let sched = read_io_scheduler(&dev)?;
+            Some(match read_nr_requests(&dev) {
+                Some(n) => format!("{} {}, nr_requests {}", dev, sched, n),
+                None => format!("{} {}", dev, sched),
+            })
+        });
+    if let Some(queue) = disk_queue.as_ref() {
+        banner.push(format!("DSK queue: {}", queue));
+    }
+    if let Some((every, duration)) = args.disk_stall.filter(|_| disk_wanted) {
+        banner.push(format!(
+            "DSK stall injection: {} ms every {} ops",
+            duration.as_millis(),
+            every
+        ));
+    }
+    if let Some(sla) = args.disk_latency_sla.filter(|_| disk_wanted) {
+        banner.push(format!(
+            "DSK latency SLA: {} us per write{}",
+            sla.as_micros(),
+            if args.cli.disk_sla_abort_pct < 100.0 {
+                format!(", abort above {}% violations", args.cli.disk_sla_abort_pct)
+            } else {
+                String::new()
+            }
+        ));
+    }
+    let mut cache_flush = args.cli.disk_cache_flush || args.disk_cache_flush_interval.is_some();
+    if cache_flush && disk_wanted && unsafe { geteuid() } != 0 {
+        log::warn!("--disk-cache-flush needs root to write /proc/sys/vm/drop_caches; skipping");
+        cache_flush = false;
+    }
+    let flush_before_spawn =
+        cache_flush && args.cli.disk_cache_flush && args.cli.disk_io_type != DiskIoType::Write;
+    let flush_cache = |when: &str| match drop_page_cache() {
+        Ok(()) => log::info!("DSK page cache dropped ({})", when),
+        Err(e) => log::warn!("DSK: cannot drop page cache: {}", e),
+    };
+    if cache_flush && disk_wanted {
+        let mut modes = Vec::new();
+        if flush_before_spawn {
+            modes.push("before disk workers start".to_string());
+        }
+        if let Some(every) = args.disk_cache_flush_interval {
+            modes.push(format!("every {}s", every.as_secs()));
+        }
+        if !modes.is_empty() {
+            banner.push(format!("DSK page cache flush: {}", modes.join(", ")));
+        }
+    }
+    // Returns the primary worker followed by the mirror, if any. Both share
+    // one seed so they write identical data at identical offsets.
+    let spawn_disk = || {
+        if let Err(e) = std::fs::create_dir_all(&stress_dir) {
+            log::error!("Disk worker: cannot create {}: {}", stress_dir.display(), e);
+            return Vec::new();
+        }
+        let mut worker = DiskWorker::new(disk_path.clone(), disk_bytes)
+            .fsync_interval(args.cli.disk_fsync_interval)
+            .seed(seed_from_time())
+            .otel_sample_rate(span_rate);
+        if let Some(trace) = disk_trace.as_ref() {
+            worker = worker.trace(Arc::clone(trace));
+        }
+        if let Some((every, duration)) = args.disk_stall {
+            worker = worker.stall(every, duration);
+        }
+        if let Some(sla) = args.disk_latency_sla {
+            worker = worker.latency_sla(sla);
+        }
+        if let Some(model) = args.cli.disk_latency_model {
+            worker = worker.latency_model(model);
+        }
+        if let Some(prio) = args.cli.disk_io_priority {
+            worker = worker.io_priority(prio);
+        }
+        if let Some(nice) = args.cli.disk_worker_nice {
+            worker = worker.nice(nice);
+        }
+        if let Some(iops) = args.disk_io_throttle {
+            worker = worker.iops_limit(iops as f64);
+        }
+        if args.cli.disk_rmw {
+            worker = worker.read_modify_write();
+        }
+        worker = worker
+            .io_pattern(args.cli.io_pattern)
+            .access_pattern(args.cli.disk_access_pattern, args.cli.zipf_s)
+            .aio_depth(args.cli.disk_aio_depth)
+            .compress(args.cli.disk_compress)
+            .io_type(args.cli.disk_io_type, args.cli.disk_read_pct);
+        if let Some(pattern) = args.disk_write_pattern.clone() {
+            worker = worker.write_pattern(pattern);
+        }
+        if args.cli.disk_pre_allocate {
+            worker = worker.pre_allocate();
+        }
+        if args.cli.disk_raw_device.is_some() {
+            worker = worker.direct_io();
+        }
+        if let Some(sigfig) = args.latency_sigfig {
+            worker = worker.latency_histogram(sigfig);
+        }
+        let mirror = args.cli.disk_mirror.as_ref().map(|path| worker.mirror(path.clone()));
+        std::iter::once(worker).chain(mirror).map(DiskWorker::spawn).collect::<Vec<_>>()
     };
 
-    let stop = Arc::new(AtomicBool::new(false));
-    let bytes_written = Arc::new(AtomicU64::new(0));
-    let cpu_ops = Arc::new(AtomicU64::new(0));
-    let mem_ops = Arc::new(AtomicU64::new(0));
-    let disk_ops = Arc::new(AtomicU64::new(0));
+    // Before any worker starts: inherited counters only follow new threads.
+    let mut perf_counters = args.cli.perf_counters.then(|| {
+        perf::PerfCounters::open().unwrap_or_else(|e| {
+            eprintln!("Cannot open --perf-counters: {}", e);
+            std::process::exit(2);
+        })
+    });
+    if perf_counters.is_some() {
+        banner.push("CPU perf counters: branch instructions/misses (all threads, user space)".to_string());
+    }
 
-    let mut handles = Vec::new();
-    for _ in 0..cpu_workers {
-        let s = Arc::clone(&stop);
-        let ops = Arc::clone(&cpu_ops);
-        handles.push(thread::spawn(move || cpu_worker_ops(s, ops)));
+    // Before any worker starts, so the whole run is measured under the cap.
+    let mut power_cap = args.cli.cpu_energy_cap.map(|watts| {
+        rapl::PowerCap::apply(watts).unwrap_or_else(|e| {
+            eprintln!("Cannot apply --cpu-energy-cap: {}", e);
+            std::process::exit(2);
+        })
+    });
+    if let Some(cap) = power_cap.as_ref() {
+        banner.push(format!("CPU energy cap: {} W (RAPL package 0)", cap.cap_w));
     }
 
-    if args.enable_mem {
-        let s = Arc::clone(&stop);
-        let ops = Arc::clone(&mem_ops);
-        handles.push(thread::spawn(move || mem_worker(s, mem_target, ops)));
+    let first_spec = phase_specs.first();
+    let initial_cpu = first_spec.and_then(|p| p.cpu_workers).unwrap_or(cpu_workers);
+    let mut cpu_handles: Vec<WorkerHandle<CpuWorkerStats>> =
+        (0..initial_cpu).map(&spawn_cpu).collect();
+    let mut mem_handles = Vec::new();
+    if first_spec.and_then(|p| p.mem).unwrap_or(args.enable_mem) {
+        mem_handles.extend((0..mem_workers).map(&spawn_mem));
+    }
+    let mut disk_handles = Vec::new();
+    if first_spec.and_then(|p| p.disk).unwrap_or(args.enable_disk) {
+        if flush_before_spawn {
+            flush_cache("start");
+        }
+        disk_handles.extend(spawn_disk());
     }
+    let mut disk_used = !disk_handles.is_empty();
 
-    let mut disk_path = None;
-    if args.enable_disk {
-        let dir = args
-            .temp_dir
-            .unwrap_or_else(|| env::temp_dir().join("stress_rust"));
-        if let Err(e) = std::fs::create_dir_all(&dir) {
-            eprintln!("Disk worker: cannot create {}: {}", dir.display(), e);
+    let mut net_handles = Vec::new();
+    if args.cli.net_workers_unix > 0 {
+        if let Err(e) = std::fs::create_dir_all(&stress_dir) {
+            log::error!("Net worker: cannot create {}: {}", stress_dir.display(), e);
+        } else {
+            let pid = std::process::id();
+            for i in 0..args.cli.net_workers_unix {
+                let path = stress_dir.join(format!("stress_rust_{}_{}.sock", pid, i));
+                let mut worker = NetWorker::unix(path);
+                if let Some(sigfig) = args.latency_sigfig {
+                    worker = worker.latency_histogram(sigfig);
+                }
+                net_handles.push(worker.spawn());
+            }
+            banner.push(format!(
+                "NET unix socket pairs: {} in {}",
+                net_handles.len(),
+                stress_dir.display()
+            ));
+        }
+    }
+    let mut udp_handles = Vec::new();
+    if args.cli.net_udp_workers > 0 {
+        if let Err(e) = std::fs::create_dir_all(&stress_dir) {
+            log::error!("Net worker: cannot create {}: {}", stress_dir.display(), e);
+        } else {
+            let pid = std::process::id();
+            for i in 0..args.cli.net_udp_workers {
+                let path = stress_dir.join(format!("stress_rust_{}_dgram_{}.sock", pid, i));
+                udp_handles.push(NetWorker::unix_dgram(path).spawn());
+            }
+            banner.push(format!(
+                "NET unix datagram pairs: {} in {}",
+                udp_handles.len(),
+                stress_dir.display()
+            ));
+        }
+    }
+    // The first handle is the accept loop, the rest connect to it.
+    let mut sock_handles = Vec::new();
+    if args.cli.socket_stress > 0 {
+        let bind = |host: &str| {
+            TcpListener::bind(host).and_then(|l| l.local_addr().map(|addr| (l, addr)))
+        };
+        let listener = if args.cli.net_ipv6 {
+            bind("[::1]:0").or_else(|e| {
+                log::warn!("Socket stress: cannot listen on ::1 ({}), using IPv4", e);
+                bind("127.0.0.1:0")
+            })
         } else {
-            let path = dir.join("stress_rust.dat");
-            let path_for_thread = path.clone();
-            let s = Arc::clone(&stop);
-            let bw = Arc::clone(&bytes_written);
-            let ops = Arc::clone(&disk_ops);
-            handles.push(thread::spawn(move || disk_worker(s, bw, ops, path_for_thread, disk_bytes)));
-            disk_path = Some(path);
+            bind("127.0.0.1:0")
+        };
+        match listener {
+            Ok((listener, addr)) => {
+                sock_handles.push(tcp_accept_server(listener));
+                for _ in 0..args.cli.socket_stress {
+                    let mut worker = NetWorker::tcp_connect(addr);
+                    if let Some(sigfig) = args.latency_sigfig {
+                        worker = worker.latency_histogram(sigfig);
+                    }
+                    sock_handles.push(worker.spawn());
+                }
+                banner.push(format!(
+                    "SOCK connect/close threads: {} against {}",
+                    args.cli.socket_stress, addr
+                ));
+            }
+            Err(e) => log::error!("Socket stress: cannot listen on loopback: {}", e),
         }
     }
 
-    let mut csv_file = args.csv_path.as_ref().and_then(|path| {
+    let matrix = MatrixWorker::new(args.cpu_matrix_n);
+    let mat_handles: Vec<WorkerHandle> =
+        (0..args.cli.cpu_matrix_workers).map(|_| matrix.clone().spawn()).collect();
+    if !mat_handles.is_empty() {
+        banner.push(format!(
+            "CPU matrix workers: {} x {}x{} f64",
+            mat_handles.len(),
+            args.cpu_matrix_n,
+            args.cpu_matrix_n
+        ));
+    }
+
+    let lat_handles: Vec<WorkerHandle> = if args.cli.mem_latency_test {
+        banner.push(format!(
+            "MEM latency test: pointer chasing over {} MB",
+            LATENCY_TEST_MB
+        ));
+        vec![LatencyWorker::new(LATENCY_TEST_MB).spawn()]
+    } else {
+        Vec::new()
+    };
+
+    let gpu_compute = args.cli.gpu_compute_stress.then(gpu_compute::GpuCompute::start);
+    match gpu_compute.as_ref() {
+        Some(Ok(gc)) => banner.push(format!("GPU compute: SAXPY on {} ({})", gc.device, gc.backend)),
+        Some(Err(e)) => banner.push(format!("GPU compute: not available ({})", e)),
+        None => {}
+    }
+    let mut prev_gpu_flops = 0u64;
+
+    let mut csv_file = args.cli.csv_path.as_ref().and_then(|path| {
         OpenOptions::new()
             .create(true)
             .append(true)
@@ -688,40 +2197,304 @@ fn main() {
     if let Some(f) = csv_file.as_mut() {
         let _ = writeln!(
             f,
-            "ts,cpu_pct,mem_pct,disk_mb_s,cpu_ops_s,mem_ops_s,disk_iops,temp_c,cpu_freq_ghz,load1,load5,load15,mem_used_mb,mem_total_mb,gpu_util,gpu_mem_util,gpu_temp_c,gpu_sm_clock_mhz,gpu_mem_clock_mhz"
+            "ts,cpu_pct,mem_pct,disk_mb_s,cpu_ops_s,mem_ops_s,disk_iops,temp_c,cpu_freq_ghz,load1,load5,load15,mem_used_mb,mem_total_mb,gpu_util,gpu_mem_util,gpu_temp_c,gpu_sm_clock_mhz,gpu_mem_clock_mhz,mem_alloc_fail_rate,numa_page_mig_s,numa_hit_ratio,net_mb_s,net_ops_s,mem_hugepages_used,pgflt_minor_s,pgflt_major_s,irq_s,softirq_s,throttle_events_s{}",
+            [
+                (args.cli.csv_ema, ",cpu_pct_ema,mem_pct_ema,disk_mb_s_ema"),
+                (!udp_handles.is_empty(), ",udp_dgram_s,udp_drops_s"),
+                (args.disk_stall.is_some(), ",disk_stall_count"),
+                (args.disk_latency_sla.is_some(), ",disk_sla_violation_pct"),
+                (args.cli.disk_worker_nice.is_some(), ",disk_nice_level"),
+            ]
+            .iter()
+            .filter(|(on, _)| *on)
+            .map(|(_, cols)| *cols)
+            .collect::<String>()
         );
     }
 
+    #[cfg(feature = "proto")]
+    let mut proto_file = args.cli.output_proto.as_ref().and_then(|path| {
+        proto_out::ProtoWriter::create(path)
+            .map_err(|e| log::error!("Cannot open {}: {}", path.display(), e))
+            .ok()
+    });
+
     let width = args.graph_width.max(20);
     let height = args.graph_height.max(4);
-    let mut cpu_hist = vec![0.0f64; width];
-    let mut mem_hist = vec![0.0f64; width];
-    let mut disk_hist = vec![0.0f64; width];
-    let mut cpu_ops_hist = vec![0.0f64; width];
-    let mut mem_ops_hist = vec![0.0f64; width];
-    let mut disk_ops_hist = vec![0.0f64; width];
+    let mut sample = Duration::from_millis(args.cli.sample_ms.max(MIN_SAMPLE_MS));
+    let window_ms = if args.cli.graph_window_s > 0 {
+        args.cli.graph_window_s * 1000
+    } else {
+        width as u64 * 1000
+    };
+    let hist_len = width.max(window_ms.div_ceil(sample.as_millis() as u64) as usize);
+    let mut cpu_hist = vec![0.0f64; hist_len];
+    let mut mem_hist = vec![0.0f64; hist_len];
+    let mut disk_hist = vec![0.0f64; hist_len];
+    let mut cpu_ops_hist = vec![0.0f64; hist_len];
+    let mut cpy_hist = vec![0.0f64; hist_len];
+    let mut mem_ops_hist = vec![0.0f64; hist_len];
+    let mut disk_ops_hist = vec![0.0f64; hist_len];
     let mut head = 0usize;
     let mut disk_peak = 1.0f64;
     let mut filled = 0usize;
 
     let mut prev_cpu = read_cpu_times();
-    let mut prev_bytes = bytes_written.load(Ordering::Relaxed);
-    let mut prev_cpu_ops = cpu_ops.load(Ordering::Relaxed);
-    let mut prev_mem_ops = mem_ops.load(Ordering::Relaxed);
-    let mut prev_disk_ops = disk_ops.load(Ordering::Relaxed);
+    let mut prev_bytes = total_bytes(&disk_handles);
+    let mut prev_cpu_ops = total_ops(&cpu_handles);
+    let mut prev_cpu_mix = total_mix_ops(&cpu_handles);
+    let mut prev_cpy_bytes = total_bytes(&cpu_handles);
+    let mut prev_mem_ops = total_ops(&mem_handles);
+    let mut prev_mem_bytes = total_bytes(&mem_handles);
+    let mut prev_allocs = 0u64;
+    let mut prev_numa = if args.cli.mem_numa_monitor {
+        read_numa_stats()
+    } else {
+        None
+    };
+    let mut prev_alloc_fails = 0u64;
+    let mut prev_pgfault = read_vmstat_pgfault();
+    let mut prev_irq = read_irq_stats();
+    let mut prev_throttle = read_throttle_events();
+    let mut throttle_streak = 0usize;
+    let mut prev_ctxt = read_context_switches();
+    let mut prev_disk_ops = total_ops(&disk_handles);
+    let mut prev_path_bytes: Vec<u64> = Vec::new();
+    let mut prev_net_bytes = total_bytes(&net_handles);
+    let mut prev_net_ops = total_ops(&net_handles);
+    let mut prev_udp_ops = total_ops(&udp_handles);
+    let mut prev_udp_drops = 0u64;
+    let mut prev_disk_stalls = 0u64;
+    let mut prev_sock_conns = total_ops(&sock_handles);
+    let mut prev_mat_ops = total_ops(&mat_handles);
+    let mut prev_lat_ops = total_ops(&lat_handles);
+    let mut prev_sock_drops = 0u64;
+    let mut prev_sock_errors = 0u64;
+    let mut prev_fsyncs = 0u64;
+    let mut prev_read_bytes = 0u64;
+    let mut prev_phys_bytes = 0u64;
+    let mut prev_fsync_lat_us = 0u64;
     let start = Instant::now();
-    let sample = Duration::from_millis(args.sample_ms.max(200));
     let mut next_tick = Instant::now();
+    let mut adaptive = args.cli.adaptive_sample.then(|| {
+        let min_ms = if args.cli.min_sample_ms > 0 {
+            args.cli.min_sample_ms
+        } else {
+            args.cli.sample_ms
+        };
+        AdaptiveSampler::new(Duration::from_millis(min_ms.max(MIN_SAMPLE_MS)), sample)
+    });
+    let graph_refresh = Duration::from_millis(args.graph_refresh_ms.max(MIN_SAMPLE_MS));
+    let mut next_render = start;
+
+    let tui_layout = args
+        .tui_layout
+        .clone()
+        .unwrap_or_else(|| tui_layout::DEFAULT_LAYOUT.to_vec());
 
     let is_tty = io::stdout().is_terminal();
+    if !is_tty {
+        for line in &banner {
+            log::info!("{line}");
+        }
+    }
+    let mut notices = Notices::new(is_tty);
+    let mut keys = if is_tty { KeyReader::enable() } else { None };
+    let mut paused = false;
+    let mut disk_fill_done = false;
+    let mut mem_fault_reported = false;
+    let mut disk_prealloc_reported = false;
+    let mut cpu_stop = args.cli
+        .stop_on_cpu_pct
+        .map(|t| StopCondition::new("cpu pct", t, args.stop_sustain_samples));
+    let mut mem_stop = args.cli
+        .stop_on_mem_pct
+        .map(|t| StopCondition::new("mem pct", t, args.stop_sustain_samples));
+    let mut phase_idx = 0usize;
+    let mut phase_start = Instant::now();
+    if let Some(spec) = phase_specs.first() {
+        notices.push(format!("PHASE 1 START: {}", spec.description));
+    }
+    let mut summary = RunSummary::default();
+    summary.cpu_freq_max_khz = read_cpufreq_avg_khz("scaling_max_freq");
+    summary.cpu_freq_base_khz = read_cpufreq_avg_khz("base_frequency");
+    let ema_alpha = args.cli.ema_alpha.unwrap_or(0.2);
+    let mut ema: Option<(f64, f64, f64)> = None;
+    #[cfg(feature = "latency_histogram")]
+    let mut latency_export = args.cli
+        .latency_histogram_export
+        .as_ref()
+        .map(|_| LatencyExport::new(std::time::SystemTime::now() - start.elapsed()));
+    #[cfg(not(feature = "latency_histogram"))]
+    let mut latency_export: Option<LatencyExport> = None;
+    let mut worker_error: Option<String> = None;
+    let mut sla_breach: Option<String> = None;
+    // (violations, timed writes) at the start of the current abort window.
+    let mut sla_window_start = (0u64, 0u64);
+    let mut alerter = args.cli.alert_cmd.clone().map(Alerter::new);
+
+    let mut next_cache_flush = args
+        .disk_cache_flush_interval
+        .filter(|_| cache_flush && disk_wanted)
+        .map(|every| start + every);
 
     loop {
+        if let Some(path) = args.cli.heartbeat_file.as_ref() {
+            touch_heartbeat(path);
+        }
+        if let (Some(at), Some(every)) = (next_cache_flush, args.disk_cache_flush_interval) {
+            if Instant::now() >= at {
+                flush_cache("interval");
+                next_cache_flush = Some(at + every);
+            }
+        }
+        if args.cli.exit_after_first_error {
+            worker_error = first_worker_error(&mem_handles)
+                .or_else(|| first_worker_error(&disk_handles))
+                .or_else(|| first_worker_error(&net_handles))
+                .or_else(|| first_worker_error(&udp_handles))
+                .or_else(|| first_worker_error(&sock_handles));
+            if let Some(msg) = worker_error.as_ref() {
+                log::error!("WORKER ERROR at T={}s: {}", start.elapsed().as_secs(), msg);
+                if let Some(alerter) = alerter.as_mut() {
+                    let errors = total_errors(&mem_handles)
+                        + total_errors(&disk_handles)
+                        + total_errors(&net_handles)
+                        + total_errors(&udp_handles)
+                        + total_errors(&sock_handles);
+                    alerter.fire("worker_error", errors.to_string());
+                }
+                break;
+            }
+        }
         if GLOBAL_STOP.load(Ordering::Relaxed) {
             break;
         }
-        if args.duration_s > 0 && start.elapsed().as_secs() >= args.duration_s {
+        if args.cli.duration > 0 && start.elapsed().as_secs() >= args.cli.duration {
             break;
         }
+        if let Some(phase) = args.cpu_phases.get(phase_idx) {
+            let phase_len = Duration::from_secs(phase.duration_s);
+            if phase_start.elapsed() >= phase_len {
+                let next = phase_idx + 1;
+                if next >= args.cpu_phases.len() && !args.cli.cpu_phase_loop {
+                    log::info!("PHASES COMPLETE at T={}s", start.elapsed().as_secs());
+                    break;
+                }
+                phase_idx = next % args.cpu_phases.len();
+                let new = args.cpu_phases[phase_idx].workload;
+                cpu_control.set(new);
+                phase_start += phase_len;
+                notices.push(format!(
+                    "PHASE CHANGE: {} → {} at T={}s",
+                    phase.workload.name(),
+                    new.name(),
+                    start.elapsed().as_secs()
+                ));
+            }
+        }
+
+        if let Some(spec) = phase_specs.get(phase_idx) {
+            let phase_len = Duration::from_secs(spec.duration_s);
+            if phase_start.elapsed() >= phase_len {
+                phase_idx += 1;
+                let Some(next) = phase_specs.get(phase_idx) else {
+                    log::info!("PHASES COMPLETE at T={}s", start.elapsed().as_secs());
+                    break;
+                };
+                phase_start += phase_len;
+                // Rates of a shrunk subsystem under-report for one sample.
+                let want_cpu = next.cpu_workers.unwrap_or(cpu_workers);
+                while cpu_handles.len() > want_cpu {
+                    if let Some(h) = cpu_handles.pop() {
+                        h.stop();
+                        summary.cpu_worker_stats.extend(h.join());
+                    }
+                }
+                while cpu_handles.len() < want_cpu {
+                    cpu_handles.push(spawn_cpu(cpu_handles.len()));
+                }
+                cpu_control.set(next.cpu_workload.unwrap_or(args.cli.cpu_workload));
+                let want_mem = next.mem.unwrap_or(args.enable_mem);
+                if !want_mem {
+                    absorb_latency(&mut latency_export, "mem_op", &mem_handles);
+                    stop_all(&mut mem_handles);
+                } else if mem_handles.is_empty() {
+                    mem_handles.extend((0..mem_workers).map(&spawn_mem));
+                }
+                let want_disk = next.disk.unwrap_or(args.enable_disk);
+                if !want_disk {
+                    absorb_latency(&mut latency_export, "disk_write", &disk_handles);
+                    stop_all(&mut disk_handles);
+                } else if disk_handles.is_empty() {
+                    if flush_before_spawn {
+                        flush_cache("phase start");
+                    }
+                    disk_handles.extend(spawn_disk());
+                    disk_used |= !disk_handles.is_empty();
+                }
+                notices.push(format!("PHASE {} START: {}", phase_idx + 1, next.description));
+            }
+        }
+
+        for key in keys.as_mut().map(KeyReader::poll).unwrap_or_default() {
+            match key {
+                Key::Pause if !paused => {
+                    paused = true;
+                    notices.push("PAUSED: workers parked, r to resume".to_string());
+                }
+                Key::Resume if paused => {
+                    paused = false;
+                    notices.push("RESUMED".to_string());
+                }
+                Key::AddCpu => {
+                    cpu_handles.push(spawn_cpu(cpu_handles.len()));
+                    notices.push(format!("CPU workers: {}", cpu_handles.len()));
+                }
+                Key::RemoveCpu if cpu_handles.len() > 1 => {
+                    if let Some(h) = cpu_handles.pop() {
+                        h.stop();
+                        summary.cpu_worker_stats.extend(h.join());
+                    }
+                    notices.push(format!("CPU workers: {}", cpu_handles.len()));
+                }
+                Key::MemUp | Key::MemDown => {
+                    let cur = mem_live_target.get();
+                    let next = if key == Key::MemUp { cur * 11 / 10 } else { cur * 9 / 10 };
+                    mem_live_target.set(next.clamp(256, 16384));
+                    // Workers size their buffers at start; restart them.
+                    if !mem_handles.is_empty() {
+                        absorb_latency(&mut latency_export, "mem_op", &mem_handles);
+                        stop_all(&mut mem_handles);
+                        mem_handles.extend((0..mem_workers).map(&spawn_mem));
+                    }
+                    notices.push(format!("MEM target: {} MB", mem_live_target.get()));
+                }
+                Key::ToggleDisk if disk_handles.is_empty() => {
+                    if flush_before_spawn {
+                        flush_cache("disk on");
+                    }
+                    disk_handles.extend(spawn_disk());
+                    disk_used |= !disk_handles.is_empty();
+                    notices.push("DSK workers: on".to_string());
+                }
+                Key::ToggleDisk => {
+                    absorb_latency(&mut latency_export, "disk_write", &disk_handles);
+                    stop_all(&mut disk_handles);
+                    notices.push("DSK workers: off".to_string());
+                }
+                _ => {}
+            }
+            // Re-applied every key so workers started while paused park too.
+            set_paused(&cpu_handles, paused);
+            set_paused(&mem_handles, paused);
+            set_paused(&disk_handles, paused);
+            set_paused(&net_handles, paused);
+            set_paused(&udp_handles, paused);
+            set_paused(&sock_handles, paused);
+            set_paused(&mat_handles, paused);
+            set_paused(&lat_handles, paused);
+        }
 
         let now = Instant::now();
         if now < next_tick {
@@ -734,7 +2507,9 @@ fn main() {
             let idle = cur.1.saturating_sub(prev.1);
             prev_cpu = Some(cur);
             if total > 0 {
-                100.0 * (total - idle) as f64 / total as f64
+                let pct = 100.0 * (total - idle) as f64 / total as f64;
+                summary.record_cpu(pct);
+                pct
             } else {
                 0.0
             }
@@ -754,7 +2529,12 @@ fn main() {
         let mem_total_mb = mem_total_kb / 1024;
         let mem_used_mb = mem_used_kb / 1024;
 
-        let cur_bytes = bytes_written.load(Ordering::Relaxed);
+        // Reads count towards the chart once the worker issues them.
+        let cur_bytes = total_bytes(&disk_handles)
+            + match args.cli.disk_io_type {
+                DiskIoType::Write => 0,
+                _ => disk_handles.iter().map(WorkerHandle::bytes_read).sum(),
+            };
         let delta_bytes = cur_bytes.saturating_sub(prev_bytes);
         prev_bytes = cur_bytes;
         let disk_mb_s = delta_bytes as f64 / (1024.0 * 1024.0) / sample.as_secs_f64();
@@ -762,69 +2542,399 @@ fn main() {
             disk_peak = disk_mb_s;
         }
 
-        let cur_cpu_ops = cpu_ops.load(Ordering::Relaxed);
+        if args.cpu_yield_strategy == CpuYieldStrategy::Park {
+            for h in &cpu_handles {
+                h.unpark();
+            }
+        }
+        let cur_cpu_ops = total_ops(&cpu_handles);
         let delta_cpu_ops = cur_cpu_ops.saturating_sub(prev_cpu_ops);
         prev_cpu_ops = cur_cpu_ops;
         let cpu_ops_s = delta_cpu_ops as f64 / sample.as_secs_f64();
+        let cpu_branch = perf_counters.as_mut().and_then(perf::PerfCounters::branch_delta).map(
+            |(branches, misses)| {
+                let miss_pct = 100.0 * misses as f64 / branches.max(1) as f64;
+                (branches as f64 / sample.as_secs_f64(), miss_pct)
+            },
+        );
+        let cur_cpu_mix = total_mix_ops(&cpu_handles);
+        let cpu_mix_s: [f64; 3] = std::array::from_fn(|i| {
+            cur_cpu_mix[i].saturating_sub(prev_cpu_mix[i]) as f64 / sample.as_secs_f64()
+        });
+        prev_cpu_mix = cur_cpu_mix;
+        let cur_mat_ops = total_ops(&mat_handles);
+        let mat_ops_s = cur_mat_ops.saturating_sub(prev_mat_ops) as f64 / sample.as_secs_f64();
+        prev_mat_ops = cur_mat_ops;
+        let cur_lat_ops = total_ops(&lat_handles);
+        let lat_hops_s = cur_lat_ops.saturating_sub(prev_lat_ops) as f64 / sample.as_secs_f64();
+        prev_lat_ops = cur_lat_ops;
+        let mem_lat_ns = (lat_hops_s > 0.0).then(|| 1e9 / lat_hops_s);
+        let gpu_gflops = match gpu_compute.as_ref() {
+            Some(Ok(gc)) => {
+                let cur = gc.flops();
+                let rate = cur.saturating_sub(prev_gpu_flops) as f64 / 1e9 / sample.as_secs_f64();
+                prev_gpu_flops = cur;
+                rate
+            }
+            _ => 0.0,
+        };
+        let cur_cpy_bytes = total_bytes(&cpu_handles);
+        let cpy_gb_s =
+            cur_cpy_bytes.saturating_sub(prev_cpy_bytes) as f64 / GIB / sample.as_secs_f64();
+        prev_cpy_bytes = cur_cpy_bytes;
 
-        let cur_mem_ops = mem_ops.load(Ordering::Relaxed);
+        let cur_mem_ops = total_ops(&mem_handles);
         let delta_mem_ops = cur_mem_ops.saturating_sub(prev_mem_ops);
         prev_mem_ops = cur_mem_ops;
         let mem_ops_s = delta_mem_ops as f64 / sample.as_secs_f64();
+        let cur_mem_bytes = total_bytes(&mem_handles);
+        let mem_gb_s =
+            cur_mem_bytes.saturating_sub(prev_mem_bytes) as f64 / GIB / sample.as_secs_f64();
+        prev_mem_bytes = cur_mem_bytes;
+
+        let mem_huge_pages: u64 = mem_handles.iter().map(WorkerHandle::huge_pages_used).sum();
+        // Workers fault in parallel, so the slowest one is the fault time.
+        let mem_fault: Option<Vec<Duration>> =
+            mem_handles.iter().map(WorkerHandle::mem_fault_duration).collect();
+        if let Some(max) = mem_fault.and_then(|d| d.into_iter().max()) {
+            if !mem_fault_reported {
+                notices.push(format!(
+                    "MEM mem_fault_duration_s={:.3} ({}, {})",
+                    max.as_secs_f64(),
+                    args.cli.prealloc_strategy.name(),
+                    if args.cli.mem_zero { "zero, first write" } else { "prefault" }
+                ));
+                mem_fault_reported = true;
+            }
+        }
+        let cur_allocs: u64 = mem_handles.iter().map(WorkerHandle::alloc_attempts).sum();
+        let cur_alloc_fails: u64 = mem_handles.iter().map(WorkerHandle::alloc_failures).sum();
+        let delta_allocs = cur_allocs.saturating_sub(prev_allocs);
+        let delta_alloc_fails = cur_alloc_fails.saturating_sub(prev_alloc_fails);
+        prev_allocs = cur_allocs;
+        prev_alloc_fails = cur_alloc_fails;
+        let mem_alloc_fail_rate = if delta_allocs > 0 {
+            delta_alloc_fails as f64 / delta_allocs as f64
+        } else {
+            0.0
+        };
+
+        // Primary and mirror MB/s, when mirroring.
+        let cur_path_bytes: Vec<u64> =
+            disk_handles.iter().map(WorkerHandle::bytes_transferred).collect();
+        let path_mb_s: Vec<f64> = cur_path_bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                let prev = prev_path_bytes.get(i).copied().unwrap_or(0);
+                b.saturating_sub(prev) as f64 / (1024.0 * 1024.0) / sample.as_secs_f64()
+            })
+            .collect();
+        prev_path_bytes = cur_path_bytes;
+
+        // zip-sequentially: (fill finished, fill progress 0..1) of the primary.
+        let disk_fill = disk_handles
+            .first()
+            .filter(|_| args.cli.io_pattern == IoPattern::ZipSequentially)
+            .map(|h| {
+                let frac = h.bytes_transferred() as f64 / disk_bytes.max(1) as f64;
+                (h.disk_filled(), frac.min(1.0))
+            });
+        if let Some(took) = disk_handles.first().and_then(WorkerHandle::disk_prealloc_duration) {
+            if !disk_prealloc_reported {
+                notices.push(format!("DSK pre-alloc took {:.3}s", took.as_secs_f64()));
+                disk_prealloc_reported = true;
+            }
+        }
+        let fill_done = disk_fill.is_some_and(|(done, _)| done);
+        if fill_done && !disk_fill_done {
+            notices.push(format!("DSK FILL → OVR at T={}s", start.elapsed().as_secs()));
+        }
+        disk_fill_done = fill_done;
 
-        let cur_disk_ops = disk_ops.load(Ordering::Relaxed);
+        let cur_disk_ops = total_ops(&disk_handles);
         let delta_disk_ops = cur_disk_ops.saturating_sub(prev_disk_ops);
         prev_disk_ops = cur_disk_ops;
         let disk_iops = delta_disk_ops as f64 / sample.as_secs_f64();
 
+        let cur_net_bytes = total_bytes(&net_handles);
+        let cur_net_ops = total_ops(&net_handles);
+        let net_mb_s = cur_net_bytes.saturating_sub(prev_net_bytes) as f64
+            / (1024.0 * 1024.0)
+            / sample.as_secs_f64();
+        let net_ops_s = cur_net_ops.saturating_sub(prev_net_ops) as f64 / sample.as_secs_f64();
+        prev_net_bytes = cur_net_bytes;
+        prev_net_ops = cur_net_ops;
+
+        let disk_stalls: u64 = disk_handles.iter().map(WorkerHandle::disk_stall_count).sum();
+        let disk_stalled = disk_stalls > prev_disk_stalls;
+        prev_disk_stalls = disk_stalls;
+        let sla_counts = args.disk_latency_sla.map(|_| {
+            let violations: u64 =
+                disk_handles.iter().map(WorkerHandle::disk_sla_violation_count).sum();
+            let checked: u64 = disk_handles.iter().map(WorkerHandle::disk_sla_checked_count).sum();
+            (violations, checked)
+        });
+        let disk_sla_pct = sla_counts.map(|(violations, checked)| {
+            if checked > 0 {
+                violations as f64 / checked as f64 * 100.0
+            } else {
+                0.0
+            }
+        });
+        // The abort threshold applies to recent writes, not the whole run.
+        let mut window_pct = None;
+        if let Some((violations, checked)) = sla_counts {
+            let window_checked = checked.saturating_sub(sla_window_start.1);
+            if window_checked >= SLA_WINDOW_MIN_OPS {
+                let window_violations = violations.saturating_sub(sla_window_start.0);
+                window_pct = Some(window_violations as f64 / window_checked as f64 * 100.0);
+                sla_window_start = (violations, checked);
+            }
+        }
+        if let Some(pct) = window_pct.filter(|&p| p > args.cli.disk_sla_abort_pct) {
+            if sla_breach.is_none() {
+                let msg = format!(
+                    "DISK SLA BREACH at T={}s: {:.2}% of recent disk writes over {} us (limit {}%)",
+                    start.elapsed().as_secs(),
+                    pct,
+                    args.disk_latency_sla.unwrap_or_default().as_micros(),
+                    args.cli.disk_sla_abort_pct
+                );
+                notices.push(msg.clone());
+                sla_breach = Some(msg);
+                GLOBAL_STOP.store(true, Ordering::Relaxed);
+            }
+        }
+
+        let cur_udp_ops = total_ops(&udp_handles);
+        let cur_udp_drops: u64 = udp_handles.iter().map(WorkerHandle::drop_count).sum();
+        let udp_dgram_s = cur_udp_ops.saturating_sub(prev_udp_ops) as f64 / sample.as_secs_f64();
+        let udp_drops_s =
+            cur_udp_drops.saturating_sub(prev_udp_drops) as f64 / sample.as_secs_f64();
+        prev_udp_ops = cur_udp_ops;
+        prev_udp_drops = cur_udp_drops;
+
+        let cur_sock_conns = total_ops(&sock_handles);
+        let cur_sock_drops: u64 = sock_handles.iter().map(WorkerHandle::drop_count).sum();
+        let sock_conn_s =
+            cur_sock_conns.saturating_sub(prev_sock_conns) as f64 / sample.as_secs_f64();
+        let sock_drops_s =
+            cur_sock_drops.saturating_sub(prev_sock_drops) as f64 / sample.as_secs_f64();
+        prev_sock_conns = cur_sock_conns;
+        prev_sock_drops = cur_sock_drops;
+        let cur_sock_errors: u64 =
+            sock_handles.iter().map(WorkerHandle::connect_error_count).sum();
+        let sock_errors_s =
+            cur_sock_errors.saturating_sub(prev_sock_errors) as f64 / sample.as_secs_f64();
+        prev_sock_errors = cur_sock_errors;
+        let sock_timewait = if sock_handles.is_empty() {
+            None
+        } else {
+            read_sockstat_timewait()
+        };
+
+        let cur_read_bytes: u64 = disk_handles.iter().map(WorkerHandle::bytes_read).sum();
+        let disk_read_mb_s = cur_read_bytes.saturating_sub(prev_read_bytes) as f64
+            / (1024.0 * 1024.0)
+            / sample.as_secs_f64();
+        prev_read_bytes = cur_read_bytes;
+
+        let cur_phys_bytes: u64 = disk_handles.iter().map(WorkerHandle::bytes_physical).sum();
+        let disk_phys_mb_s = cur_phys_bytes.saturating_sub(prev_phys_bytes) as f64
+            / (1024.0 * 1024.0)
+            / sample.as_secs_f64();
+        prev_phys_bytes = cur_phys_bytes;
+
+        let cur_fsyncs: u64 = disk_handles.iter().map(WorkerHandle::fsync_count).sum();
+        let cur_fsync_lat_us: u64 = disk_handles.iter().map(WorkerHandle::fsync_latency_us).sum();
+        let delta_fsyncs = cur_fsyncs.saturating_sub(prev_fsyncs);
+        let delta_fsync_lat_us = cur_fsync_lat_us.saturating_sub(prev_fsync_lat_us);
+        prev_fsyncs = cur_fsyncs;
+        prev_fsync_lat_us = cur_fsync_lat_us;
+        let fsync_avg_us = if delta_fsyncs > 0 {
+            delta_fsync_lat_us as f64 / delta_fsyncs as f64
+        } else {
+            0.0
+        };
+
+        let cur_numa = if args.cli.mem_numa_monitor {
+            read_numa_stats()
+        } else {
+            None
+        };
+        let (numa_mig_s, numa_hit_ratio) = match (prev_numa, cur_numa) {
+            (Some(prev), Some(cur)) => {
+                prev_numa = Some(cur);
+                let hits = cur.1.saturating_sub(prev.1);
+                let misses = cur.2.saturating_sub(prev.2);
+                let ratio = if hits + misses > 0 {
+                    hits as f64 / (hits + misses) as f64
+                } else {
+                    1.0
+                };
+                (cur.0.saturating_sub(prev.0) as f64 / sample.as_secs_f64(), ratio)
+            }
+            (_, cur) => {
+                prev_numa = cur;
+                (-1.0, -1.0)
+            }
+        };
+
+        let cur_pgfault = read_vmstat_pgfault();
+        let (pgflt_minor_s, pgflt_major_s) = match (prev_pgfault, cur_pgfault) {
+            (Some(prev), Some(cur)) => (
+                cur.0.saturating_sub(prev.0) as f64 / sample.as_secs_f64(),
+                cur.1.saturating_sub(prev.1) as f64 / sample.as_secs_f64(),
+            ),
+            _ => (-1.0, -1.0),
+        };
+        prev_pgfault = cur_pgfault;
+
+        let cur_irq = read_irq_stats();
+        let (irq_s, softirq_s) = match (prev_irq, cur_irq) {
+            (Some(prev), Some(cur)) => (
+                cur.0.saturating_sub(prev.0) as f64 / sample.as_secs_f64(),
+                cur.1.saturating_sub(prev.1) as f64 / sample.as_secs_f64(),
+            ),
+            _ => (-1.0, -1.0),
+        };
+        prev_irq = cur_irq;
+
+        let cur_throttle = read_throttle_events();
+        let throttle_s = match (prev_throttle, cur_throttle) {
+            (Some(prev), Some(cur)) => cur.saturating_sub(prev) as f64 / sample.as_secs_f64(),
+            _ => -1.0,
+        };
+        prev_throttle = cur_throttle;
+        let power_w = power_cap.as_mut().and_then(rapl::PowerCap::power_w);
+        if throttle_s > THROTTLE_WARN_RATE {
+            throttle_streak += 1;
+        } else {
+            throttle_streak = 0;
+        }
+        if throttle_streak == THROTTLE_WARN_SAMPLES {
+            notices.push(format!(
+                "WARNING: CPU THERMAL THROTTLE ACTIVE ({:.1} events/s for {} samples)",
+                throttle_s, THROTTLE_WARN_SAMPLES
+            ));
+        }
+
+        let cur_ctxt = read_context_switches();
+        let ctxt_s = match (prev_ctxt, cur_ctxt) {
+            (Some(prev), Some(cur)) => cur.saturating_sub(prev) as f64 / sample.as_secs_f64(),
+            _ => -1.0,
+        };
+        prev_ctxt = cur_ctxt;
+
         let temp_opt = read_temp_c();
-        let temp_c = temp_opt.unwrap_or(-1.0);
+        let temp_c = temp_opt.as_ref().map(|t| t.0).unwrap_or(-1.0);
         let freq_opt = read_cpu_freq_khz();
+        if let Some(khz) = freq_opt {
+            summary.record_cpu_freq(khz, sample.as_secs_f64());
+        }
         let freq_ghz = freq_opt.map(|v| v as f64 / 1_000_000.0).unwrap_or(-1.0);
         let load_opt = read_loadavg();
         let (load1, load5, load15) = load_opt.unwrap_or((0.0, 0.0, 0.0));
-        let gpu_opt = args
-            .gpu_status_path
-            .as_ref()
-            .and_then(|p| read_gpu_status(p));
+        let gpu_opt = gpu.read();
         let (gpu_util, gpu_mem_util, gpu_temp, gpu_sm, gpu_memclk) =
             gpu_opt.unwrap_or((-1.0, -1.0, -1.0, -1.0, -1.0));
 
+        let (cpu_ema, mem_ema, disk_ema) = match ema {
+            Some((c, m, d)) => (
+                ema_update(c, cpu_pct, ema_alpha),
+                ema_update(m, mem_pct, ema_alpha),
+                ema_update(d, disk_mb_s, ema_alpha),
+            ),
+            None => (cpu_pct, mem_pct, disk_mb_s),
+        };
+        ema = Some((cpu_ema, mem_ema, disk_ema));
+        let mut csv_extra = if args.cli.csv_ema {
+            format!(",{:.2},{:.2},{:.2}", cpu_ema, mem_ema, disk_ema)
+        } else {
+            String::new()
+        };
+        if !udp_handles.is_empty() {
+            csv_extra.push_str(&format!(",{:.1},{:.1}", udp_dgram_s, udp_drops_s));
+        }
+        if args.disk_stall.is_some() {
+            csv_extra.push_str(&format!(",{}", disk_stalls));
+        }
+        if let Some(pct) = disk_sla_pct {
+            csv_extra.push_str(&format!(",{:.3}", pct));
+        }
+        if let Some(nice) = args.cli.disk_worker_nice {
+            csv_extra.push_str(&format!(",{}", nice));
+        }
+
         cpu_hist[head] = cpu_pct;
         mem_hist[head] = mem_pct;
         disk_hist[head] = disk_mb_s;
         cpu_ops_hist[head] = cpu_ops_s;
+        cpy_hist[head] = cpy_gb_s;
         mem_ops_hist[head] = mem_ops_s;
         disk_ops_hist[head] = disk_iops;
-        head = (head + 1) % width;
-        if filled < width {
+        head = (head + 1) % hist_len;
+        if filled < hist_len {
             filled += 1;
         }
 
-        if is_tty {
+        let now = Instant::now();
+        let redraw = now >= next_render;
+        if is_tty && !redraw {
+            print!(
+                "\r\x1b[2KLIVE cpu {:5.1}%  mem {:5.1}%  dsk {:7.1}MB/s  {}",
+                cpu_pct,
+                mem_pct,
+                disk_mb_s,
+                format_rate(cpu_ops_s, "ops/s")
+            );
+            let _ = io::stdout().flush();
+        } else if is_tty {
+            // Stay on the start-aligned grid so a refresh equal to the sample
+            // interval does not skip every other frame to timer jitter.
+            while next_render <= now {
+                next_render += graph_refresh;
+            }
             print!("\x1b[2J\x1b[H");
             println!("Rust Stress All - Ctrl+C to stop");
+            if throttle_streak >= THROTTLE_WARN_SAMPLES {
+                println!("WARNING: CPU THERMAL THROTTLE ACTIVE ({:.1} events/s)", throttle_s);
+            }
             println!(
                 "CPU workers: {}  Mem target: {} MB  Disk: {}  Sample: {}ms",
-                cpu_workers,
-                if args.enable_mem { mem_target } else { 0 },
-                if args.enable_disk {
+                cpu_handles.len(),
+                if mem_handles.is_empty() { 0 } else { mem_live_target.get() },
+                if !disk_handles.is_empty() {
                     format!("{:.1} GB", disk_bytes as f64 / (1024.0 * 1024.0 * 1024.0))
                 } else {
                     "off".to_string()
                 },
                 sample.as_millis()
             );
+            if args.cli.mem_huge_pages {
+                println!(
+                    "Huge pages: {} x 2MB in use ({} MB)",
+                    mem_huge_pages,
+                    mem_huge_pages * 2
+                );
+            }
+            for line in &banner {
+                println!("{line}");
+            }
+            for line in notices.iter() {
+                println!("{line}");
+            }
             let elapsed = start.elapsed().as_secs();
             let window_s = (filled as u64 * sample.as_millis() as u64) / 1000;
-            if args.duration_s > 0 {
-                println!("Elapsed: {}s / {}s  Window: {}s", elapsed, args.duration_s, window_s);
+            if args.cli.duration > 0 {
+                println!("Elapsed: {}s / {}s  Window: {}s", elapsed, args.cli.duration, window_s);
             } else {
                 println!("Elapsed: {}s  Window: {}s", elapsed, window_s);
             }
 
-            let temp_str = if temp_opt.is_some() {
-                format!("{:.1}C", temp_c)
+            let temp_str = if let Some((_, zone)) = temp_opt.as_ref() {
+                format!("{:.1}C ({})", temp_c, zone)
             } else {
                 "n/a".to_string()
             };
@@ -838,56 +2948,123 @@ fn main() {
             } else {
                 "n/a".to_string()
             };
+            let throttle_str = if throttle_s > 0.0 {
+                format!("  THRTLE {:.1}/s", throttle_s)
+            } else {
+                String::new()
+            };
+            let power_str = match (power_cap.as_ref(), power_w) {
+                (Some(cap), Some(w)) => format!("  PWR {:.1}W (cap {}W)", w, cap.cap_w),
+                (Some(cap), None) => format!("  PWR n/a (cap {}W)", cap.cap_w),
+                (None, _) => String::new(),
+            };
             println!(
-                "SENS temp {}  freq {}  load {}  mem {}/{} MB",
-                temp_str, freq_str, load_str, mem_used_mb, mem_total_mb
+                "SENS temp {}  freq {}  load {}  mem {}/{} MB{}{}",
+                temp_str, freq_str, load_str, mem_used_mb, mem_total_mb, throttle_str, power_str
             );
-            if args.gpu_status_path.is_some() {
-                if gpu_opt.is_some() {
-                    println!(
-                        "GPU  util {:>5.1}% mem {:>5.1}% temp {:>5.1}C sm {:>4.0}MHz mem {:>4.0}MHz",
-                        gpu_util, gpu_mem_util, gpu_temp, gpu_sm, gpu_memclk
-                    );
-                } else {
-                    println!("GPU  n/a");
-                }
-            }
-
-            for line in render_chart("CPU", "%", cpu_pct, &cpu_hist, head, filled, 100.0, width, height) {
-                println!("{line}");
+            if irq_s >= 0.0 {
+                println!(
+                    "IRQ {:.1} kHz  softirq {:.1} kHz",
+                    irq_s / 1000.0,
+                    softirq_s / 1000.0
+                );
+            } else {
+                println!("IRQ n/a");
             }
-            let (_, cpu_ops_avg, cpu_ops_max) = stats_window(&cpu_ops_hist, head, filled);
-            println!(
-                "CPU ops/s: {} (avg {}, max {})",
-                format_rate(cpu_ops_s, "ops/s"),
-                format_rate(cpu_ops_avg, "ops/s"),
-                format_rate(cpu_ops_max, "ops/s")
-            );
-            for line in render_chart("MEM", "%", mem_pct, &mem_hist, head, filled, 100.0, width, height) {
-                println!("{line}");
+            if args.cli.show_all_thermals {
+                let zones: Vec<String> = read_thermal_zones()
+                    .iter()
+                    .map(|z| format!("{} {:.1}C", z.type_, z.temp_c))
+                    .collect();
+                if !zones.is_empty() {
+                    println!("THERM {}", zones.join("  "));
+                }
             }
-            let (_, mem_ops_avg, mem_ops_max) = stats_window(&mem_ops_hist, head, filled);
-            println!(
-                "MEM ops/s: {} (avg {}, max {})",
-                format_rate(mem_ops_s, "ops/s"),
-                format_rate(mem_ops_avg, "ops/s"),
-                format_rate(mem_ops_max, "ops/s")
-            );
-            for line in render_chart("DSK", "MB/s", disk_mb_s, &disk_hist, head, filled, disk_peak, width, height) {
-                println!("{line}");
+            let core_temps = read_per_core_temp();
+            let state = SampleState {
+                args: &args,
+                head,
+                filled,
+                phase_specs: &phase_specs,
+                phase_idx,
+                phase_elapsed_s: phase_start.elapsed().as_secs(),
+                cpu_pct,
+                cpu_ema,
+                cpu_hist: &cpu_hist,
+                cpu_ops_s,
+                cpu_ops_hist: &cpu_ops_hist,
+                cpu_mat: (!mat_handles.is_empty())
+                    .then(|| (mat_ops_s, mat_ops_s * matrix.flops_per_op() / 1e9)),
+                cpu_simd: Some(simd_width).filter(|_| cpu_control.get() == CpuWorkload::Avx512),
+                cpu_branch,
+                cpu_mix: Some(cpu_mix_s)
+                    .filter(|_| cpu_control.get() == CpuWorkload::SyntheticMix),
+                cpy_enabled: memcpy_wanted,
+                cpy_gb_s,
+                cpy_hist: &cpy_hist,
+                mem_pct,
+                mem_ema,
+                mem_hist: &mem_hist,
+                mem_ops_s,
+                mem_ops_hist: &mem_ops_hist,
+                mem_gb_s,
+                mem_corruptions: mem_handles.iter().map(WorkerHandle::mem_corruption_count).sum(),
+                pgflt_minor_s,
+                pgflt_major_s,
+                numa_mig_s,
+                numa_hit_ratio,
+                mem_allocs_s: delta_allocs as f64 / sample.as_secs_f64(),
+                mem_alloc_fail_rate,
+                mem_lat_ns,
+                disk_mb_s,
+                disk_ema,
+                disk_hist: &disk_hist,
+                disk_peak,
+                disk_iops,
+                disk_ops_hist: &disk_ops_hist,
+                disk_read_mb_s,
+                disk_phys_mb_s,
+                disk_trace: disk_trace.as_deref(),
+                disk_queue: disk_queue.as_deref(),
+                disk_stalls: args.disk_stall.map(|_| (disk_stalls, disk_stalled)),
+                disk_sla_pct,
+                disk_fill,
+                path_mb_s: &path_mb_s,
+                fsync_avg_us,
+                delta_fsyncs,
+                net_enabled: !net_handles.is_empty(),
+                net_ops_s,
+                net_mb_s,
+                udp_enabled: !udp_handles.is_empty(),
+                udp_dgram_s,
+                udp_drops_s,
+                sock_enabled: !sock_handles.is_empty(),
+                sock_conn_s,
+                sock_drops_s,
+                sock_errors_s,
+                sock_timewait,
+                gpu_enabled: gpu.enabled(),
+                gpu: gpu_opt,
+                gpu_compute: gpu_compute.as_ref().map(|gc| match gc {
+                    Ok(gc) => Ok((gpu_gflops, gc.backend, gc.device.as_str())),
+                    Err(e) => Err(e.as_str()),
+                }),
+                ctxt_s,
+                core_temps: &core_temps,
+            };
+            for section in &tui_layout {
+                for line in section.render(&state, width, height) {
+                    println!("{line}");
+                }
             }
-            let (_, disk_ops_avg, disk_ops_max) = stats_window(&disk_ops_hist, head, filled);
-            println!(
-                "DSK IOPS: {} (avg {}, max {})",
-                format_rate(disk_iops, "IOPS"),
-                format_rate(disk_ops_avg, "IOPS"),
-                format_rate(disk_ops_max, "IOPS")
-            );
             println!("Legend: '*' sample, '|' join. Disk scale uses peak MB/s in window.");
+            if keys.is_some() {
+                println!("{}{}", keys::HELP, if paused { "  [PAUSED]" } else { "" });
+            }
             let _ = io::stdout().flush();
         } else {
             println!(
-                "{},{:.1},{:.1},{:.1},{:.2},{:.2},{:.2},{:.1},{:.3},{:.2},{:.2},{:.2},{},{},{:.1},{:.1},{:.1},{:.0},{:.0}",
+                "{},{:.1},{:.1},{:.1},{:.2},{:.2},{:.2},{:.1},{:.3},{:.2},{:.2},{:.2},{},{},{:.1},{:.1},{:.1},{:.0},{:.0},{:.4},{:.1},{:.4},{:.2},{:.2},{},{:.1},{:.1},{:.0},{:.0},{:.1}{}",
                 start.elapsed().as_secs_f64(),
                 cpu_pct,
                 mem_pct,
@@ -906,14 +3083,26 @@ fn main() {
                 gpu_mem_util,
                 gpu_temp,
                 gpu_sm,
-                gpu_memclk
+                gpu_memclk,
+                mem_alloc_fail_rate,
+                numa_mig_s,
+                numa_hit_ratio,
+                net_mb_s,
+                net_ops_s,
+                mem_huge_pages,
+                pgflt_minor_s,
+                pgflt_major_s,
+                irq_s,
+                softirq_s,
+                throttle_s,
+                csv_extra
             );
         }
 
         if let Some(f) = csv_file.as_mut() {
             let _ = writeln!(
                 f,
-                "{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.3},{:.2},{:.2},{:.2},{},{},{:.2},{:.2},{:.2},{:.0},{:.0}",
+                "{},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.3},{:.2},{:.2},{:.2},{},{},{:.2},{:.2},{:.2},{:.0},{:.0},{:.4},{:.1},{:.4},{:.2},{:.2},{},{:.1},{:.1},{:.0},{:.0},{:.1}{}",
                 start.elapsed().as_secs_f64(),
                 cpu_pct,
                 mem_pct,
@@ -932,17 +3121,224 @@ fn main() {
                 gpu_mem_util,
                 gpu_temp,
                 gpu_sm,
-                gpu_memclk
+                gpu_memclk,
+                mem_alloc_fail_rate,
+                numa_mig_s,
+                numa_hit_ratio,
+                net_mb_s,
+                net_ops_s,
+                mem_huge_pages,
+                pgflt_minor_s,
+                pgflt_major_s,
+                irq_s,
+                softirq_s,
+                throttle_s,
+                csv_extra
+            );
+        }
+
+        #[cfg(feature = "proto")]
+        if let Some(w) = proto_file.as_mut() {
+            let sample = proto_out::pb::StressSample {
+                timestamp_ns: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_nanos() as u64),
+                ts: start.elapsed().as_secs_f64(),
+                cpu_pct,
+                mem_pct,
+                disk_mb_s,
+                cpu_ops_s,
+                mem_ops_s,
+                disk_iops,
+                temp_c,
+                cpu_freq_ghz: freq_ghz,
+                load1,
+                load5,
+                load15,
+                mem_used_mb,
+                mem_total_mb,
+                gpu_util,
+                gpu_mem_util,
+                gpu_temp_c: gpu_temp,
+                gpu_sm_clock_mhz: gpu_sm,
+                gpu_mem_clock_mhz: gpu_memclk,
+                mem_alloc_fail_rate,
+                numa_page_mig_s: numa_mig_s,
+                numa_hit_ratio,
+                net_mb_s,
+                net_ops_s,
+                mem_hugepages_used: mem_huge_pages,
+                pgflt_minor_s,
+                pgflt_major_s,
+                irq_s,
+                softirq_s,
+                throttle_events_s: throttle_s,
+                cpu_pct_ema: args.cli.csv_ema.then_some(cpu_ema),
+                mem_pct_ema: args.cli.csv_ema.then_some(mem_ema),
+                disk_mb_s_ema: args.cli.csv_ema.then_some(disk_ema),
+                udp_dgram_s: (!udp_handles.is_empty()).then_some(udp_dgram_s),
+                udp_drops_s: (!udp_handles.is_empty()).then_some(udp_drops_s),
+            };
+            if let Err(e) = w.write_sample_protobuf(&sample) {
+                log::error!("Cannot write --output-proto sample: {}", e);
+                proto_file = None;
+            }
+        }
+
+        let cpu_hit = cpu_stop.as_mut().is_some_and(|c| c.check(cpu_pct));
+        let mem_hit = mem_stop.as_mut().is_some_and(|c| c.check(mem_pct));
+        if cpu_hit || mem_hit {
+            GLOBAL_STOP.store(true, Ordering::Relaxed);
+        }
+        if let Some(alerter) = alerter.as_mut() {
+            if cpu_hit {
+                alerter.fire("cpu_pct", format!("{:.1}", cpu_pct));
+            }
+            if mem_hit {
+                alerter.fire("mem_pct", format!("{:.1}", mem_pct));
+            }
+            let errors = total_errors(&mem_handles)
+                + total_errors(&disk_handles)
+                + total_errors(&net_handles)
+                + total_errors(&udp_handles)
+                + total_errors(&sock_handles);
+            if errors > 0 {
+                alerter.fire("worker_error", errors.to_string());
+            }
+        }
+
+        summary.record_rates(cpu_ops_s, mem_ops_s, disk_mb_s, disk_iops);
+        let metrics = [cpu_pct, mem_pct, disk_mb_s, cpu_ops_s, mem_ops_s, disk_iops];
+        if let Some(next) = adaptive.as_mut().and_then(|a| a.update(&metrics, sample)) {
+            log::debug!(
+                "Sample interval {}ms -> {}ms",
+                sample.as_millis(),
+                next.as_millis()
             );
+            next_tick = next_tick - sample + next;
+            sample = next;
         }
     }
 
-    stop.store(true, Ordering::Relaxed);
-    for h in handles {
-        let _ = h.join();
+    drop(keys);
+    absorb_latency(&mut latency_export, "disk_write", &disk_handles);
+    absorb_latency(&mut latency_export, "net_rtt", &net_handles);
+    absorb_latency(&mut latency_export, "sock_connect", &sock_handles);
+    absorb_latency(&mut latency_export, "mem_op", &mem_handles);
+    let mut handles: Vec<WorkerHandle> = mem_handles
+        .into_iter()
+        .chain(disk_handles)
+        .chain(net_handles)
+        .chain(udp_handles)
+        .chain(sock_handles)
+        .chain(mat_handles)
+        .chain(lat_handles)
+        .collect();
+    for h in &handles {
+        h.stop();
+    }
+    summary.cpu_worker_stats.extend(stop_all(&mut cpu_handles));
+    stop_all(&mut handles);
+    drop(gpu_compute);
+    drop(power_cap);
+    #[cfg(feature = "otlp")]
+    if let Some(provider) = otlp_provider {
+        if let Err(e) = provider.shutdown() {
+            log::warn!("OTLP: flushing spans failed: {}", e);
+        }
     }
 
-    if let Some(path) = disk_path {
+    if disk_used
+        && !args.cli.no_cleanup
+        && args.cli.disk_raw_device.is_none()
+        && args.cli.disk_read_file.is_none()
+    {
+        let _ = std::fs::remove_file(&disk_path);
+        if let Some(mirror) = args.cli.disk_mirror.as_ref() {
+            let _ = std::fs::remove_file(mirror);
+        }
+    }
+    if let Some(path) = args.cli.heartbeat_file.as_ref() {
         let _ = std::fs::remove_file(path);
     }
+
+    summary.duration_s = start.elapsed().as_secs_f64();
+    #[cfg(feature = "latency_histogram")]
+    if let (Some(export), Some(path)) = (latency_export, args.cli.latency_histogram_export.as_ref()) {
+        match export.write(path, start.elapsed()) {
+            Ok(()) => {
+                log::info!("Latency histograms written to {}", path.display());
+                for line in export.summary() {
+                    log::info!("LAT {}", line);
+                }
+            }
+            Err(e) => log::error!("Cannot write latency histograms {}: {}", path.display(), e),
+        }
+    }
+    summary.print(args.cli.verbose);
+    let summary_json = summary.to_json();
+    if let Some(path) = args.cli.summary_json.as_ref() {
+        if let Err(e) = std::fs::write(path, &summary_json) {
+            log::error!("Cannot write summary {}: {}", path.display(), e);
+        }
+    }
+    if let Some(path) = args.cli.save_baseline.as_ref() {
+        if let Err(e) = std::fs::write(path, &summary_json) {
+            log::error!("Cannot write baseline {}: {}", path.display(), e);
+        }
+    }
+    let regressions = baseline_json
+        .map(|base| baseline::compare(&base, &summary_json, args.regression_threshold_pct))
+        .unwrap_or_default();
+    if let Some(msg) = worker_error {
+        log::error!("Exiting after first worker error: {}", msg);
+        std::process::exit(1);
+    }
+    if !regressions.is_empty() {
+        log::error!("Regressed against baseline: {}", regressions.join(", "));
+        std::process::exit(3);
+    }
+    if let Some(msg) = sla_breach {
+        log::error!("{}", msg);
+        std::process::exit(4);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compute_stats;
+
+    #[test]
+    fn percentiles_of_one_to_hundred() {
+        let hist: Vec<f64> = (1..=100).map(f64::from).collect();
+        let s = compute_stats(&hist, 0, 100);
+        assert_eq!((s.min, s.max, s.avg), (1.0, 100.0, 50.5));
+        assert_eq!((s.p50, s.p95, s.p99), (50.0, 95.0, 99.0));
+    }
+
+    #[test]
+    fn single_sample() {
+        let hist = [0.0, 7.0, 0.0, 0.0];
+        let s = compute_stats(&hist, 2, 1);
+        assert_eq!((s.min, s.max, s.avg), (7.0, 7.0, 7.0));
+        assert_eq!((s.p50, s.p95, s.p99), (7.0, 7.0, 7.0));
+        assert_eq!(s.stddev, 0.0);
+    }
+
+    #[test]
+    fn wrapped_ring_buffer() {
+        // Five samples written in order 1..=5 into a ring of six that wrapped:
+        // 1, 2 and 3 sit at the end, 4 and 5 at the start, `head` is 2.
+        let hist = [4.0, 5.0, 99.0, 1.0, 2.0, 3.0];
+        let s = compute_stats(&hist, 2, 5);
+        assert_eq!((s.min, s.max, s.avg), (1.0, 5.0, 3.0));
+        assert_eq!((s.p50, s.p95, s.p99), (3.0, 5.0, 5.0));
+        assert_eq!(s.stddev, 2f64.sqrt());
+    }
+
+    #[test]
+    fn empty_window() {
+        let s = compute_stats(&[1.0, 2.0, 3.0], 1, 0);
+        assert_eq!((s.min, s.max, s.avg, s.p50, s.p99), (0.0, 0.0, 0.0, 0.0, 0.0));
+    }
 }
@@ -0,0 +1,107 @@
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use stress_lib::{MemWorker, WorkerHandle};
+
+use super::{format_rate, Args, GLOBAL_STOP};
+
+/// Length of each `--mem-prefetch-compare` pass when `--duration` is not
+/// given.
+const DEFAULT_PASS_S: u64 = 10;
+/// Pass length when sweeping distances without `--duration`; shorter, as
+/// there are `SWEEP_DISTANCES.len() + 1` passes.
+const DEFAULT_SWEEP_PASS_S: u64 = 5;
+/// Prefetch distances (random accesses ahead) tried when
+/// `--mem-prefetch` is 0.
+const SWEEP_DISTANCES: [usize; 6] = [1, 2, 4, 8, 16, 32];
+
+/// Measures busy-waiting memory workers without software prefetch, then
+/// with `--mem-prefetch` or, if that is 0, at each of `SWEEP_DISTANCES`,
+/// and prints the rates and the speedup of the best distance. Returns the
+/// process exit code.
+pub fn run(args: &Args, mem_target_mb: u64) -> i32 {
+    let distances: Vec<usize> = if args.cli.mem_prefetch > 0 {
+        vec![args.cli.mem_prefetch]
+    } else {
+        SWEEP_DISTANCES.to_vec()
+    };
+    let pass_s = match args.cli.duration {
+        0 if distances.len() > 1 => DEFAULT_SWEEP_PASS_S,
+        0 => DEFAULT_PASS_S,
+        s => s,
+    };
+    let workers = args.mem_worker_count.max(1);
+    let list: Vec<String> = distances.iter().map(usize::to_string).collect();
+    eprintln!(
+        "MEM prefetch compare: {} workers x {} MB, {}s per pass, distance {}",
+        workers,
+        mem_target_mb / workers as u64,
+        pass_s,
+        list.join(", ")
+    );
+    let Some(plain) = measure(args, mem_target_mb, 0, pass_s) else {
+        eprintln!("MEM prefetch compare: interrupted");
+        return 1;
+    };
+    eprintln!("MEM ops/s without prefetch: {}", format_rate(plain, "ops/s"));
+    let mut best = (0, 0.0);
+    for &distance in &distances {
+        let Some(rate) = measure(args, mem_target_mb, distance, pass_s) else {
+            eprintln!("MEM prefetch compare: interrupted");
+            return 1;
+        };
+        eprintln!(
+            "MEM ops/s with prefetch distance {}: {}",
+            distance,
+            format_rate(rate, "ops/s")
+        );
+        if rate > best.1 {
+            best = (distance, rate);
+        }
+    }
+    let (distance, prefetched) = best;
+    println!(
+        "Prefetch speedup: {:.2}x at {}distance {} ({} vs {})",
+        prefetched / plain.max(f64::MIN_POSITIVE),
+        if distances.len() > 1 { "best " } else { "" },
+        distance,
+        format_rate(prefetched, "ops/s"),
+        format_rate(plain, "ops/s")
+    );
+    0
+}
+
+/// Average ops/s of one pass, timed from when every worker has faulted its
+/// memory in. `None` if interrupted.
+fn measure(args: &Args, mem_target_mb: u64, distance: usize, pass_s: u64) -> Option<f64> {
+    let workers = args.mem_worker_count.max(1);
+    let handles: Vec<WorkerHandle> = (0..workers)
+        .map(|_| {
+            MemWorker::new(mem_target_mb / workers as u64)
+                .alloc_strategy(args.cli.prealloc_strategy)
+                .prefetch_distance(distance)
+                .busy_wait()
+                .spawn()
+        })
+        .collect();
+    let total_ops = || handles.iter().map(|h| h.ops_count()).sum::<u64>();
+    let stopped = || GLOBAL_STOP.load(Ordering::Relaxed);
+
+    while !stopped() && handles.iter().any(|h| h.mem_fault_duration().is_none()) {
+        thread::sleep(Duration::from_millis(50));
+    }
+    let (start_ops, start) = (total_ops(), Instant::now());
+    let end = start + Duration::from_secs(pass_s);
+    while !stopped() && Instant::now() < end {
+        thread::sleep(Duration::from_millis(100).min(end.saturating_duration_since(Instant::now())));
+    }
+    let rate = total_ops().saturating_sub(start_ops) as f64 / start.elapsed().as_secs_f64();
+    for h in &handles {
+        h.stop();
+    }
+    for h in handles {
+        let _ = h.join();
+    }
+    (!stopped()).then_some(rate)
+}
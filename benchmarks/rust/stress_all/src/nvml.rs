@@ -0,0 +1,101 @@
+use std::ffi::c_void;
+
+use libloading::Library;
+
+const NVML_SUCCESS: i32 = 0;
+const NVML_TEMPERATURE_GPU: i32 = 0;
+const NVML_CLOCK_SM: i32 = 1;
+const NVML_CLOCK_MEM: i32 = 2;
+
+const LIB_NAMES: [&str; 3] = ["libnvidia-ml.so.1", "libnvidia-ml.so", "libnvml.so"];
+
+type Device = *mut c_void;
+
+#[repr(C)]
+struct Utilization {
+    gpu: u32,
+    memory: u32,
+}
+
+type InitFn = unsafe extern "C" fn() -> i32;
+type ShutdownFn = unsafe extern "C" fn() -> i32;
+type HandleByIndexFn = unsafe extern "C" fn(u32, *mut Device) -> i32;
+type UtilizationFn = unsafe extern "C" fn(Device, *mut Utilization) -> i32;
+type TemperatureFn = unsafe extern "C" fn(Device, i32, *mut u32) -> i32;
+type ClockInfoFn = unsafe extern "C" fn(Device, i32, *mut u32) -> i32;
+
+pub struct Nvml {
+    device: Device,
+    shutdown: ShutdownFn,
+    utilization: UtilizationFn,
+    temperature: TemperatureFn,
+    clock_info: ClockInfoFn,
+    _lib: Library,
+}
+
+impl Nvml {
+    /// Loads NVML at runtime and opens GPU 0. Returns None when the library
+    /// or a device is not available.
+    pub fn open() -> Option<Self> {
+        let lib = LIB_NAMES
+            .iter()
+            .find_map(|name| unsafe { Library::new(name).ok() })?;
+        unsafe {
+            let init = *lib.get::<InitFn>(b"nvmlInit_v2\0").ok()?;
+            let shutdown = *lib.get::<ShutdownFn>(b"nvmlShutdown\0").ok()?;
+            let handle_by_index =
+                *lib.get::<HandleByIndexFn>(b"nvmlDeviceGetHandleByIndex_v2\0").ok()?;
+            let utilization = *lib.get::<UtilizationFn>(b"nvmlDeviceGetUtilizationRates\0").ok()?;
+            let temperature = *lib.get::<TemperatureFn>(b"nvmlDeviceGetTemperature\0").ok()?;
+            let clock_info = *lib.get::<ClockInfoFn>(b"nvmlDeviceGetClockInfo\0").ok()?;
+            if init() != NVML_SUCCESS {
+                return None;
+            }
+            let mut device: Device = std::ptr::null_mut();
+            if handle_by_index(0, &mut device) != NVML_SUCCESS {
+                shutdown();
+                return None;
+            }
+            Some(Self {
+                device,
+                shutdown,
+                utilization,
+                temperature,
+                clock_info,
+                _lib: lib,
+            })
+        }
+    }
+
+    /// Same layout as `read_gpu_status`: util, mem util, temp, SM clock, mem clock.
+    pub fn read(&self) -> Option<(f64, f64, f64, f64, f64)> {
+        let mut util = Utilization { gpu: 0, memory: 0 };
+        let mut temp = 0u32;
+        let mut sm_clock = 0u32;
+        let mut mem_clock = 0u32;
+        unsafe {
+            if (self.utilization)(self.device, &mut util) != NVML_SUCCESS
+                || (self.temperature)(self.device, NVML_TEMPERATURE_GPU, &mut temp) != NVML_SUCCESS
+                || (self.clock_info)(self.device, NVML_CLOCK_SM, &mut sm_clock) != NVML_SUCCESS
+                || (self.clock_info)(self.device, NVML_CLOCK_MEM, &mut mem_clock) != NVML_SUCCESS
+            {
+                return None;
+            }
+        }
+        Some((
+            util.gpu as f64,
+            util.memory as f64,
+            temp as f64,
+            sm_clock as f64,
+            mem_clock as f64,
+        ))
+    }
+}
+
+impl Drop for Nvml {
+    fn drop(&mut self) {
+        unsafe {
+            (self.shutdown)();
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::FromRawFd;
+
+extern "C" {
+    fn syscall(num: i64, ...) -> i64;
+}
+
+#[cfg(target_arch = "x86_64")]
+const SYS_PERF_EVENT_OPEN: Option<i64> = Some(298);
+#[cfg(target_arch = "aarch64")]
+const SYS_PERF_EVENT_OPEN: Option<i64> = Some(241);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const SYS_PERF_EVENT_OPEN: Option<i64> = None;
+
+const PERF_TYPE_HARDWARE: u32 = 0;
+const PERF_COUNT_HW_BRANCH_INSTRUCTIONS: u64 = 4;
+const PERF_COUNT_HW_BRANCH_MISSES: u64 = 5;
+/// `perf_event_attr` flag bits: count threads created after opening, and
+/// only user space (allowed at `perf_event_paranoid` 2).
+const ATTR_INHERIT: u64 = 1 << 1;
+const ATTR_EXCLUDE_KERNEL: u64 = 1 << 5;
+const ATTR_EXCLUDE_HV: u64 = 1 << 6;
+
+/// `struct perf_event_attr` up to `config1` (PERF_ATTR_SIZE_VER0); the
+/// kernel zero-fills the rest.
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+}
+
+/// Opens a user-space hardware counter for this process, including threads
+/// spawned later.
+fn open_counter(config: u64) -> Result<File, String> {
+    let nr = SYS_PERF_EVENT_OPEN.ok_or("perf_event_open is not supported on this architecture")?;
+    let attr = PerfEventAttr {
+        kind: PERF_TYPE_HARDWARE,
+        size: std::mem::size_of::<PerfEventAttr>() as u32,
+        config,
+        flags: ATTR_INHERIT | ATTR_EXCLUDE_KERNEL | ATTR_EXCLUDE_HV,
+        ..Default::default()
+    };
+    let fd = unsafe { syscall(nr, &attr as *const PerfEventAttr, 0i32, -1i32, -1i32, 0u64) };
+    if fd < 0 {
+        return Err(format!("perf_event_open: {}", std::io::Error::last_os_error()));
+    }
+    Ok(unsafe { File::from_raw_fd(fd as i32) })
+}
+
+fn read_counter(file: &mut File) -> Option<u64> {
+    let mut buf = [0u8; 8];
+    file.read_exact(&mut buf).ok()?;
+    Some(u64::from_ne_bytes(buf))
+}
+
+/// `--perf-counters`: branch instructions and mispredictions retired in
+/// user space by every thread of the process. Open it before spawning the
+/// workers; inherited counters only follow threads created afterwards.
+pub struct PerfCounters {
+    branches: File,
+    misses: File,
+    prev: (u64, u64),
+}
+
+impl PerfCounters {
+    /// Fails without a hardware PMU (most VMs) or when
+    /// `/proc/sys/kernel/perf_event_paranoid` is above 2.
+    pub fn open() -> Result<Self, String> {
+        Ok(Self {
+            branches: open_counter(PERF_COUNT_HW_BRANCH_INSTRUCTIONS)?,
+            misses: open_counter(PERF_COUNT_HW_BRANCH_MISSES)?,
+            prev: (0, 0),
+        })
+    }
+
+    /// (branches, mispredicted branches) since the previous call.
+    pub fn branch_delta(&mut self) -> Option<(u64, u64)> {
+        let cur = (read_counter(&mut self.branches)?, read_counter(&mut self.misses)?);
+        let (prev_b, prev_m) = std::mem::replace(&mut self.prev, cur);
+        Some((cur.0.saturating_sub(prev_b), cur.1.saturating_sub(prev_m)))
+    }
+}
@@ -0,0 +1,95 @@
+use stress_lib::CpuWorkload;
+
+use super::cli::parse_duration;
+
+/// One `[[phase]]` entry of a `--phase-file`. Unset keys fall back to the
+/// command-line configuration.
+pub struct PhaseSpec {
+    pub description: String,
+    pub duration_s: u64,
+    pub cpu_workers: Option<usize>,
+    pub cpu_workload: Option<CpuWorkload>,
+    pub mem: Option<bool>,
+    pub disk: Option<bool>,
+}
+
+/// The only keys a phase accepts: the settings main can switch between
+/// phases without respawning workers. This is deliberately not a general
+/// mapping onto command-line flags; anything else is an error.
+const KEYS: &str = "description, duration, cpu_workers, cpu_workload, mem, disk";
+
+/// Parses the TOML subset used by phase files:
+///
+/// ```text
+/// [[phase]]
+/// description = "CPU only"
+/// duration = "60s"
+/// mem = false
+/// disk = false
+/// ```
+pub fn parse(text: &str) -> Result<Vec<PhaseSpec>, String> {
+    let mut phases: Vec<PhaseSpec> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let lineno = i + 1;
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "[[phase]]" {
+            phases.push(PhaseSpec {
+                description: format!("phase {}", phases.len() + 1),
+                duration_s: 0,
+                cpu_workers: None,
+                cpu_workload: None,
+                mem: None,
+                disk: None,
+            });
+            continue;
+        }
+        let Some(phase) = phases.last_mut() else {
+            return Err(format!("line {}: expected [[phase]]", lineno));
+        };
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(format!("line {}: expected key = value", lineno));
+        };
+        let key = key.trim();
+        let value = value.trim().trim_matches('"');
+        let invalid = || format!("line {}: invalid {}: {}", lineno, key, value);
+        match key {
+            "description" => phase.description = value.to_string(),
+            "duration" => phase.duration_s = parse_duration(value).map_err(|_| invalid())?,
+            "cpu_workers" => phase.cpu_workers = Some(value.parse().map_err(|_| invalid())?),
+            "cpu_workload" => {
+                phase.cpu_workload = Some(CpuWorkload::parse(value).ok_or_else(invalid)?)
+            }
+            "mem" => phase.mem = Some(value.parse().map_err(|_| invalid())?),
+            "disk" => phase.disk = Some(value.parse().map_err(|_| invalid())?),
+            _ => {
+                return Err(format!(
+                    "line {}: unknown key '{}' (supported: {})",
+                    lineno, key, KEYS
+                ))
+            }
+        }
+    }
+    if phases.is_empty() {
+        return Err("no [[phase]] entries".to_string());
+    }
+    if let Some((i, _)) = phases.iter().enumerate().find(|(_, p)| p.duration_s == 0) {
+        return Err(format!("phase {} has no duration", i + 1));
+    }
+    Ok(phases)
+}
+
+/// Cuts a `#` comment off `line`; a `#` inside a quoted value is kept.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
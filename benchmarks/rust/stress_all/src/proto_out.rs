@@ -0,0 +1,33 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use prost::Message;
+
+/// Types generated from `proto/stress_sample.proto`.
+pub mod pb {
+    include!(concat!(env!("OUT_DIR"), "/stress.rs"));
+}
+
+/// `--output-proto` stream: length-delimited `StressSample` messages
+/// appended to a file, readable with any protobuf library's
+/// `parseDelimitedFrom` / `decode_length_delimited`.
+pub struct ProtoWriter {
+    out: BufWriter<File>,
+}
+
+impl ProtoWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            out: BufWriter::new(file),
+        })
+    }
+
+    /// Appends one sample and flushes, so a crash loses at most the sample
+    /// being written.
+    pub fn write_sample_protobuf(&mut self, sample: &pb::StressSample) -> io::Result<()> {
+        self.out.write_all(&sample.encode_length_delimited_to_vec())?;
+        self.out.flush()
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// RAPL package 0 powercap domain.
+const RAPL_DOMAIN: &str = "/sys/class/powercap/intel-rapl/intel-rapl:0";
+/// Smallest `--cpu-energy-cap` accepted.
+const MIN_CAP_W: f64 = 1.0;
+
+fn read_u64(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// `--cpu-energy-cap`: a RAPL long-term power limit on package 0, restored
+/// to its original value on drop. Also samples the package energy counter
+/// to report the measured draw.
+pub struct PowerCap {
+    limit_path: PathBuf,
+    original_uw: u64,
+    pub cap_w: f64,
+    energy_path: PathBuf,
+    /// `max_energy_range_uj`: where `energy_uj` wraps to 0.
+    energy_range_uj: u64,
+    prev_energy: Option<(u64, Instant)>,
+}
+
+impl PowerCap {
+    /// Checks that the domain exists and `watts` lies within [1 W,
+    /// `constraint_0_max_power_uw`] without changing anything; returns the
+    /// current limit in uW.
+    pub fn check(watts: f64) -> Result<u64, String> {
+        let dir = Path::new(RAPL_DOMAIN);
+        let limit_path = dir.join("constraint_0_power_limit_uw");
+        let current_uw = read_u64(&limit_path)
+            .ok_or_else(|| format!("cannot read {}", limit_path.display()))?;
+        let max_uw = read_u64(&dir.join("constraint_0_max_power_uw")).filter(|&uw| uw > 0);
+        let max_w = max_uw.map_or(f64::INFINITY, |uw| uw as f64 / 1e6);
+        if !(MIN_CAP_W..=max_w).contains(&watts) {
+            return Err(format!(
+                "{} W is outside [{} W, {} W]",
+                watts, MIN_CAP_W, max_w
+            ));
+        }
+        Ok(current_uw)
+    }
+
+    /// Writes `watts` to `constraint_0_power_limit_uw`. Fails as
+    /// [`PowerCap::check`] does, or if the write is refused (usually not
+    /// root).
+    pub fn apply(watts: f64) -> Result<Self, String> {
+        let original_uw = Self::check(watts)?;
+        let dir = Path::new(RAPL_DOMAIN);
+        let limit_path = dir.join("constraint_0_power_limit_uw");
+        let uw = (watts * 1e6).round() as u64;
+        std::fs::write(&limit_path, uw.to_string())
+            .map_err(|e| format!("cannot write {}: {}", limit_path.display(), e))?;
+        let energy_path = dir.join("energy_uj");
+        let energy_range_uj = read_u64(&dir.join("max_energy_range_uj")).unwrap_or(u64::MAX);
+        let prev_energy = read_u64(&energy_path).map(|uj| (uj, Instant::now()));
+        Ok(Self {
+            limit_path,
+            original_uw,
+            cap_w: watts,
+            energy_path,
+            energy_range_uj,
+            prev_energy,
+        })
+    }
+
+    /// Average package power since the previous call, or `None` if the
+    /// energy counter is unreadable.
+    pub fn power_w(&mut self) -> Option<f64> {
+        let now = Instant::now();
+        let uj = read_u64(&self.energy_path)?;
+        let prev = self.prev_energy.replace((uj, now));
+        let (prev_uj, prev_at) = prev?;
+        let delta_uj = if uj >= prev_uj {
+            uj - prev_uj
+        } else {
+            self.energy_range_uj.saturating_sub(prev_uj) + uj
+        };
+        let secs = now.duration_since(prev_at).as_secs_f64();
+        (secs > 0.0).then(|| delta_uj as f64 / 1e6 / secs)
+    }
+}
+
+impl Drop for PowerCap {
+    fn drop(&mut self) {
+        match std::fs::write(&self.limit_path, self.original_uw.to_string()) {
+            Ok(()) => log::info!(
+                "CPU energy cap: restored {:.1} W",
+                self.original_uw as f64 / 1e6
+            ),
+            Err(e) => log::error!(
+                "CPU energy cap: cannot restore {} to {} uW: {}",
+                self.limit_path.display(),
+                self.original_uw,
+                e
+            ),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::path::Path;
+
+use super::{
+    format_cpu_list, read_numa_nodes, read_online_cpus, read_physical_core_cpus,
+    read_thermal_zones, Args,
+};
+
+/// Prints the detected hardware and the resolved configuration to stderr
+/// (`--verbose-startup`), for bug reports.
+pub fn print(args: &Args, disk_path: &Path) {
+    let na = || "n/a".to_string();
+    eprintln!("=== stress_all startup ===");
+    eprintln!("OS:          {}", read_os_name().unwrap_or_else(na));
+    eprintln!("Kernel:      {}", read_kernel_version().unwrap_or_else(na));
+    eprintln!("CPU model:   {}", read_cpu_model().unwrap_or_else(na));
+
+    let online = read_online_cpus().unwrap_or_default();
+    match read_physical_core_cpus() {
+        Some((cores, _)) => eprintln!(
+            "Topology:    {} logical CPUs ({}), {} physical cores",
+            online.len(),
+            format_cpu_list(&online),
+            cores.len()
+        ),
+        None => eprintln!("Topology:    {} logical CPUs", online.len()),
+    }
+
+    let nodes: Vec<String> = read_numa_nodes()
+        .iter()
+        .enumerate()
+        .map(|(i, cpus)| format!("node{}: {}", i, format_cpu_list(cpus)))
+        .collect();
+    if nodes.is_empty() {
+        eprintln!("NUMA:        n/a");
+    } else {
+        eprintln!("NUMA:        {}", nodes.join("  "));
+    }
+
+    match read_meminfo_mb() {
+        Some((total, avail)) => {
+            eprintln!("RAM:         {} MB total, {} MB available", total, avail)
+        }
+        None => eprintln!("RAM:         n/a"),
+    }
+
+    let zones = read_thermal_zones();
+    if zones.is_empty() {
+        eprintln!("Thermal:     none");
+    }
+    for z in &zones {
+        eprintln!("Thermal:     {} ({}) {:.1}C", z.name, z.type_, z.temp_c);
+    }
+
+    eprintln!(
+        "Disk path:   {} ({})",
+        disk_path.display(),
+        read_fs_type(disk_path).unwrap_or_else(na)
+    );
+    for (name, value) in read_cgroup_limits() {
+        eprintln!("cgroup:      {} = {}", name, value);
+    }
+    eprintln!("Config:      {:#?}", args);
+    eprintln!("==========================");
+}
+
+fn read_os_name() -> Option<String> {
+    let text = std::fs::read_to_string("/etc/os-release").ok()?;
+    text.lines()
+        .find_map(|l| l.strip_prefix("PRETTY_NAME="))
+        .map(|v| v.trim_matches('"').to_string())
+}
+
+fn read_kernel_version() -> Option<String> {
+    let ostype = std::fs::read_to_string("/proc/sys/kernel/ostype").ok()?;
+    let release = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+    Some(format!("{} {}", ostype.trim(), release.trim()))
+}
+
+fn read_cpu_model() -> Option<String> {
+    let text = std::fs::read_to_string("/proc/cpuinfo").ok()?;
+    text.lines().find_map(|l| {
+        let (key, value) = l.split_once(':')?;
+        matches!(key.trim(), "model name" | "Model" | "cpu model").then(|| value.trim().to_string())
+    })
+}
+
+/// (MemTotal, MemAvailable) in MB.
+fn read_meminfo_mb() -> Option<(u64, u64)> {
+    let text = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|l| l.strip_prefix(name))
+            .and_then(|v| v.split_whitespace().next()?.parse::<u64>().ok())
+            .map(|kb| kb / 1024)
+    };
+    Some((field("MemTotal:")?, field("MemAvailable:")?))
+}
+
+/// Filesystem type of the longest `/proc/mounts` entry containing `path`.
+fn read_fs_type(path: &Path) -> Option<String> {
+    let text = std::fs::read_to_string("/proc/mounts").ok()?;
+    text.lines()
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            let _dev = parts.next()?;
+            let mount = parts.next()?;
+            let fstype = parts.next()?;
+            path.starts_with(mount).then(|| (mount.len(), fstype.to_string()))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, fstype)| fstype)
+}
+
+/// CPU and memory limits of this process's cgroup (v2, falling back to v1),
+/// read under the cgroup's own directory rather than the hierarchy root.
+fn read_cgroup_limits() -> Vec<(&'static str, String)> {
+    let Ok(text) = std::fs::read_to_string("/proc/self/cgroup") else {
+        return Vec::new();
+    };
+    // Lines are `id:controllers:path`; the v2 hierarchy is the `0::` line.
+    let path_of = |controller: &str| {
+        text.lines().find_map(|l| {
+            let mut parts = l.splitn(3, ':');
+            let _id = parts.next()?;
+            let controllers = parts.next()?;
+            let path = parts.next()?;
+            controllers.split(',').any(|c| c == controller).then_some(path)
+        })
+    };
+    let v2 = text.lines().find_map(|l| l.strip_prefix("0::"));
+    let files = [
+        ("cpu.max", "", v2),
+        ("memory.max", "", v2),
+        ("cpu.cfs_quota_us", "cpu", path_of("cpu")),
+        ("memory.limit_in_bytes", "memory", path_of("memory")),
+    ];
+    files
+        .into_iter()
+        .filter_map(|(name, hierarchy, cgroup)| {
+            let path = Path::new("/sys/fs/cgroup")
+                .join(hierarchy)
+                .join(cgroup?.trim_start_matches('/'))
+                .join(name);
+            let value = std::fs::read_to_string(path).ok()?;
+            Some((name, value.trim().to_string()))
+        })
+        .collect()
+}
@@ -0,0 +1,210 @@
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{baseline, cli::parse_duration, GLOBAL_STOP};
+
+/// Summary metrics shown in the end-of-script comparison table.
+const COLUMNS: [&str; 5] = [
+    "cpu_pct_avg",
+    "cpu_ops_s_avg",
+    "mem_ops_s_avg",
+    "disk_mb_s_avg",
+    "disk_iops_avg",
+];
+
+/// Flags the script runner sets itself on every `stress` line.
+const RESERVED: [&str; 2] = ["--duration", "--summary-json"];
+
+/// One line of a `--stress-script`.
+enum Step {
+    /// `stress <dur> [flags...]`: a `stress_all` run with those flags.
+    Stress { duration_s: u64, flags: Vec<String> },
+    /// `sleep <dur>`: idle between runs.
+    Sleep(u64),
+}
+
+struct RunResult {
+    command: String,
+    elapsed: Duration,
+    exit: Option<i32>,
+    metrics: Vec<(String, f64)>,
+}
+
+/// Parses a script:
+///
+/// ```text
+/// # CPU only, then everything
+/// stress 60s --cpu-workers 4 --no-disk
+/// sleep 10s
+/// stress 2m
+/// ```
+///
+/// Flags are split on whitespace; quoting is not supported.
+fn parse(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let lineno = i + 1;
+        let line = line.split_once('#').map_or(line, |(l, _)| l).trim();
+        let mut words = line.split_whitespace();
+        let Some(cmd) = words.next() else {
+            continue;
+        };
+        let duration_s = words
+            .next()
+            .ok_or_else(|| format!("line {}: {} needs a duration", lineno, cmd))
+            .and_then(|d| parse_duration(d).map_err(|e| format!("line {}: {}", lineno, e)))?;
+        match cmd {
+            "stress" => {
+                if duration_s == 0 {
+                    return Err(format!("line {}: stress duration must be > 0", lineno));
+                }
+                let flags: Vec<String> = words.map(str::to_string).collect();
+                if let Some(flag) = flags.iter().find(|f| {
+                    RESERVED.iter().any(|r| f == r || f.starts_with(&format!("{}=", r)))
+                }) {
+                    return Err(format!("line {}: {} is set by the script runner", lineno, flag));
+                }
+                steps.push(Step::Stress { duration_s, flags });
+            }
+            "sleep" => {
+                if let Some(extra) = words.next() {
+                    return Err(format!("line {}: unexpected '{}' after sleep", lineno, extra));
+                }
+                steps.push(Step::Sleep(duration_s));
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: unknown command '{}' (supported: stress, sleep)",
+                    lineno, cmd
+                ))
+            }
+        }
+    }
+    if !steps.iter().any(|s| matches!(s, Step::Stress { .. })) {
+        return Err("no stress lines".to_string());
+    }
+    Ok(steps)
+}
+
+/// Runs every step of the script at `path` in order, each `stress` line as a
+/// `stress_all` subprocess, then prints a table comparing the runs' summaries.
+/// Returns the process exit code: 0 if every run exited cleanly.
+pub fn run(path: &Path) -> i32 {
+    let steps = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| parse(&text))
+    {
+        Ok(steps) => steps,
+        Err(e) => {
+            eprintln!("Invalid stress script {}: {}", path.display(), e);
+            return 2;
+        }
+    };
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            eprintln!("Stress script: cannot locate the stress_all binary: {}", e);
+            return 2;
+        }
+    };
+    let stopped = || GLOBAL_STOP.load(Ordering::Relaxed);
+    let total = steps.iter().filter(|s| matches!(s, Step::Stress { .. })).count();
+    let mut results: Vec<RunResult> = Vec::new();
+
+    for step in &steps {
+        if stopped() {
+            break;
+        }
+        match step {
+            Step::Sleep(secs) => {
+                eprintln!("SCRIPT sleep {}s", secs);
+                let end = Instant::now() + Duration::from_secs(*secs);
+                while !stopped() && Instant::now() < end {
+                    thread::sleep(
+                        Duration::from_millis(100).min(end.saturating_duration_since(Instant::now())),
+                    );
+                }
+            }
+            Step::Stress { duration_s, flags } => {
+                let n = results.len() + 1;
+                let command = format!("stress {}s {}", duration_s, flags.join(" "));
+                let command = command.trim_end().to_string();
+                eprintln!("SCRIPT run {}/{}: {}", n, total, command);
+                let summary_path = std::env::temp_dir()
+                    .join(format!("stress_script_{}_{}.json", std::process::id(), n));
+                let start = Instant::now();
+                let status = Command::new(&exe)
+                    .arg("--duration")
+                    .arg(duration_s.to_string())
+                    .args(flags)
+                    .arg("--summary-json")
+                    .arg(&summary_path)
+                    .status();
+                let elapsed = start.elapsed();
+                let exit = match status {
+                    Ok(status) => status.code(),
+                    Err(e) => {
+                        eprintln!("SCRIPT run {}: cannot start {}: {}", n, exe.display(), e);
+                        None
+                    }
+                };
+                let metrics = std::fs::read_to_string(&summary_path)
+                    .map(|text| baseline::parse_metrics(&text))
+                    .unwrap_or_default();
+                let _ = std::fs::remove_file(&summary_path);
+                eprintln!(
+                    "SCRIPT run {}/{} finished in {:.1}s (exit {})",
+                    n,
+                    total,
+                    elapsed.as_secs_f64(),
+                    exit.map_or("signal".to_string(), |c| c.to_string())
+                );
+                results.push(RunResult {
+                    command,
+                    elapsed,
+                    exit,
+                    metrics,
+                });
+            }
+        }
+    }
+
+    print_table(&results);
+    if stopped() {
+        eprintln!("SCRIPT interrupted after {} of {} runs", results.len(), total);
+        return 1;
+    }
+    if results.iter().all(|r| r.exit == Some(0)) {
+        0
+    } else {
+        1
+    }
+}
+
+fn print_table(results: &[RunResult]) {
+    let mut header = format!("{:>3} {:>9} {:>6}", "run", "elapsed_s", "exit");
+    for col in COLUMNS {
+        header.push_str(&format!(" {:>14}", col));
+    }
+    println!("{}  command", header);
+    for (i, r) in results.iter().enumerate() {
+        let mut row = format!(
+            "{:>3} {:>9.1} {:>6}",
+            i + 1,
+            r.elapsed.as_secs_f64(),
+            r.exit.map_or("-".to_string(), |c| c.to_string())
+        );
+        for col in COLUMNS {
+            let cell = match r.metrics.iter().find(|(k, _)| k == col) {
+                Some(&(_, v)) if v >= 0.0 => format!("{:.1}", v),
+                Some(_) => "n/a".to_string(),
+                None => "-".to_string(),
+            };
+            row.push_str(&format!(" {:>14}", cell));
+        }
+        println!("{}  {}", row, r.command);
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt::Write as _;
+
+use stress_lib::CpuWorkerStats;
+
+const BAR_WIDTH: usize = 40;
+
+/// Whole-run statistics, printed when the run ends and optionally written
+/// to `--summary-json`.
+#[derive(Default)]
+pub struct RunSummary {
+    pub duration_s: f64,
+    samples: u64,
+    cpu_pct_sum: f64,
+    cpu_pct_max: f64,
+    /// Samples per 10% CPU utilization bucket (the last includes 100%).
+    cpu_buckets: [u64; 10],
+    /// `scaling_max_freq` and `base_frequency` read at startup.
+    pub cpu_freq_max_khz: Option<u64>,
+    pub cpu_freq_base_khz: Option<u64>,
+    /// Sum of frequency × sample length, for the time-weighted average.
+    cpu_freq_khz_s: f64,
+    cpu_freq_s: f64,
+    /// Per-sample sums of cpu ops/s, mem ops/s, disk MB/s and disk IOPS.
+    rate_sums: [f64; 4],
+    rate_samples: u64,
+    /// One entry per CPU worker that ran, including ones retired by phases.
+    pub cpu_worker_stats: Vec<CpuWorkerStats>,
+}
+
+impl RunSummary {
+    pub fn record_cpu(&mut self, pct: f64) {
+        let pct = pct.clamp(0.0, 100.0);
+        self.samples += 1;
+        self.cpu_pct_sum += pct;
+        self.cpu_pct_max = self.cpu_pct_max.max(pct);
+        self.cpu_buckets[((pct / 10.0) as usize).min(9)] += 1;
+    }
+
+    pub fn record_cpu_freq(&mut self, khz: u64, interval_s: f64) {
+        self.cpu_freq_khz_s += khz as f64 * interval_s;
+        self.cpu_freq_s += interval_s;
+    }
+
+    pub fn record_rates(&mut self, cpu_ops_s: f64, mem_ops_s: f64, disk_mb_s: f64, disk_iops: f64) {
+        for (sum, v) in self
+            .rate_sums
+            .iter_mut()
+            .zip([cpu_ops_s, mem_ops_s, disk_mb_s, disk_iops])
+        {
+            *sum += v;
+        }
+        self.rate_samples += 1;
+    }
+
+    /// (min, max, avg) total ops across CPU workers.
+    fn cpu_worker_ops_spread(&self) -> Option<(u64, u64, f64)> {
+        let ops = self.cpu_worker_stats.iter().map(|s| s.total_ops);
+        let min = ops.clone().min()?;
+        let max = ops.clone().max()?;
+        let avg = ops.sum::<u64>() as f64 / self.cpu_worker_stats.len() as f64;
+        Some((min, max, avg))
+    }
+
+    fn cpu_freq_avg_khz(&self) -> Option<f64> {
+        (self.cpu_freq_s > 0.0).then(|| self.cpu_freq_khz_s / self.cpu_freq_s)
+    }
+
+    /// How far into the turbo range the average frequency reached:
+    /// 0% at base frequency, 100% at the maximum.
+    pub fn turbo_utilization_pct(&self) -> Option<f64> {
+        let max = self.cpu_freq_max_khz? as f64;
+        let base = self.cpu_freq_base_khz? as f64;
+        let avg = self.cpu_freq_avg_khz()?;
+        (max > base).then(|| 100.0 * (avg - base) / (max - base))
+    }
+
+    /// Fraction of samples in each 10% bucket.
+    pub fn cpu_histogram(&self) -> [f64; 10] {
+        let total = self.samples.max(1) as f64;
+        self.cpu_buckets.map(|n| n as f64 / total)
+    }
+
+    fn cpu_pct_avg(&self) -> f64 {
+        self.cpu_pct_sum / self.samples.max(1) as f64
+    }
+
+    /// `verbose` adds one line per CPU worker.
+    pub fn print(&self, verbose: bool) {
+        eprintln!(
+            "SUMMARY: {:.1}s, {} samples, CPU avg {:.1}% max {:.1}%",
+            self.duration_s,
+            self.samples,
+            self.cpu_pct_avg(),
+            self.cpu_pct_max
+        );
+        if let Some(avg) = self.cpu_freq_avg_khz() {
+            let ghz = |khz: Option<u64>| {
+                khz.map_or("n/a".to_string(), |k| format!("{:.2}", k as f64 / 1e6))
+            };
+            eprintln!(
+                "CPU freq: avg {:.2} GHz  base {} GHz  max {} GHz  turbo utilization {}",
+                avg / 1e6,
+                ghz(self.cpu_freq_base_khz),
+                ghz(self.cpu_freq_max_khz),
+                self.turbo_utilization_pct()
+                    .map_or("n/a".to_string(), |t| format!("{:.1}%", t))
+            );
+        }
+        if let Some((min, max, avg)) = self.cpu_worker_ops_spread() {
+            let imbalance = if min > 0 { max as f64 / min as f64 } else { 0.0 };
+            eprintln!(
+                "CPU workers: {}  ops min {} max {} avg {:.0} (max/min {:.2}x)",
+                self.cpu_worker_stats.len(),
+                min,
+                max,
+                avg,
+                imbalance
+            );
+        }
+        if verbose {
+            for (i, s) in self.cpu_worker_stats.iter().enumerate() {
+                eprintln!(
+                    "  worker {:>3}: ops {} bursts {} peak burst {} slept {:.1}ms",
+                    i,
+                    s.total_ops,
+                    s.total_bursts,
+                    s.peak_burst_size,
+                    s.total_sleep_us as f64 / 1000.0
+                );
+            }
+        }
+        eprintln!("CPU utilization histogram:");
+        for (i, frac) in self.cpu_histogram().iter().enumerate() {
+            let bar = (frac * BAR_WIDTH as f64).round() as usize;
+            eprintln!(
+                "  {:>7} |{:<w$}| {:5.1}%",
+                format!("{}-{}%", i * 10, i * 10 + 10),
+                "█".repeat(bar),
+                frac * 100.0,
+                w = BAR_WIDTH
+            );
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let hist: Vec<String> = self
+            .cpu_histogram()
+            .iter()
+            .map(|f| format!("{:.4}", f))
+            .collect();
+        let mut out = String::from("{\n");
+        let _ = writeln!(out, "  \"duration_s\": {:.3},", self.duration_s);
+        let _ = writeln!(out, "  \"samples\": {},", self.samples);
+        let _ = writeln!(out, "  \"cpu_pct_avg\": {:.2},", self.cpu_pct_avg());
+        let _ = writeln!(out, "  \"cpu_pct_max\": {:.2},", self.cpu_pct_max);
+        let _ = writeln!(
+            out,
+            "  \"cpu_freq_avg_khz\": {:.0},",
+            self.cpu_freq_avg_khz().unwrap_or(-1.0)
+        );
+        let _ = writeln!(
+            out,
+            "  \"turbo_utilization_pct\": {:.2},",
+            self.turbo_utilization_pct().unwrap_or(-1.0)
+        );
+        let n = self.rate_samples.max(1) as f64;
+        let _ = writeln!(out, "  \"cpu_ops_s_avg\": {:.1},", self.rate_sums[0] / n);
+        let _ = writeln!(out, "  \"mem_ops_s_avg\": {:.1},", self.rate_sums[1] / n);
+        let _ = writeln!(out, "  \"disk_mb_s_avg\": {:.2},", self.rate_sums[2] / n);
+        let _ = writeln!(out, "  \"disk_iops_avg\": {:.1},", self.rate_sums[3] / n);
+        if let Some((min, max, avg)) = self.cpu_worker_ops_spread() {
+            let _ = writeln!(out, "  \"cpu_worker_ops_min\": {},", min);
+            let _ = writeln!(out, "  \"cpu_worker_ops_max\": {},", max);
+            let _ = writeln!(out, "  \"cpu_worker_ops_avg\": {:.0},", avg);
+        }
+        let _ = writeln!(out, "  \"cpu_utilization_histogram\": [{}]", hist.join(", "));
+        out.push_str("}\n");
+        out
+    }
+}
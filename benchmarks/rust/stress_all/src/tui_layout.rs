@@ -0,0 +1,484 @@
+use stress_lib::{DiskAccessPattern, DiskCompress, DiskTrace, SimdWidth};
+
+use super::{disk_io_label, format_rate, compute_stats, render_chart, Args};
+use crate::phase_file::PhaseSpec;
+
+/// One block of the TUI below the header, in `--tui-layout` order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TuiSection {
+    Cpu,
+    Cpy,
+    Mem,
+    Disk,
+    Net,
+    Gpu,
+    Ctx,
+}
+
+/// Order used when `--tui-layout` is not given.
+pub const DEFAULT_LAYOUT: [TuiSection; 6] = [
+    TuiSection::Gpu,
+    TuiSection::Cpu,
+    TuiSection::Cpy,
+    TuiSection::Mem,
+    TuiSection::Disk,
+    TuiSection::Net,
+];
+
+impl TuiSection {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "cpu" => Some(TuiSection::Cpu),
+            "cpy" => Some(TuiSection::Cpy),
+            "mem" => Some(TuiSection::Mem),
+            "disk" => Some(TuiSection::Disk),
+            "net" => Some(TuiSection::Net),
+            "gpu" => Some(TuiSection::Gpu),
+            "ctx" => Some(TuiSection::Ctx),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TuiSection::Cpu => "cpu",
+            TuiSection::Cpy => "cpy",
+            TuiSection::Mem => "mem",
+            TuiSection::Disk => "disk",
+            TuiSection::Net => "net",
+            TuiSection::Gpu => "gpu",
+            TuiSection::Ctx => "ctx",
+        }
+    }
+
+    /// Lines for this section; empty when the section has nothing to show
+    /// this run (e.g. `net` without net workers in the default layout).
+    pub fn render(self, state: &SampleState, width: usize, height: usize) -> Vec<String> {
+        let s = state;
+        let show_ema = |v: f64| s.args.cli.ema_alpha.map(|_| v);
+        let mut out = Vec::new();
+        match self {
+            TuiSection::Gpu => {
+                match s.gpu {
+                    Some((util, mem_util, temp, sm, memclk)) => out.push(format!(
+                        "GPU  util {:>5.1}% mem {:>5.1}% temp {:>5.1}C sm {:>4.0}MHz mem {:>4.0}MHz",
+                        util, mem_util, temp, sm, memclk
+                    )),
+                    None if s.gpu_enabled => out.push("GPU  n/a".to_string()),
+                    None => {}
+                }
+                match s.gpu_compute {
+                    Some(Ok((gflops, backend, device))) => out.push(format!(
+                        "GPU compute: {:.1} GFLOP/s ({}, {})",
+                        gflops, backend, device
+                    )),
+                    Some(Err(reason)) => out.push(format!("GPU compute: not available ({})", reason)),
+                    None => {}
+                }
+            }
+            TuiSection::Cpu => {
+                out.extend(render_chart(
+                    "CPU",
+                    "%",
+                    s.cpu_pct,
+                    s.cpu_hist,
+                    s.head,
+                    s.filled,
+                    &compute_stats(s.cpu_hist, s.head, s.filled),
+                    100.0,
+                    width,
+                    height,
+                    show_ema(s.cpu_ema),
+                    s.args.cli.chart_mode,
+                ));
+                let stats = compute_stats(s.cpu_ops_hist, s.head, s.filled);
+                let mix = match s.cpu_mix {
+                    Some([int, fp, mem]) => format!(
+                        "  LCG/FP/MEM: {}/{}/{} ops/s",
+                        format_rate(int, ""),
+                        format_rate(fp, ""),
+                        format_rate(mem, "")
+                    ),
+                    None => String::new(),
+                };
+                out.push(format!(
+                    "CPU ops/s: {} (avg {}, max {}){}",
+                    format_rate(s.cpu_ops_s, "ops/s"),
+                    format_rate(stats.avg, "ops/s"),
+                    format_rate(stats.max, "ops/s"),
+                    mix
+                ));
+                if let Some((mat_s, gflops)) = s.cpu_mat {
+                    out.push(format!(
+                        "MAT ops/s: {} ({}x{}, {:.2} GFLOP/s)",
+                        format_rate(mat_s, "ops/s"),
+                        s.args.cpu_matrix_n,
+                        s.args.cpu_matrix_n,
+                        gflops
+                    ));
+                }
+                if let Some((branches_s, miss_pct)) = s.cpu_branch {
+                    out.push(format!(
+                        "CPU branches: {} (mispredicted {:.2}%)",
+                        format_rate(branches_s, "/s"),
+                        miss_pct
+                    ));
+                }
+                if let Some(width) = s.cpu_simd {
+                    out.push(format!("CPU SIMD: {}-bit ({})", width.bits(), width.name()));
+                }
+                if let Some(spec) = s.phase_specs.get(s.phase_idx) {
+                    out.push(format!(
+                        "Phase {}/{}: {} ({}s left)",
+                        s.phase_idx + 1,
+                        s.phase_specs.len(),
+                        spec.description,
+                        spec.duration_s.saturating_sub(s.phase_elapsed_s)
+                    ));
+                }
+                if let Some(phase) = s.args.cpu_phases.get(s.phase_idx) {
+                    out.push(format!(
+                        "CPU phase {}/{}: {} ({}s left)",
+                        s.phase_idx + 1,
+                        s.args.cpu_phases.len(),
+                        phase.workload.name(),
+                        phase.duration_s.saturating_sub(s.phase_elapsed_s)
+                    ));
+                }
+                let readings: Vec<(usize, f64)> = s
+                    .core_temps
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(core, t)| t.map(|t| (core, t)))
+                    .collect();
+                if let Some(&(hot_core, max)) =
+                    readings.iter().max_by(|a, b| a.1.total_cmp(&b.1))
+                {
+                    let min = readings.iter().map(|r| r.1).fold(f64::INFINITY, f64::min);
+                    let avg = readings.iter().map(|r| r.1).sum::<f64>() / readings.len() as f64;
+                    out.push(format!(
+                        "CORETEMP min {:.1}C avg {:.1}C max {:.1}C (core {}, {} cores)",
+                        min,
+                        avg,
+                        max,
+                        hot_core,
+                        readings.len()
+                    ));
+                }
+            }
+            TuiSection::Cpy => {
+                if s.cpy_enabled {
+                    let stats = compute_stats(s.cpy_hist, s.head, s.filled);
+                    out.push(format!(
+                        "CPY memcpy: {:.2} GB/s (avg {:.2}, max {:.2})",
+                        s.cpy_gb_s, stats.avg, stats.max
+                    ));
+                }
+            }
+            TuiSection::Mem => {
+                out.extend(render_chart(
+                    "MEM",
+                    "%",
+                    s.mem_pct,
+                    s.mem_hist,
+                    s.head,
+                    s.filled,
+                    &compute_stats(s.mem_hist, s.head, s.filled),
+                    100.0,
+                    width,
+                    height,
+                    show_ema(s.mem_ema),
+                    s.args.cli.chart_mode,
+                ));
+                let stats = compute_stats(s.mem_ops_hist, s.head, s.filled);
+                out.push(format!(
+                    "MEM ops/s: {} (avg {}, max {})",
+                    format_rate(s.mem_ops_s, "ops/s"),
+                    format_rate(stats.avg, "ops/s"),
+                    format_rate(stats.max, "ops/s")
+                ));
+                if let Some(target) = s.args.mem_bandwidth_target {
+                    out.push(format!(
+                        "MEM bandwidth: {:.2} GB/s (target {:.2} GB/s)",
+                        s.mem_gb_s, target
+                    ));
+                }
+                if let Some(rate) = s.args.mem_error_injection {
+                    out.push(format!(
+                        "MEM injected bit flips: {} (rate {}/s)",
+                        s.mem_corruptions, rate
+                    ));
+                }
+                if s.pgflt_minor_s >= 0.0 {
+                    out.push(format!(
+                        "MEM pgflt minor/major /s: {} / {}",
+                        format_rate(s.pgflt_minor_s, ""),
+                        format_rate(s.pgflt_major_s, "")
+                    ));
+                } else {
+                    out.push("MEM pgflt minor/major /s: n/a".to_string());
+                }
+                if s.args.cli.mem_numa_monitor {
+                    if s.numa_mig_s >= 0.0 {
+                        out.push(format!(
+                            "NUMA mig/s: {}  hit ratio {:.2}%",
+                            format_rate(s.numa_mig_s, ""),
+                            s.numa_hit_ratio * 100.0
+                        ));
+                    } else {
+                        out.push("NUMA mig/s: n/a".to_string());
+                    }
+                }
+                if s.args.cli.mem_latency_test {
+                    out.push(match s.mem_lat_ns {
+                        Some(ns) => format!("MEM lat {:.1}ns", ns),
+                        None => "MEM lat n/a".to_string(),
+                    });
+                }
+                if s.args.mem_gc_pattern.is_some() {
+                    out.push(format!(
+                        "MEM GC allocs/s: {}  fail rate {:.2}%",
+                        format_rate(s.mem_allocs_s, ""),
+                        s.mem_alloc_fail_rate * 100.0
+                    ));
+                }
+            }
+            TuiSection::Disk => {
+                let hot = if s.args.cli.disk_access_pattern == DiskAccessPattern::Hotspot {
+                    " HOT"
+                } else {
+                    ""
+                };
+                let hot = match s.args.disk_io_throttle {
+                    Some(iops) => format!("{} THROTTLED @ {} IOPS", hot, iops),
+                    None => hot.to_string(),
+                };
+                out.extend(render_chart(
+                    &match s.disk_queue {
+                        Some(queue) => format!("DSK {}{} ({})", disk_io_label(s.args), hot, queue),
+                        None => format!("DSK {}{}", disk_io_label(s.args), hot),
+                    },
+                    "MB/s",
+                    s.disk_mb_s,
+                    s.disk_hist,
+                    s.head,
+                    s.filled,
+                    &compute_stats(s.disk_hist, s.head, s.filled),
+                    s.disk_peak,
+                    width,
+                    height,
+                    show_ema(s.disk_ema),
+                    s.args.cli.chart_mode,
+                ));
+                let stats = compute_stats(s.disk_ops_hist, s.head, s.filled);
+                out.push(format!(
+                    "DSK IOPS: {} (avg {}, max {})",
+                    format_rate(s.disk_iops, "IOPS"),
+                    format_rate(stats.avg, "IOPS"),
+                    format_rate(stats.max, "IOPS")
+                ));
+                if let Some((count, stalled)) = s.disk_stalls {
+                    out.push(format!(
+                        "DSK stalls: {}{}",
+                        count,
+                        if stalled { "  STALL" } else { "" }
+                    ));
+                }
+                if let Some(pct) = s.disk_sla_pct {
+                    out.push(format!("DSK SLA viol {:.2}%", pct));
+                }
+                if let Some(trace) = s.disk_trace {
+                    let rel = match trace.natural_ops_s {
+                        Some(rate) if rate > 0.0 => {
+                            format!(" ({:.2}x trace rate)", s.disk_iops / rate)
+                        }
+                        _ => String::new(),
+                    };
+                    out.push(format!(
+                        "DSK trace: {}{}  read {:.1}MB/s",
+                        format_rate(s.disk_iops, "ops/s"),
+                        rel,
+                        s.disk_read_mb_s
+                    ));
+                }
+                match s.disk_fill {
+                    Some((true, _)) => out.push("DSK pattern: FILL → OVR".to_string()),
+                    Some((false, frac)) => {
+                        out.push(format!("DSK pattern: FILL {:.0}%", frac * 100.0))
+                    }
+                    None => {}
+                }
+                if s.args.cli.disk_rmw && s.disk_trace.is_none() {
+                    out.push(format!(
+                        "DSK RMW: {:.1}MB/s (read {:.1}MB/s, write {:.1}MB/s)",
+                        s.disk_read_mb_s + s.disk_mb_s,
+                        s.disk_read_mb_s,
+                        s.disk_mb_s
+                    ));
+                }
+                if let [primary, mirror] = s.path_mb_s[..] {
+                    let ratio = if mirror > 0.0 {
+                        format!("{:.2}x", primary / mirror)
+                    } else {
+                        "n/a".to_string()
+                    };
+                    out.push(format!(
+                        "DSK mirror: {:.1}MB/s <-> {:.1}MB/s ({})",
+                        primary, mirror, ratio
+                    ));
+                }
+                if s.args.cli.disk_compress != DiskCompress::None {
+                    let ratio = if s.disk_phys_mb_s > 0.0 {
+                        format!("{:.2}x", s.disk_mb_s / s.disk_phys_mb_s)
+                    } else {
+                        "n/a".to_string()
+                    };
+                    out.push(format!(
+                        "DSK {}: RAW {:.1}MB/s  PHYS {:.1}MB/s ({})",
+                        s.args.cli.disk_compress.name(),
+                        s.disk_mb_s,
+                        s.disk_phys_mb_s,
+                        ratio
+                    ));
+                }
+                if s.args.cli.disk_fsync_interval > 0 {
+                    out.push(format!(
+                        "DSK fsync: every {} writes, avg {:.0}us ({} this sample)",
+                        s.args.cli.disk_fsync_interval, s.fsync_avg_us, s.delta_fsyncs
+                    ));
+                }
+            }
+            TuiSection::Net => {
+                if s.net_enabled {
+                    out.push(format!(
+                        "NET unix: {}  {:.1}MB/s",
+                        format_rate(s.net_ops_s, "ops/s"),
+                        s.net_mb_s
+                    ));
+                }
+                if s.udp_enabled {
+                    out.push(format!(
+                        "NET dgram: {}  drops {}",
+                        format_rate(s.udp_dgram_s, " DGRAM/s"),
+                        format_rate(s.udp_drops_s, "/s")
+                    ));
+                }
+                if s.sock_enabled {
+                    out.push(format!(
+                        "SOCK conn/s: {}  no-port {}  failed {}  TIME_WAIT {}",
+                        format_rate(s.sock_conn_s, ""),
+                        format_rate(s.sock_drops_s, "/s"),
+                        format_rate(s.sock_errors_s, "/s"),
+                        s.sock_timewait
+                            .map_or_else(|| "n/a".to_string(), |tw| tw.to_string())
+                    ));
+                }
+            }
+            TuiSection::Ctx => {
+                if s.ctxt_s >= 0.0 {
+                    out.push(format!("CTX switches/s: {}", format_rate(s.ctxt_s, "")));
+                } else {
+                    out.push("CTX switches/s: n/a".to_string());
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Everything the TUI sections read for one redraw.
+pub struct SampleState<'a> {
+    pub args: &'a Args,
+    pub head: usize,
+    pub filled: usize,
+    pub phase_specs: &'a [PhaseSpec],
+    pub phase_idx: usize,
+    pub phase_elapsed_s: u64,
+    pub cpu_pct: f64,
+    pub cpu_ema: f64,
+    pub cpu_hist: &'a [f64],
+    pub cpu_ops_s: f64,
+    pub cpu_ops_hist: &'a [f64],
+    /// Matrix products/s and GFLOP/s of `--cpu-matrix-workers`.
+    pub cpu_mat: Option<(f64, f64)>,
+    /// Vector unit in use while the avx512 workload runs.
+    pub cpu_simd: Option<SimdWidth>,
+    /// Branches/s and misprediction % from `--perf-counters`.
+    pub cpu_branch: Option<(f64, f64)>,
+    /// Integer/float/memory ops/s while the synthetic-mix workload runs.
+    pub cpu_mix: Option<[f64; 3]>,
+    pub cpy_enabled: bool,
+    pub cpy_gb_s: f64,
+    pub cpy_hist: &'a [f64],
+    pub mem_pct: f64,
+    pub mem_ema: f64,
+    pub mem_hist: &'a [f64],
+    pub mem_ops_s: f64,
+    pub mem_ops_hist: &'a [f64],
+    pub mem_gb_s: f64,
+    pub mem_corruptions: u64,
+    pub pgflt_minor_s: f64,
+    pub pgflt_major_s: f64,
+    pub numa_mig_s: f64,
+    pub numa_hit_ratio: f64,
+    pub mem_allocs_s: f64,
+    pub mem_alloc_fail_rate: f64,
+    /// `--mem-latency-test`: ns per pointer-chasing hop.
+    pub mem_lat_ns: Option<f64>,
+    pub disk_mb_s: f64,
+    pub disk_ema: f64,
+    pub disk_hist: &'a [f64],
+    pub disk_peak: f64,
+    pub disk_iops: f64,
+    pub disk_ops_hist: &'a [f64],
+    pub disk_read_mb_s: f64,
+    pub disk_phys_mb_s: f64,
+    pub disk_trace: Option<&'a DiskTrace>,
+    /// Device, I/O scheduler and `nr_requests` of the disk under test.
+    pub disk_queue: Option<&'a str>,
+    /// Cumulative injected stalls, and whether one began this sample.
+    pub disk_stalls: Option<(u64, bool)>,
+    /// `--disk-latency-sla`: writes over the SLA, as % of disk ops.
+    pub disk_sla_pct: Option<f64>,
+    pub disk_fill: Option<(bool, f64)>,
+    pub path_mb_s: &'a [f64],
+    pub fsync_avg_us: f64,
+    pub delta_fsyncs: u64,
+    pub net_enabled: bool,
+    pub net_ops_s: f64,
+    pub net_mb_s: f64,
+    pub udp_enabled: bool,
+    pub udp_dgram_s: f64,
+    pub udp_drops_s: f64,
+    pub sock_enabled: bool,
+    pub sock_conn_s: f64,
+    pub sock_drops_s: f64,
+    /// Failed (refused/reset/timed out) and retried connects per second.
+    pub sock_errors_s: f64,
+    pub sock_timewait: Option<u64>,
+    pub gpu_enabled: bool,
+    pub gpu: Option<(f64, f64, f64, f64, f64)>,
+    /// `--gpu-compute-stress`: GFLOP/s, backend and device, or why no
+    /// backend could be used.
+    pub gpu_compute: Option<Result<(f64, &'a str, &'a str), &'a str>>,
+    pub ctxt_s: f64,
+    pub core_temps: &'a [Option<f64>],
+}
+
+/// Parses `--tui-layout cpu,mem,disk,...`.
+pub fn parse_layout(s: &str) -> Result<Vec<TuiSection>, String> {
+    let mut layout = Vec::new();
+    for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        let section = TuiSection::parse(name).ok_or_else(|| {
+            format!("unknown section '{}' (supported: cpu,cpy,mem,disk,net,gpu,ctx)", name)
+        })?;
+        if layout.contains(&section) {
+            return Err(format!("section '{}' listed twice", name));
+        }
+        layout.push(section);
+    }
+    if layout.is_empty() {
+        return Err("no sections".to_string());
+    }
+    Ok(layout)
+}
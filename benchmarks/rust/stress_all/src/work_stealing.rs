@@ -0,0 +1,108 @@
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Instant;
+
+use super::{format_rate, GLOBAL_STOP};
+
+/// LCG steps per worker thread; the same total is split both ways.
+const ITERS_PER_WORKER: u64 = 1 << 28;
+/// LCG steps per rayon task: small enough that idle threads have plenty to
+/// steal, large enough that a task is mostly arithmetic.
+const TASK_ITERS: u64 = 1 << 16;
+
+/// `n` steps of the CPU workers' LCG workload.
+fn lcg(seed: u64, n: u64) -> u64 {
+    let mut x = seed;
+    for _ in 0..n {
+        x = x
+            .wrapping_mul(1664525)
+            .wrapping_add(1013904223)
+            .rotate_left(5);
+    }
+    std::hint::black_box(x)
+}
+
+/// One row of the comparison table.
+struct ModeResult {
+    mode: &'static str,
+    tasks: u64,
+    secs: f64,
+}
+
+/// Runs the same total LCG workload twice on `workers` threads: first as
+/// one independent share per thread, like the regular CPU workers, then as
+/// `TASK_ITERS`-sized tasks spawned into a single `rayon::scope` that the
+/// pool's threads steal from each other. Prints both side by side. Returns
+/// the process exit code.
+pub fn run(workers: usize) -> i32 {
+    let workers = workers.max(1);
+    let total = ITERS_PER_WORKER * workers as u64;
+    eprintln!(
+        "CPU work-stealing compare: {} threads, {} LCG steps per mode",
+        workers,
+        format_rate(total as f64, "")
+    );
+
+    let start = Instant::now();
+    let threads: Vec<_> = (0..workers as u64)
+        .map(|i| thread::spawn(move || lcg(i + 1, ITERS_PER_WORKER)))
+        .collect();
+    for t in threads {
+        let _ = t.join();
+    }
+    let independent = ModeResult {
+        mode: "independent",
+        tasks: workers as u64,
+        secs: start.elapsed().as_secs_f64(),
+    };
+    if GLOBAL_STOP.load(Ordering::Relaxed) {
+        eprintln!("CPU work-stealing compare: interrupted");
+        return 1;
+    }
+
+    let pool = match rayon::ThreadPoolBuilder::new().num_threads(workers).build() {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("CPU work-stealing compare: cannot start the rayon pool: {}", e);
+            return 1;
+        }
+    };
+    let tasks = total.div_ceil(TASK_ITERS);
+    let start = Instant::now();
+    pool.scope(|s| {
+        for i in 0..tasks {
+            let n = TASK_ITERS.min(total - i * TASK_ITERS);
+            s.spawn(move |_| {
+                if !GLOBAL_STOP.load(Ordering::Relaxed) {
+                    lcg(i + 1, n);
+                }
+            });
+        }
+    });
+    let stealing = ModeResult {
+        mode: "work-stealing",
+        tasks,
+        secs: start.elapsed().as_secs_f64(),
+    };
+    if GLOBAL_STOP.load(Ordering::Relaxed) {
+        eprintln!("CPU work-stealing compare: interrupted");
+        return 1;
+    }
+
+    println!(
+        "{:<14} {:>7} {:>8} {:>9} {:>14} {:>9}",
+        "mode", "threads", "tasks", "time_s", "steps/s", "relative"
+    );
+    for r in [&independent, &stealing] {
+        println!(
+            "{:<14} {:>7} {:>8} {:>9.3} {:>14} {:>8.2}x",
+            r.mode,
+            workers,
+            r.tasks,
+            r.secs,
+            format_rate(total as f64 / r.secs, ""),
+            independent.secs / r.secs
+        );
+    }
+    0
+}
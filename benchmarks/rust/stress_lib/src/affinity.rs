@@ -0,0 +1,73 @@
+extern "C" {
+    fn sched_setaffinity(pid: i32, cpusetsize: usize, mask: *const u64) -> i32;
+    fn syscall(num: i64, ...) -> i64;
+    fn setpriority(which: i32, who: u32, prio: i32) -> i32;
+    fn gettid() -> i32;
+}
+
+const CPU_SET_WORDS: usize = 16;
+const MPOL_BIND: i64 = 2;
+
+#[cfg(target_arch = "x86_64")]
+const SYS_SET_MEMPOLICY: Option<i64> = Some(238);
+#[cfg(target_arch = "aarch64")]
+const SYS_SET_MEMPOLICY: Option<i64> = Some(237);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const SYS_SET_MEMPOLICY: Option<i64> = None;
+
+const PRIO_PROCESS: i32 = 0;
+
+const IOPRIO_WHO_PROCESS: i64 = 1;
+const IOPRIO_CLASS_SHIFT: i64 = 13;
+
+#[cfg(target_arch = "x86_64")]
+const SYS_IOPRIO_SET: Option<i64> = Some(251);
+#[cfg(target_arch = "aarch64")]
+const SYS_IOPRIO_SET: Option<i64> = Some(30);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const SYS_IOPRIO_SET: Option<i64> = None;
+
+/// Pins the calling thread to `cpus`. Returns false if the kernel refused.
+pub fn set_thread_affinity(cpus: &[usize]) -> bool {
+    let mut mask = [0u64; CPU_SET_WORDS];
+    for &cpu in cpus {
+        if cpu < CPU_SET_WORDS * 64 {
+            mask[cpu / 64] |= 1 << (cpu % 64);
+        }
+    }
+    unsafe { sched_setaffinity(0, std::mem::size_of_val(&mask), mask.as_ptr()) == 0 }
+}
+
+/// Binds future allocations of the calling thread to NUMA `node`.
+pub fn bind_thread_mem_to_node(node: usize) -> bool {
+    let Some(nr) = SYS_SET_MEMPOLICY else {
+        return false;
+    };
+    if node >= CPU_SET_WORDS * 64 {
+        return false;
+    }
+    let mut mask = [0u64; CPU_SET_WORDS];
+    mask[node / 64] |= 1 << (node % 64);
+    let maxnode = (CPU_SET_WORDS * 64) as u64;
+    unsafe { syscall(nr, MPOL_BIND, mask.as_ptr(), maxnode) == 0 }
+}
+
+/// Sets the I/O scheduling class and level (0 = highest) of the calling thread.
+pub(crate) fn set_thread_io_priority(class: i64, level: i64) -> bool {
+    let Some(nr) = SYS_IOPRIO_SET else {
+        return false;
+    };
+    let prio = (class << IOPRIO_CLASS_SHIFT) | level;
+    unsafe { syscall(nr, IOPRIO_WHO_PROCESS, 0i64, prio) == 0 }
+}
+
+/// Sets the nice level of the calling thread (Linux nice values are per
+/// thread). Lowering it below the current value needs CAP_SYS_NICE and
+/// fails with EPERM otherwise.
+pub(crate) fn set_thread_nice(nice: i32) -> std::io::Result<()> {
+    if unsafe { setpriority(PRIO_PROCESS, gettid() as u32, nice) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
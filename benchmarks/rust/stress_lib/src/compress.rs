@@ -0,0 +1,90 @@
+use std::io;
+
+/// Compression applied to disk write buffers before they hit the file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiskCompress {
+    None,
+    /// LZ4 block format (`lz4` feature).
+    Lz4,
+    /// zstd level 1 (`zstd` feature).
+    Zstd,
+}
+
+impl DiskCompress {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "none" => Some(DiskCompress::None),
+            "lz4" => Some(DiskCompress::Lz4),
+            "zstd" => Some(DiskCompress::Zstd),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DiskCompress::None => "none",
+            DiskCompress::Lz4 => "lz4",
+            DiskCompress::Zstd => "zstd",
+        }
+    }
+
+    /// Whether this build includes the codec.
+    pub fn available(self) -> bool {
+        match self {
+            DiskCompress::None => true,
+            DiskCompress::Lz4 => cfg!(feature = "lz4"),
+            DiskCompress::Zstd => cfg!(feature = "zstd"),
+        }
+    }
+}
+
+/// Per-worker codec state and output buffer, reused across writes.
+pub(crate) struct Compressor {
+    kind: DiskCompress,
+    #[cfg(any(feature = "lz4", feature = "zstd"))]
+    out: Vec<u8>,
+    #[cfg(feature = "zstd")]
+    zstd: Option<zstd::bulk::Compressor<'static>>,
+}
+
+impl Compressor {
+    pub(crate) fn new(kind: DiskCompress) -> Self {
+        Self {
+            kind,
+            #[cfg(any(feature = "lz4", feature = "zstd"))]
+            out: Vec::new(),
+            #[cfg(feature = "zstd")]
+            zstd: None,
+        }
+    }
+
+    /// `data` compressed into the internal buffer.
+    pub(crate) fn compress<'a>(&'a mut self, data: &'a [u8]) -> io::Result<&'a [u8]> {
+        match self.kind {
+            DiskCompress::None => Ok(data),
+            #[cfg(feature = "lz4")]
+            DiskCompress::Lz4 => {
+                self.out.resize(lz4_flex::block::get_maximum_output_size(data.len()), 0);
+                let n = lz4_flex::block::compress_into(data, &mut self.out)
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                Ok(&self.out[..n])
+            }
+            #[cfg(feature = "zstd")]
+            DiskCompress::Zstd => {
+                let zstd = match self.zstd.as_mut() {
+                    Some(z) => z,
+                    None => self.zstd.insert(zstd::bulk::Compressor::new(1)?),
+                };
+                self.out.clear();
+                self.out.reserve(zstd::zstd_safe::compress_bound(data.len()));
+                zstd.compress_to_buffer(data, &mut self.out)?;
+                Ok(&self.out)
+            }
+            #[allow(unreachable_patterns)]
+            kind => Err(io::Error::other(format!(
+                "built without the `{}` feature",
+                kind.name()
+            ))),
+        }
+    }
+}
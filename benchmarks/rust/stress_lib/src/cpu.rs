@@ -0,0 +1,668 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicU8, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::affinity::{bind_thread_mem_to_node, set_thread_affinity};
+use crate::otel::SpanSampler;
+use crate::simd::{fma_burst, SimdWidth, SIMD_LANES};
+use crate::rng::{seed_from_time, Rng};
+use crate::token_bucket::TokenBucket;
+use crate::{wait_if_paused, Counters, WorkerHandle};
+
+/// Working set for the cache-thrash workload; larger than typical L3.
+const CACHE_THRASH_BYTES: usize = 64 * 1024 * 1024;
+const CACHE_THRASH_STRIDE: usize = 4096 + 64;
+/// Cache-thrash working set as a multiple of the largest detected cache.
+const CACHE_THRASH_FACTOR: usize = 2;
+/// Default memcpy source/destination size.
+pub const MEMCPY_DEFAULT_BYTES: usize = 1024 * 1024;
+/// Hot working set read by the synthetic-mix workload; fits in L2.
+const MIX_HOT_BYTES: usize = 256 * 1024;
+/// Default integer/float/memory weights of the synthetic-mix workload.
+pub const CPU_MIX_DEFAULT: [u32; 3] = [50, 30, 20];
+/// Bytes copied per LCG step of burst, so memcpy bursts last about as long
+/// as the other workloads'.
+const MEMCPY_BYTES_PER_STEP: u64 = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuWorkload {
+    /// Integer LCG chain (the original workload).
+    Lcg,
+    /// Dependent floating-point multiply/add/sqrt chain.
+    Fp,
+    /// SHA-256 block compression.
+    Sha256,
+    /// Strided read-modify-write over a buffer larger than the caches.
+    CacheThrash,
+    /// `copy_from_slice` between two buffers; bytes copied are counted in
+    /// `bytes_transferred()`.
+    Memcpy,
+    /// 512-bit fused multiply-add chains (AVX-512F), falling back to AVX2
+    /// and then the LCG; see [`SimdWidth::detect`].
+    Avx512,
+    /// Integer LCG steps, floating-point sin/cos and random reads from a
+    /// 256 KiB hot set, picked per op by weighted random draw (see
+    /// [`CpuWorker::mix`]); per-type ops in `cpu_mix_ops()`.
+    SyntheticMix,
+    /// LCG steps each followed by three branches on bits of the result, so
+    /// every branch is a coin flip the predictor cannot learn.
+    Branch,
+}
+
+impl CpuWorkload {
+    pub const ALL: [CpuWorkload; 8] = [
+        CpuWorkload::Lcg,
+        CpuWorkload::Fp,
+        CpuWorkload::Sha256,
+        CpuWorkload::CacheThrash,
+        CpuWorkload::Memcpy,
+        CpuWorkload::Avx512,
+        CpuWorkload::SyntheticMix,
+        CpuWorkload::Branch,
+    ];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|w| w.name() == s)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CpuWorkload::Lcg => "lcg",
+            CpuWorkload::Fp => "fp",
+            CpuWorkload::Sha256 => "sha256",
+            CpuWorkload::CacheThrash => "cache-thrash",
+            CpuWorkload::Memcpy => "memcpy",
+            CpuWorkload::Avx512 => "avx512",
+            CpuWorkload::SyntheticMix => "synthetic-mix",
+            CpuWorkload::Branch => "branch",
+        }
+    }
+
+    fn index(self) -> u8 {
+        Self::ALL.iter().position(|w| *w == self).unwrap_or(0) as u8
+    }
+}
+
+/// One data or unified cache of CPU 0, as listed in sysfs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheLevel {
+    pub level: u32,
+    /// Data-only (`L1d`) rather than unified.
+    pub data: bool,
+    pub bytes: u64,
+}
+
+impl CacheLevel {
+    /// `L1d`, `L2`, ...
+    pub fn name(self) -> String {
+        format!("L{}{}", self.level, if self.data { "d" } else { "" })
+    }
+}
+
+/// Reads `/sys/devices/system/cpu/cpu0/cache/index*/{level,type,size}`,
+/// skipping instruction caches, in sysfs order. Sizes are `<n>K`, `<n>M` or
+/// plain bytes. Empty if sysfs has no cache info.
+pub fn cache_levels() -> Vec<CacheLevel> {
+    let mut levels = Vec::new();
+    for index in 0.. {
+        let dir = format!("/sys/devices/system/cpu/cpu0/cache/index{}", index);
+        let read = |name: &str| std::fs::read_to_string(format!("{}/{}", dir, name)).ok();
+        let (Some(level), Some(size)) = (read("level"), read("size")) else {
+            break;
+        };
+        let kind = read("type").unwrap_or_default();
+        if kind.trim() == "Instruction" {
+            continue;
+        }
+        let size = size.trim();
+        let bytes = match size.strip_suffix('K') {
+            Some(kb) => kb.parse::<u64>().ok().map(|kb| kb * 1024),
+            None => match size.strip_suffix('M') {
+                Some(mb) => mb.parse::<u64>().ok().map(|mb| mb * 1024 * 1024),
+                None => size.parse().ok(),
+            },
+        };
+        let (Ok(level), Some(bytes)) = (level.trim().parse(), bytes) else {
+            continue;
+        };
+        levels.push(CacheLevel {
+            level,
+            data: kind.trim() == "Data",
+            bytes,
+        });
+    }
+    levels
+}
+
+/// Data/unified cache sizes of CPU 0 from sysfs; 0 for a level that is
+/// absent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheSizes {
+    pub l1_kb: u32,
+    pub l2_kb: u32,
+    pub l3_kb: u32,
+}
+
+impl CacheSizes {
+    /// Sizes from [`cache_levels`]. `None` if no level could be read.
+    pub fn detect() -> Option<Self> {
+        let mut sizes = Self::default();
+        for cache in cache_levels() {
+            let kb = (cache.bytes / 1024).min(u32::MAX as u64) as u32;
+            match cache.level {
+                1 => sizes.l1_kb = kb,
+                2 => sizes.l2_kb = kb,
+                3 => sizes.l3_kb = kb,
+                _ => {}
+            }
+        }
+        (sizes != Self::default()).then_some(sizes)
+    }
+
+    /// Size of the outermost cache, in KiB.
+    pub fn largest_kb(self) -> u32 {
+        self.l3_kb.max(self.l2_kb).max(self.l1_kb)
+    }
+
+    /// Cache-thrash working set: twice the outermost cache, so every access
+    /// misses in all levels.
+    pub fn thrash_bytes(self) -> usize {
+        match self.largest_kb() {
+            0 => CACHE_THRASH_BYTES,
+            kb => kb as usize * 1024 * CACHE_THRASH_FACTOR,
+        }
+    }
+}
+
+/// Shared, switchable workload for a group of CPU workers. Workers pick up a
+/// change at their next burst.
+#[derive(Clone)]
+pub struct CpuWorkloadControl(Arc<AtomicU8>);
+
+impl CpuWorkloadControl {
+    pub fn new(workload: CpuWorkload) -> Self {
+        Self(Arc::new(AtomicU8::new(workload.index())))
+    }
+
+    pub fn set(&self, workload: CpuWorkload) {
+        self.0.store(workload.index(), Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> CpuWorkload {
+        CpuWorkload::ALL[self.0.load(Ordering::Relaxed) as usize % CpuWorkload::ALL.len()]
+    }
+}
+
+impl Default for CpuWorkloadControl {
+    fn default() -> Self {
+        Self::new(CpuWorkload::Lcg)
+    }
+}
+
+/// How a CPU worker gives the core back to the scheduler after each burst.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CpuYieldStrategy {
+    /// Never: spin for maximum heat and power draw.
+    None,
+    /// `thread::yield_now()` (sched_yield) after every burst.
+    Yield,
+    /// `thread::park()` after every burst until [`WorkerHandle::unpark`]
+    /// (or `stop()`).
+    Park,
+    /// A 200us sleep after about one burst in 1200 (the default).
+    #[default]
+    Sleep,
+}
+
+impl CpuYieldStrategy {
+    pub const ALL: [CpuYieldStrategy; 4] = [
+        CpuYieldStrategy::None,
+        CpuYieldStrategy::Yield,
+        CpuYieldStrategy::Park,
+        CpuYieldStrategy::Sleep,
+    ];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|y| y.name() == s)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CpuYieldStrategy::None => "none",
+            CpuYieldStrategy::Yield => "yield",
+            CpuYieldStrategy::Park => "park",
+            CpuYieldStrategy::Sleep => "sleep",
+        }
+    }
+}
+
+/// Runs the selected workload in randomized bursts with occasional short sleeps.
+#[derive(Clone, Default)]
+pub struct CpuWorker {
+    affinity: Option<Vec<usize>>,
+    mem_node: Option<usize>,
+    ops_target: Option<f64>,
+    stack_size: Option<usize>,
+    pacing: Pacing,
+    workload: CpuWorkloadControl,
+    memcpy_bytes: Option<usize>,
+    thrash_bytes: Option<usize>,
+    mix: Option<[u32; 3]>,
+    span_rate: f64,
+}
+
+/// How a CPU worker gives the core back between bursts.
+#[derive(Clone, Copy, Default)]
+struct Pacing {
+    /// What to do after each burst.
+    strategy: CpuYieldStrategy,
+    /// `thread::yield_now()` after every N ops; overrides `strategy`.
+    yield_after_ops: Option<u64>,
+}
+
+impl CpuWorker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the worker thread to the given logical CPUs.
+    pub fn affinity(mut self, cpus: Vec<usize>) -> Self {
+        self.affinity = Some(cpus);
+        self
+    }
+
+    /// Binds the worker thread's memory allocations to NUMA `node`.
+    pub fn mem_node(mut self, node: usize) -> Self {
+        self.mem_node = Some(node);
+        self
+    }
+
+    /// Throttles this worker to `ops_per_s` with a token bucket.
+    pub fn ops_target(mut self, ops_per_s: f64) -> Self {
+        self.ops_target = Some(ops_per_s).filter(|r| *r > 0.0);
+        self
+    }
+
+    /// Spins without the occasional 200us sleep, for maximum heat and power
+    /// draw.
+    pub fn busy_wait(self) -> Self {
+        self.yield_strategy(CpuYieldStrategy::None)
+    }
+
+    /// Sets how the worker yields after each burst (default
+    /// [`CpuYieldStrategy::Sleep`]).
+    pub fn yield_strategy(mut self, strategy: CpuYieldStrategy) -> Self {
+        self.pacing.strategy = strategy;
+        self
+    }
+
+    /// Calls `thread::yield_now()` after every `ops` operations instead of
+    /// the occasional sleep.
+    pub fn yield_after_ops(mut self, ops: u64) -> Self {
+        self.pacing.yield_after_ops = Some(ops).filter(|n| *n > 0);
+        self
+    }
+
+    /// Buffer size copied by the memcpy workload (default:
+    /// [`MEMCPY_DEFAULT_BYTES`]).
+    pub fn memcpy_bytes(mut self, bytes: usize) -> Self {
+        self.memcpy_bytes = Some(bytes.max(1));
+        self
+    }
+
+    /// Sizes the cache-thrash working set from `caches` (see
+    /// [`CacheSizes::thrash_bytes`]) instead of a fixed 64 MiB.
+    pub fn cache_sizes(mut self, caches: CacheSizes) -> Self {
+        self.thrash_bytes = Some(caches.thrash_bytes());
+        self
+    }
+
+    /// Relative weights of integer, floating-point and memory ops in the
+    /// synthetic-mix workload (default: [`CPU_MIX_DEFAULT`]).
+    pub fn mix(mut self, integer: u32, float: u32, memory: u32) -> Self {
+        self.mix = Some([integer, float, memory]).filter(|w| w.iter().sum::<u32>() > 0);
+        self
+    }
+
+    /// Exports a span for a `rate` (0.0-1.0) fraction of bursts to the global
+    /// OpenTelemetry tracer (`otel` feature).
+    pub fn otel_sample_rate(mut self, rate: f64) -> Self {
+        self.span_rate = rate;
+        self
+    }
+
+    /// Spawns the worker thread with a `bytes` stack instead of the OS default.
+    pub fn stack_size(mut self, bytes: usize) -> Self {
+        self.stack_size = Some(bytes);
+        self
+    }
+
+    /// Runs a fixed workload.
+    pub fn workload(mut self, workload: CpuWorkload) -> Self {
+        self.workload = CpuWorkloadControl::new(workload);
+        self
+    }
+
+    /// Follows a workload shared with other workers (see [`CpuWorkloadControl`]).
+    pub fn workload_control(mut self, control: CpuWorkloadControl) -> Self {
+        self.workload = control;
+        self
+    }
+
+    pub fn spawn(self) -> WorkerHandle<CpuWorkerStats> {
+        WorkerHandle::spawn_with_stack(self.stack_size, move |stop, counters| {
+            if let Some(cpus) = self.affinity.as_ref() {
+                if !set_thread_affinity(cpus) {
+                    log::warn!("CPU worker: cannot set affinity to {:?}", cpus);
+                }
+            }
+            if let Some(node) = self.mem_node {
+                if !bind_thread_mem_to_node(node) {
+                    log::warn!("CPU worker: cannot bind memory to node {}", node);
+                }
+            }
+            let bucket = self.ops_target.map(TokenBucket::new);
+            log::debug!("CPU worker: start (ops target {:?})", self.ops_target);
+            let state = WorkloadState {
+                memcpy_bytes: self.memcpy_bytes.unwrap_or(MEMCPY_DEFAULT_BYTES),
+                thrash_bytes: self.thrash_bytes.unwrap_or(CACHE_THRASH_BYTES),
+                mix: self.mix.unwrap_or(CPU_MIX_DEFAULT),
+                ..Default::default()
+            };
+            let spans = SpanSampler::new(self.span_rate);
+            let stats =
+                cpu_worker_ops(stop, counters, self.workload, self.pacing, bucket, state, spans);
+            log::debug!("CPU worker: stop");
+            stats
+        })
+    }
+}
+
+/// Totals a CPU worker returns from [`WorkerHandle::join`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CpuWorkerStats {
+    pub total_ops: u64,
+    pub total_bursts: u64,
+    /// Time spent sleeping (throttling and scheduler-yield pauses).
+    pub total_sleep_us: u64,
+    pub peak_burst_size: u32,
+}
+
+fn cpu_worker_ops(
+    stop: Arc<AtomicBool>,
+    counters: Arc<Counters>,
+    workload: CpuWorkloadControl,
+    pacing: Pacing,
+    mut bucket: Option<TokenBucket>,
+    mut state: WorkloadState,
+    mut spans: SpanSampler,
+) -> CpuWorkerStats {
+    let mut rng = Rng::new(seed_from_time());
+    let mut stats = CpuWorkerStats::default();
+    let mut slept = Duration::ZERO;
+    let mut since_yield = 0u64;
+    while !stop.load(Ordering::Relaxed) {
+        wait_if_paused(&stop, &counters);
+        let burst = 5000 + (rng.next_u32() % 15000) as u64;
+        let current = workload.get();
+        let span_start = spans.start();
+        let ops = state.run(current, burst);
+        if let Some(t0) = span_start {
+            spans.cpu_span(current.name(), ops, t0);
+        }
+        counters.ops.fetch_add(ops, Ordering::Relaxed);
+        if state.copied > 0 {
+            counters.bytes.fetch_add(state.copied, Ordering::Relaxed);
+            state.copied = 0;
+        }
+        if state.mix_done != [0; 3] {
+            for (total, n) in counters.mix_ops.iter().zip(state.mix_done) {
+                total.fetch_add(n, Ordering::Relaxed);
+            }
+            state.mix_done = [0; 3];
+        }
+        stats.total_ops += ops;
+        stats.total_bursts += 1;
+        stats.peak_burst_size = stats.peak_burst_size.max(ops as u32);
+        if let Some(b) = bucket.as_mut() {
+            slept += b.consume(ops, &stop);
+        }
+        if let Some(every) = pacing.yield_after_ops {
+            since_yield += ops;
+            if since_yield >= every {
+                since_yield %= every;
+                thread::yield_now();
+            }
+        } else {
+            match pacing.strategy {
+                CpuYieldStrategy::None => {}
+                CpuYieldStrategy::Yield => thread::yield_now(),
+                CpuYieldStrategy::Park => {
+                    let t0 = Instant::now();
+                    if !stop.load(Ordering::Relaxed) {
+                        thread::park();
+                    }
+                    slept += t0.elapsed();
+                }
+                CpuYieldStrategy::Sleep => {
+                    if rng.next_u32().is_multiple_of(1200) {
+                        let pause = Duration::from_micros(200);
+                        thread::sleep(pause);
+                        slept += pause;
+                    }
+                }
+            }
+        }
+    }
+    std::hint::black_box(state);
+    stats.total_sleep_us = slept.as_micros() as u64;
+    stats
+}
+
+#[derive(Default)]
+struct WorkloadState {
+    x: u64,
+    fa: f64,
+    fb: f64,
+    sha: [u32; 8],
+    block: [u32; 16],
+    thrash: Vec<u8>,
+    thrash_idx: usize,
+    thrash_bytes: usize,
+    memcpy_bytes: usize,
+    memcpy_src: Vec<u8>,
+    memcpy_dst: Vec<u8>,
+    /// Bytes copied since the worker last drained it into its counters.
+    copied: u64,
+    simd: Option<SimdWidth>,
+    simd_acc: Vec<f64>,
+    mix: [u32; 3],
+    mix_rng: Option<Rng>,
+    mix_hot: Vec<u8>,
+    /// Integer/float/memory ops since the worker last drained it.
+    mix_done: [u64; 3],
+}
+
+impl WorkloadState {
+    /// Runs roughly `burst` LCG-steps worth of work and returns the ops done.
+    fn run(&mut self, workload: CpuWorkload, burst: u64) -> u64 {
+        match workload {
+            CpuWorkload::Lcg => {
+                let mut x = self.x;
+                for _ in 0..burst {
+                    x = x
+                        .wrapping_mul(1664525)
+                        .wrapping_add(1013904223)
+                        .rotate_left(5);
+                }
+                self.x = x;
+                burst
+            }
+            CpuWorkload::Fp => {
+                let (mut a, mut b) = (self.fa.max(1.0), self.fb);
+                for _ in 0..burst {
+                    a = a.mul_add(0.999_999_9, 1e-7);
+                    b = (b * a).sqrt() + 0.5;
+                }
+                self.fa = a;
+                self.fb = b;
+                burst
+            }
+            CpuWorkload::Sha256 => {
+                let blocks = (burst / 64).max(1);
+                for i in 0..blocks {
+                    self.block[(i % 16) as usize] ^= self.sha[0].wrapping_add(i as u32);
+                    sha256_compress(&mut self.sha, &self.block);
+                }
+                blocks
+            }
+            CpuWorkload::CacheThrash => {
+                if self.thrash.is_empty() {
+                    self.thrash = vec![1u8; self.thrash_bytes];
+                }
+                let len = self.thrash.len();
+                let mut idx = self.thrash_idx;
+                for _ in 0..burst {
+                    idx = (idx + CACHE_THRASH_STRIDE) % len;
+                    self.thrash[idx] = self.thrash[idx].wrapping_add(1);
+                }
+                self.thrash_idx = idx;
+                burst
+            }
+            CpuWorkload::Memcpy => {
+                if self.memcpy_src.is_empty() {
+                    self.memcpy_src = vec![0x5a; self.memcpy_bytes];
+                    self.memcpy_dst = vec![0; self.memcpy_bytes];
+                }
+                let len = self.memcpy_src.len() as u64;
+                let copies = (burst * MEMCPY_BYTES_PER_STEP / len).max(1);
+                for _ in 0..copies {
+                    self.memcpy_dst.copy_from_slice(&self.memcpy_src);
+                    std::hint::black_box(&mut self.memcpy_dst);
+                }
+                self.copied += copies * len;
+                copies
+            }
+            CpuWorkload::Avx512 => {
+                let width = *self.simd.get_or_insert_with(SimdWidth::detect);
+                if self.simd_acc.is_empty() {
+                    self.simd_acc = vec![1.0; SIMD_LANES];
+                }
+                if fma_burst(width, &mut self.simd_acc, burst) {
+                    burst
+                } else {
+                    self.run(CpuWorkload::Lcg, burst)
+                }
+            }
+            CpuWorkload::SyntheticMix => {
+                if self.mix_hot.is_empty() {
+                    self.mix_hot = vec![1u8; MIX_HOT_BYTES];
+                }
+                let rng = self.mix_rng.get_or_insert_with(|| Rng::new(seed_from_time()));
+                let [int_w, fp_w, mem_w] = self.mix;
+                let total = (int_w + fp_w + mem_w).max(1) as u64;
+                let (mut x, mut f, mut acc) = (self.x, self.fa, 0u8);
+                for _ in 0..burst {
+                    let r = rng.next_u64();
+                    let pick = (r % total) as u32;
+                    if pick < int_w {
+                        x = x
+                            .wrapping_mul(1664525)
+                            .wrapping_add(1013904223)
+                            .rotate_left(5);
+                        self.mix_done[0] += 1;
+                    } else if pick < int_w + fp_w {
+                        f = f.sin() + (f * 0.5).cos();
+                        self.mix_done[1] += 1;
+                    } else {
+                        acc = acc.wrapping_add(self.mix_hot[(r >> 32) as usize % MIX_HOT_BYTES]);
+                        self.mix_done[2] += 1;
+                    }
+                }
+                self.x = x ^ acc as u64;
+                self.fa = f;
+                burst
+            }
+            CpuWorkload::Branch => {
+                let mut x = self.x;
+                let mut acc = 0u64;
+                for _ in 0..burst {
+                    x = x
+                        .wrapping_mul(1664525)
+                        .wrapping_add(1013904223)
+                        .rotate_left(5);
+                    // black_box in one arm keeps LLVM from turning the
+                    // branches into conditional moves.
+                    if x & 1 == 0 {
+                        acc = std::hint::black_box(acc.wrapping_add(x >> 7));
+                    } else {
+                        acc ^= x.rotate_left(13);
+                    }
+                    if x & 2 == 0 {
+                        acc = std::hint::black_box(acc.rotate_left(3));
+                    } else {
+                        acc = acc.wrapping_mul(3);
+                    }
+                    if x & 4 == 0 {
+                        acc = std::hint::black_box(acc ^ (x >> 11));
+                    } else {
+                        acc = acc.wrapping_sub(x);
+                    }
+                }
+                self.x = x ^ (acc & 0xff);
+                burst
+            }
+        }
+    }
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+fn sha256_compress(state: &mut [u32; 8], block: &[u32; 16]) {
+    let mut w = [0u32; 64];
+    w[..16].copy_from_slice(block);
+    for i in 16..64 {
+        let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+        let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+        w[i] = w[i - 16]
+            .wrapping_add(s0)
+            .wrapping_add(w[i - 7])
+            .wrapping_add(s1);
+    }
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = *state;
+    for i in 0..64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let ch = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(ch)
+            .wrapping_add(SHA256_K[i])
+            .wrapping_add(w[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let maj = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(maj);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+    }
+    for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+        *s = s.wrapping_add(v);
+    }
+}
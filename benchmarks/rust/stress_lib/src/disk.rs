@@ -0,0 +1,1264 @@
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::affinity::{set_thread_io_priority, set_thread_nice};
+use crate::compress::{Compressor, DiskCompress};
+use crate::otel::SpanSampler;
+use crate::rng::{seed_from_time, Rng, Zipf};
+use crate::token_bucket::TokenBucket;
+use crate::{wait_if_paused, Counters, WorkerHandle};
+
+extern "C" {
+    fn fsync(fd: i32) -> i32;
+    fn fallocate(fd: i32, mode: i32, offset: i64, len: i64) -> i32;
+    fn ioctl(fd: i32, request: u64, ...) -> i32;
+}
+
+#[cfg(target_arch = "aarch64")]
+const O_DIRECT: i32 = 0o200000;
+#[cfg(not(target_arch = "aarch64"))]
+const O_DIRECT: i32 = 0o40000;
+/// `_IOR(0x12, 114, size_t)`
+const BLKGETSIZE64: u64 = 0x8008_1272;
+/// Buffer, offset and size alignment required by O_DIRECT.
+const DIRECT_ALIGN: usize = 4096;
+
+/// Size in bytes of the block device at `path` (`BLKGETSIZE64`).
+pub fn block_device_size(path: &Path) -> Option<u64> {
+    let file = File::open(path).ok()?;
+    let mut size = 0u64;
+    let rc = unsafe { ioctl(file.as_raw_fd(), BLKGETSIZE64, &mut size as *mut u64) };
+    (rc == 0 && size > 0).then_some(size)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TraceOp {
+    Read { offset: u64, size: usize },
+    Write { offset: u64, size: usize },
+}
+
+/// A parsed I/O trace: one `W|R offset_bytes size_bytes [time_s]` per line.
+#[derive(Clone, Debug)]
+pub struct DiskTrace {
+    pub ops: Vec<TraceOp>,
+    /// Ops/s implied by the optional timestamp column, if present.
+    pub natural_ops_s: Option<f64>,
+}
+
+impl DiskTrace {
+    /// Parses a trace, skipping blank and `#` comment lines. Every op must fit
+    /// inside a file of `max_bytes`.
+    pub fn parse(text: &str, max_bytes: u64) -> Result<Self, String> {
+        let mut ops = Vec::new();
+        let mut first_ts: Option<f64> = None;
+        let mut last_ts: Option<f64> = None;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let lineno = i + 1;
+            let mut parts = line.split_whitespace();
+            let kind = parts.next().unwrap_or("");
+            let offset = parts
+                .next()
+                .and_then(|v| v.parse::<u64>().ok())
+                .ok_or_else(|| format!("line {}: invalid offset", lineno))?;
+            let size = parts
+                .next()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|&v| v > 0)
+                .ok_or_else(|| format!("line {}: invalid size", lineno))?;
+            if let Some(ts) = parts.next() {
+                let ts = ts
+                    .parse::<f64>()
+                    .map_err(|_| format!("line {}: invalid timestamp", lineno))?;
+                first_ts.get_or_insert(ts);
+                last_ts = Some(ts);
+            }
+            let end = offset
+                .checked_add(size as u64)
+                .ok_or_else(|| format!("line {}: offset {} + size {} overflows", lineno, offset, size))?;
+            if end > max_bytes {
+                return Err(format!(
+                    "line {}: offset {} + size {} exceeds file size {}",
+                    lineno, offset, size, max_bytes
+                ));
+            }
+            ops.push(match kind {
+                "W" | "w" => TraceOp::Write { offset, size },
+                "R" | "r" => TraceOp::Read { offset, size },
+                _ => return Err(format!("line {}: unknown op '{}'", lineno, kind)),
+            });
+        }
+        if ops.is_empty() {
+            return Err("trace has no operations".to_string());
+        }
+        let natural_ops_s = match (first_ts, last_ts) {
+            (Some(a), Some(b)) if b > a => Some(ops.len() as f64 / (b - a)),
+            _ => None,
+        };
+        Ok(Self { ops, natural_ops_s })
+    }
+}
+
+/// Simulated storage tier: a sleep sampled from the model is injected before
+/// each I/O so a fast device can stand in for a slower one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LatencyModel {
+    /// 1-12ms, growing with seek distance.
+    Hdd,
+    /// 50-200us.
+    Ssd,
+    /// 10-50us.
+    Nvme,
+}
+
+impl LatencyModel {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "hdd" => Some(LatencyModel::Hdd),
+            "ssd" => Some(LatencyModel::Ssd),
+            "nvme" => Some(LatencyModel::Nvme),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LatencyModel::Hdd => "hdd",
+            LatencyModel::Ssd => "ssd",
+            LatencyModel::Nvme => "nvme",
+        }
+    }
+
+    fn sample_us(self, rng: &mut Rng, from: u64, to: u64, span: u64) -> u64 {
+        match self {
+            LatencyModel::Hdd => {
+                let frac = from.abs_diff(to) as f64 / span.max(1) as f64;
+                let seek = (10_000.0 * frac) as u64;
+                let rotation = (rng.next_u32() % 1000) as u64;
+                (1000 + seek + rotation).min(12_000)
+            }
+            LatencyModel::Ssd => 50 + (rng.next_u32() % 151) as u64,
+            LatencyModel::Nvme => 10 + (rng.next_u32() % 41) as u64,
+        }
+    }
+}
+
+/// I/O scheduler priority for the disk worker thread (`ioprio_set`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IoPriority {
+    /// `IOPRIO_CLASS_RT`; usually needs CAP_SYS_ADMIN.
+    Realtime,
+    /// Best-effort, level 0.
+    High,
+    /// Best-effort, level 4 (the kernel default).
+    Normal,
+    /// Best-effort, level 7.
+    Low,
+    /// `IOPRIO_CLASS_IDLE`: only served when the disk is otherwise idle.
+    Idle,
+}
+
+impl IoPriority {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "realtime" => Some(IoPriority::Realtime),
+            "high" => Some(IoPriority::High),
+            "normal" => Some(IoPriority::Normal),
+            "low" => Some(IoPriority::Low),
+            "idle" => Some(IoPriority::Idle),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IoPriority::Realtime => "realtime",
+            IoPriority::High => "high",
+            IoPriority::Normal => "normal",
+            IoPriority::Low => "low",
+            IoPriority::Idle => "idle",
+        }
+    }
+
+    /// (ioprio class, level)
+    fn class_level(self) -> (i64, i64) {
+        match self {
+            IoPriority::Realtime => (1, 4),
+            IoPriority::High => (2, 0),
+            IoPriority::Normal => (2, 4),
+            IoPriority::Low => (2, 7),
+            IoPriority::Idle => (3, 0),
+        }
+    }
+}
+
+/// Access pattern of the disk worker's write loop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IoPattern {
+    /// Random offsets from the start.
+    Random,
+    /// Sequential fill of the whole file, then random overwrites.
+    ZipSequentially,
+}
+
+impl IoPattern {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "random" => Some(IoPattern::Random),
+            "zip-sequentially" => Some(IoPattern::ZipSequentially),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IoPattern::Random => "random",
+            IoPattern::ZipSequentially => "zip-sequentially",
+        }
+    }
+}
+
+/// How the random I/O loops pick offsets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiskAccessPattern {
+    /// Uniformly random offsets (the default).
+    Uniform,
+    /// Zipf-distributed 4 KiB slots: a few hot slots, scattered over the
+    /// file, take most of the accesses, as in an OLTP database.
+    Hotspot,
+    /// Each block follows the previous one, wrapping at the end of the file.
+    Sequential,
+}
+
+impl DiskAccessPattern {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "uniform" => Some(DiskAccessPattern::Uniform),
+            "hotspot" => Some(DiskAccessPattern::Hotspot),
+            "sequential" => Some(DiskAccessPattern::Sequential),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DiskAccessPattern::Uniform => "uniform",
+            DiskAccessPattern::Hotspot => "hotspot",
+            DiskAccessPattern::Sequential => "sequential",
+        }
+    }
+}
+
+/// Content of the disk worker's write buffers. Without one the buffer holds
+/// a block-number byte at the start of each 4 KiB page and zeros elsewhere.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiskWritePattern {
+    /// All zero bytes: compresses (and dedups) completely.
+    Zero,
+    /// All 0xFF bytes: as compressible as zeros, but not the zero-page
+    /// special case some controllers have.
+    Ones,
+    /// Random bytes from the worker's RNG: incompressible. Filled once per
+    /// buffer, so identical blocks still dedup.
+    Random,
+    /// A repeating sequence of up to [`DiskWritePattern::PATTERN_MAX`] bytes.
+    Pattern(Vec<u8>),
+}
+
+impl DiskWritePattern {
+    /// Longest `Pattern` sequence.
+    pub const PATTERN_MAX: usize = 64;
+
+    /// Parses zero, ones, random or pattern; `pattern` repeats the bytes
+    /// 0x00-0x3F until replaced with [`DiskWritePattern::Pattern`].
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "zero" => Some(DiskWritePattern::Zero),
+            "ones" => Some(DiskWritePattern::Ones),
+            "random" => Some(DiskWritePattern::Random),
+            "pattern" => Some(DiskWritePattern::Pattern(
+                (0..Self::PATTERN_MAX as u8).collect(),
+            )),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DiskWritePattern::Zero => "zero",
+            DiskWritePattern::Ones => "ones",
+            DiskWritePattern::Random => "random",
+            DiskWritePattern::Pattern(_) => "pattern",
+        }
+    }
+}
+
+/// Multiplier (prime) scattering Zipf ranks over the file's slots, so the
+/// hottest slots are not all at the start.
+const HOTSPOT_STRIDE: u128 = 2_654_435_761;
+
+/// Reads, writes or both, for the disk worker's random I/O loop.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiskIoType {
+    /// Random reads only. An existing file is read as is (and opened
+    /// read-only); otherwise the file is created and filled first.
+    Read,
+    /// Random writes only (the default).
+    Write,
+    /// Random reads and writes, `read_pct` percent reads.
+    Mixed,
+}
+
+impl DiskIoType {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "read" => Some(DiskIoType::Read),
+            "write" => Some(DiskIoType::Write),
+            "mixed" => Some(DiskIoType::Mixed),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DiskIoType::Read => "read",
+            DiskIoType::Write => "write",
+            DiskIoType::Mixed => "mixed",
+        }
+    }
+}
+
+/// Where a `ZipSequentially` worker is in its run.
+#[derive(Clone, Copy)]
+enum DiskPhase {
+    Fill { next_offset: u64 },
+    Overwrite,
+}
+
+/// Random-offset block writes (4 KiB to 4 MiB) into a file of `max_bytes`.
+#[derive(Clone)]
+pub struct DiskWorker {
+    path: PathBuf,
+    max_bytes: u64,
+    fsync_interval: u64,
+    trace: Option<Arc<DiskTrace>>,
+    latency_model: Option<LatencyModel>,
+    io_priority: Option<IoPriority>,
+    nice: Option<i32>,
+    seed: Option<u64>,
+    rmw: bool,
+    direct: bool,
+    io_pattern: IoPattern,
+    aio_depth: usize,
+    compress: DiskCompress,
+    io_type: DiskIoType,
+    read_pct: u32,
+    pre_allocate: bool,
+    span_rate: f64,
+    access_pattern: DiskAccessPattern,
+    zipf_s: f64,
+    stall: Option<(u64, Duration)>,
+    write_pattern: Option<DiskWritePattern>,
+    latency_sla: Option<Duration>,
+    latency_sigfig: Option<u8>,
+    iops_limit: Option<f64>,
+}
+
+impl DiskWorker {
+    pub fn new(path: PathBuf, max_bytes: u64) -> Self {
+        Self {
+            path,
+            max_bytes,
+            fsync_interval: 0,
+            trace: None,
+            latency_model: None,
+            io_priority: None,
+            nice: None,
+            seed: None,
+            rmw: false,
+            direct: false,
+            io_pattern: IoPattern::Random,
+            aio_depth: 0,
+            compress: DiskCompress::None,
+            io_type: DiskIoType::Write,
+            read_pct: 50,
+            pre_allocate: false,
+            span_rate: 0.0,
+            access_pattern: DiskAccessPattern::Uniform,
+            zipf_s: 1.2,
+            stall: None,
+            write_pattern: None,
+            latency_sla: None,
+            latency_sigfig: None,
+            iops_limit: None,
+        }
+    }
+
+    /// Replays `trace` in a loop instead of issuing random writes.
+    pub fn trace(mut self, trace: Arc<DiskTrace>) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// Calls fsync after every `ops` writes (0 = never).
+    pub fn fsync_interval(mut self, ops: u64) -> Self {
+        self.fsync_interval = ops;
+        self
+    }
+
+    /// Sleeps for a model-specific seek latency before every I/O.
+    pub fn latency_model(mut self, model: LatencyModel) -> Self {
+        self.latency_model = Some(model);
+        self
+    }
+
+    /// Sets the worker thread's I/O scheduling class before it starts.
+    pub fn io_priority(mut self, priority: IoPriority) -> Self {
+        self.io_priority = Some(priority);
+        self
+    }
+
+    /// Sets the worker thread's nice level (-20..19) before it starts.
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+
+    /// Reads each random block, XORs it with random bytes and writes it back
+    /// instead of plain writes. Ignored when replaying a trace.
+    pub fn read_modify_write(mut self) -> Self {
+        self.rmw = true;
+        self
+    }
+
+    /// Selects the write pattern. Ignored when replaying a trace.
+    pub fn io_pattern(mut self, pattern: IoPattern) -> Self {
+        self.io_pattern = pattern;
+        self
+    }
+
+    /// Selects reads, writes or a mix; `read_pct` (0-100) is the share of
+    /// reads in `Mixed` mode. `Read` and `Mixed` replace the write pattern,
+    /// RMW and AIO modes. Ignored when replaying a trace.
+    pub fn io_type(mut self, io_type: DiskIoType, read_pct: u32) -> Self {
+        self.io_type = io_type;
+        self.read_pct = read_pct.min(100);
+        self
+    }
+
+    /// Selects how random blocks are placed; `zipf_s` is the Zipf exponent
+    /// for `Hotspot` (larger = hotter hot spots). Applies to random writes,
+    /// reads, RMW and AIO, and to the overwrite phase of zip-sequentially.
+    pub fn access_pattern(mut self, pattern: DiskAccessPattern, zipf_s: f64) -> Self {
+        self.access_pattern = pattern;
+        self.zipf_s = zipf_s;
+        self
+    }
+
+    /// Sleeps for `duration` after every `every_ops` reads and writes,
+    /// simulating a device stall; counted in `disk_stall_count()`. A stop
+    /// request cuts the stall short. Not applied to AIO writes.
+    pub fn stall(mut self, every_ops: u64, duration: Duration) -> Self {
+        self.stall = Some((every_ops, duration)).filter(|(n, _)| *n > 0);
+        self
+    }
+
+    /// Counts writes that take longer than `sla` (seek, simulated latency and
+    /// the write itself) in `disk_sla_violation_count()`, out of
+    /// `disk_sla_checked_count()` timed writes. AIO writes are timed from
+    /// submission to observed completion.
+    pub fn latency_sla(mut self, sla: Duration) -> Self {
+        self.latency_sla = Some(sla);
+        self
+    }
+
+    /// Caps reads and writes at `iops` per second with a token bucket that
+    /// can bank up to `2 * iops` ops for short bursts (0 = unlimited).
+    pub fn iops_limit(mut self, iops: f64) -> Self {
+        self.iops_limit = Some(iops).filter(|r| *r > 0.0);
+        self
+    }
+
+    /// Records every write's latency (as for `latency_sla`) in a histogram
+    /// with `sigfig` significant digits, read with
+    /// `WorkerHandle::latency_histogram`. Needs the `hdr` feature.
+    pub fn latency_histogram(mut self, sigfig: u8) -> Self {
+        self.latency_sigfig = Some(sigfig);
+        self
+    }
+
+    /// Issues random writes through POSIX AIO with up to `depth` requests in
+    /// flight (0 = synchronous). Latency models and fsync intervals do not
+    /// apply in this mode. Check [`DiskWorker::aio_supported`] first; where
+    /// it is false the worker reports an error and stops.
+    pub fn aio_depth(mut self, depth: usize) -> Self {
+        self.aio_depth = depth;
+        self
+    }
+
+    /// Whether this build can issue POSIX AIO writes (glibc Linux on x86_64
+    /// or aarch64).
+    pub fn aio_supported() -> bool {
+        cfg!(all(
+            target_os = "linux",
+            target_env = "gnu",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        ))
+    }
+
+    /// Compresses each write buffer before writing it. Byte counters stay
+    /// logical (uncompressed); `bytes_physical()` counts what was written.
+    /// Not applied to AIO writes.
+    pub fn compress(mut self, kind: DiskCompress) -> Self {
+        self.compress = kind;
+        self
+    }
+
+    /// Fills write buffers with `pattern` instead of the default page
+    /// markers, to see how much transparent compression in the device helps.
+    pub fn write_pattern(mut self, pattern: DiskWritePattern) -> Self {
+        self.write_pattern = Some(pattern);
+        self
+    }
+
+    /// Allocates the file's full extent with `fallocate` before the first
+    /// write, so writes overwrite allocated blocks instead of filling holes.
+    /// The time taken is reported by `disk_prealloc_duration()`.
+    pub fn pre_allocate(mut self) -> Self {
+        self.pre_allocate = true;
+        self
+    }
+
+    /// Exports a span for a `rate` (0.0-1.0) fraction of synchronous reads
+    /// and writes to the global OpenTelemetry tracer (`otel` feature).
+    pub fn otel_sample_rate(mut self, rate: f64) -> Self {
+        self.span_rate = rate;
+        self
+    }
+
+    /// Opens the target with O_DIRECT, bypassing the page cache. Offsets are
+    /// then 4 KiB-aligned.
+    pub fn direct_io(mut self) -> Self {
+        self.direct = true;
+        self
+    }
+
+    /// Seeds the offset/size RNG; workers with the same seed issue the same
+    /// sequence of writes (default: time-based).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// A copy of this worker targeting `path`. With a fixed `seed` the two
+    /// write identical data at identical offsets.
+    pub fn mirror(&self, path: PathBuf) -> Self {
+        Self {
+            path,
+            ..self.clone()
+        }
+    }
+
+    pub fn spawn(self) -> WorkerHandle {
+        WorkerHandle::spawn(move |stop, counters| disk_worker(stop, counters, self))
+    }
+}
+
+/// Open file plus the per-run state shared by the random and trace loops.
+struct DiskSession<'a> {
+    cfg: &'a DiskWorker,
+    counters: &'a Counters,
+    stop: &'a AtomicBool,
+    file: File,
+    max_bytes: u64,
+    rng: Rng,
+    writes: u64,
+    last_offset: u64,
+    compressor: Compressor,
+    spans: SpanSampler,
+    /// Zipf sampler over the file's 4 KiB slots for `Hotspot`.
+    zipf: Option<Zipf>,
+    /// Next offset for `Sequential`.
+    next_offset: u64,
+    /// Reads and writes since the last stall.
+    since_stall: u64,
+    /// `DiskWorker::iops_limit`.
+    bucket: Option<TokenBucket>,
+}
+
+impl DiskSession<'_> {
+    fn seek(&mut self, offset: u64) -> std::io::Result<()> {
+        if let Some(model) = self.cfg.latency_model {
+            let us = model.sample_us(&mut self.rng, self.last_offset, offset, self.max_bytes);
+            thread::sleep(Duration::from_micros(us));
+        }
+        self.last_offset = offset;
+        self.file.seek(SeekFrom::Start(offset)).map(|_| ())
+    }
+
+    /// Waits for a token under `DiskWorker::iops_limit`.
+    fn throttle(&mut self) {
+        if let Some(bucket) = self.bucket.as_mut() {
+            bucket.consume(1, self.stop);
+        }
+    }
+
+    fn write_at(&mut self, offset: u64, data: &[u8]) -> bool {
+        self.throttle();
+        let span_start = self.spans.start();
+        let t0 = Instant::now();
+        if let Err(e) = self.seek(offset) {
+            self.counters
+                .error(format!("Disk worker: write at {} failed: {}", offset, e));
+            return false;
+        }
+        let written = self
+            .compressor
+            .compress(data)
+            .and_then(|out| self.file.write_all(out).map(|_| out.len()));
+        let physical = match written {
+            Ok(n) => n as u64,
+            Err(e) => {
+                self.counters
+                    .error(format!("Disk worker: write at {} failed: {}", offset, e));
+                return false;
+            }
+        };
+        self.counters.phys_bytes.fetch_add(physical, Ordering::Relaxed);
+        self.counters.bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+        self.counters.ops.fetch_add(1, Ordering::Relaxed);
+        self.check_sla(t0);
+        if let Some(t0) = span_start {
+            self.spans.disk_span("write", offset, data.len(), t0);
+        }
+        self.maybe_stall();
+        wait_if_paused(self.stop, self.counters);
+        self.writes += 1;
+        let interval = self.cfg.fsync_interval;
+        if interval > 0 && self.writes.is_multiple_of(interval) {
+            self.fsync();
+        } else if self.rng.next_u32().is_multiple_of(200) {
+            let _ = self.file.flush();
+        }
+        true
+    }
+
+    fn read_at(&mut self, offset: u64, data: &mut [u8]) -> bool {
+        self.throttle();
+        let span_start = self.spans.start();
+        if let Err(e) = self.seek(offset).and_then(|_| self.file.read_exact(data)) {
+            self.counters
+                .error(format!("Disk worker: read at {} failed: {}", offset, e));
+            return false;
+        }
+        self.counters
+            .read_bytes
+            .fetch_add(data.len() as u64, Ordering::Relaxed);
+        self.counters.ops.fetch_add(1, Ordering::Relaxed);
+        if let Some(t0) = span_start {
+            self.spans.disk_span("read", offset, data.len(), t0);
+        }
+        self.maybe_stall();
+        wait_if_paused(self.stop, self.counters);
+        true
+    }
+
+    /// Counts a latency SLA violation if the write started at `t0` overran
+    /// `DiskWorker::latency_sla`, and records its latency if enabled.
+    fn check_sla(&self, t0: Instant) {
+        if let Some(sla) = self.cfg.latency_sla {
+            self.counters.sla_checked.fetch_add(1, Ordering::Relaxed);
+            if t0.elapsed() > sla {
+                self.counters.sla_violations.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        if self.cfg.latency_sigfig.is_some() {
+            self.counters.record_latency(t0);
+        }
+    }
+
+    /// Sleeps out a configured stall once every `every_ops` I/Os, in short
+    /// slices so a stop request ends it early.
+    fn maybe_stall(&mut self) {
+        let Some((every, duration)) = self.cfg.stall else {
+            return;
+        };
+        self.since_stall += 1;
+        if self.since_stall < every {
+            return;
+        }
+        self.since_stall = 0;
+        self.counters.stalls.fetch_add(1, Ordering::Relaxed);
+        let end = Instant::now() + duration;
+        while !self.stop.load(Ordering::Relaxed) {
+            let left = end.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            thread::sleep(left.min(Duration::from_millis(50)));
+        }
+    }
+
+    fn fsync(&mut self) {
+        let t0 = Instant::now();
+        unsafe {
+            fsync(self.file.as_raw_fd());
+        }
+        self.counters
+            .fsync_lat_us
+            .fetch_add(t0.elapsed().as_micros() as u64, Ordering::Relaxed);
+        self.counters.fsyncs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Fills a fresh write buffer per `DiskWorker::write_pattern`.
+    fn fill(&mut self, buf: &mut [u8]) {
+        match self.cfg.write_pattern.as_ref() {
+            None => fill_pattern(buf),
+            Some(DiskWritePattern::Zero) => buf.fill(0),
+            Some(DiskWritePattern::Ones) => buf.fill(0xFF),
+            Some(DiskWritePattern::Random) => {
+                for chunk in buf.chunks_mut(8) {
+                    let bytes = self.rng.next_u64().to_le_bytes();
+                    chunk.copy_from_slice(&bytes[..chunk.len()]);
+                }
+            }
+            Some(DiskWritePattern::Pattern(seq)) => {
+                for (b, p) in buf.iter_mut().zip(seq.iter().cycle()) {
+                    *b = *p;
+                }
+            }
+        }
+    }
+}
+
+fn fill_pattern(buf: &mut [u8]) {
+    for i in (0..buf.len()).step_by(4096) {
+        buf[i] = (i / 4096) as u8;
+    }
+}
+
+fn disk_worker(stop: Arc<AtomicBool>, counters: Arc<Counters>, cfg: DiskWorker) {
+    counters.enable_latency(cfg.latency_sigfig);
+    if let Some(prio) = cfg.io_priority {
+        let (class, level) = prio.class_level();
+        if !set_thread_io_priority(class, level) {
+            log::warn!("Disk worker: cannot set I/O priority {}", prio.name());
+        }
+    }
+    if let Some(nice) = cfg.nice {
+        match set_thread_nice(nice) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => log::warn!(
+                "Disk worker: nice {} needs root or CAP_SYS_NICE; running at the default level",
+                nice
+            ),
+            Err(e) => log::warn!("Disk worker: cannot set nice {}: {}", nice, e),
+        }
+    }
+    // Read mode reads an existing file as is, whatever its size.
+    let existing_len = std::fs::metadata(&cfg.path)
+        .map(|m| m.len())
+        .ok()
+        .filter(|&len| cfg.io_type == DiskIoType::Read && len > 0);
+    let file = match OpenOptions::new()
+        .create(existing_len.is_none())
+        .truncate(false)
+        .read(true)
+        .write(existing_len.is_none())
+        .custom_flags(if cfg.direct { O_DIRECT } else { 0 })
+        .open(&cfg.path)
+    {
+        Ok(f) => f,
+        Err(e) => {
+            counters.error(format!("Disk worker: cannot open {}: {}", cfg.path.display(), e));
+            return;
+        }
+    };
+    let max_bytes = match existing_len {
+        Some(len) if len < BLOCK_MIN as u64 => {
+            counters.error(format!(
+                "Disk worker: {} is smaller than {} bytes",
+                cfg.path.display(),
+                BLOCK_MIN
+            ));
+            return;
+        }
+        Some(len) => len,
+        None => {
+            let max_bytes = cfg.max_bytes.max(4 * 1024 * 1024);
+            let _ = file.set_len(max_bytes);
+            max_bytes
+        }
+    };
+    if cfg.pre_allocate && existing_len.is_none() {
+        let t0 = Instant::now();
+        if unsafe { fallocate(file.as_raw_fd(), 0, 0, max_bytes as i64) } != 0 {
+            log::warn!(
+                "Disk worker: fallocate {} failed: {}",
+                cfg.path.display(),
+                std::io::Error::last_os_error()
+            );
+        }
+        counters
+            .prealloc_us
+            .store((t0.elapsed().as_micros() as u64).max(1), Ordering::Relaxed);
+    }
+    let seed = cfg.seed.unwrap_or_else(seed_from_time);
+    log::debug!(
+        "Disk worker: start on {} ({} bytes, seed {:#x})",
+        cfg.path.display(),
+        max_bytes,
+        seed
+    );
+
+    let mut session = DiskSession {
+        cfg: &cfg,
+        counters: &counters,
+        stop: &stop,
+        file,
+        max_bytes,
+        rng: Rng::new(seed),
+        writes: 0,
+        last_offset: 0,
+        compressor: Compressor::new(cfg.compress),
+        spans: SpanSampler::new(cfg.span_rate),
+        zipf: (cfg.access_pattern == DiskAccessPattern::Hotspot)
+            .then(|| Zipf::new(max_bytes / BLOCK_MIN as u64, cfg.zipf_s)),
+        next_offset: 0,
+        since_stall: 0,
+        bucket: cfg
+            .iops_limit
+            .map(|iops| TokenBucket::new(iops).with_burst(2.0 * iops)),
+    };
+
+    if let Some(trace) = cfg.trace.as_ref() {
+        replay_trace(&stop, &mut session, trace);
+        return;
+    }
+    if cfg.io_type != DiskIoType::Write {
+        if existing_len.is_none() && cfg.io_type == DiskIoType::Read {
+            prefill(&stop, &mut session);
+        }
+        random_io(&stop, &mut session);
+    } else if cfg.aio_depth > 0 {
+        aio_writes(&stop, &mut session, cfg.aio_depth);
+    } else if cfg.io_pattern == IoPattern::ZipSequentially {
+        fill_then_overwrite(&stop, &mut session);
+    } else if cfg.rmw {
+        read_modify_write(&stop, &mut session);
+    } else {
+        random_writes(&stop, &mut session);
+    }
+    log::debug!("Disk worker: stop after {} writes", session.writes);
+}
+
+const BLOCK_MIN: usize = 4 * 1024;
+const BLOCK_MAX: usize = 4 * 1024 * 1024;
+
+/// Picks a random 4 KiB-aligned block size and an offset where it fits.
+fn random_block(session: &mut DiskSession) -> (u64, usize) {
+    random_block_upto(session, BLOCK_MAX)
+}
+
+/// POSIX AIO writes. `struct aiocb` is declared with glibc's 64-bit layout,
+/// so this mode only exists on glibc Linux on x86_64 and aarch64.
+#[cfg(all(
+    target_os = "linux",
+    target_env = "gnu",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod aio {
+    use super::*;
+
+    extern "C" {
+        fn aio_write(cb: *mut AioCb) -> i32;
+        fn aio_error(cb: *const AioCb) -> i32;
+        fn aio_return(cb: *mut AioCb) -> isize;
+        fn aio_suspend(list: *const *const AioCb, n: i32, timeout: *const Timespec) -> i32;
+    }
+
+    /// Same value on every architecture `aio_writes` is built for.
+    const EINPROGRESS: i32 = 115;
+    const SIGEV_NONE: i32 = 1;
+    /// Largest block per AIO request; each slot owns one buffer this size.
+    const AIO_BLOCK_MAX: usize = 1024 * 1024;
+
+    #[repr(C)]
+    struct Timespec {
+        tv_sec: i64,
+        tv_nsec: i64,
+    }
+
+    #[repr(C)]
+    struct SigEvent {
+        value: usize,
+        signo: i32,
+        notify: i32,
+        _pad: [i32; 12],
+    }
+
+    /// glibc `struct aiocb` on 64-bit Linux.
+    #[repr(C)]
+    struct AioCb {
+        fildes: i32,
+        lio_opcode: i32,
+        reqprio: i32,
+        buf: *mut u8,
+        nbytes: usize,
+        sigevent: SigEvent,
+        _next_prio: *mut AioCb,
+        _abs_prio: i32,
+        _policy: i32,
+        _error_code: i32,
+        _return_value: isize,
+        offset: i64,
+        _reserved: [u8; 32],
+    }
+
+    /// One in-flight AIO request and the buffer it writes from. Boxed so the
+    /// kernel-visible `aiocb` never moves while the request is pending.
+    struct AioSlot {
+        cb: Box<AioCb>,
+        storage: Vec<u8>,
+        in_flight: bool,
+        submitted: Instant,
+    }
+
+    impl AioSlot {
+        fn new(fd: i32) -> Self {
+            let storage = vec![0u8; AIO_BLOCK_MAX + DIRECT_ALIGN];
+            let cb = Box::new(AioCb {
+                fildes: fd,
+                lio_opcode: 0,
+                reqprio: 0,
+                buf: std::ptr::null_mut(),
+                nbytes: 0,
+                sigevent: SigEvent {
+                    value: 0,
+                    signo: 0,
+                    notify: SIGEV_NONE,
+                    _pad: [0; 12],
+                },
+                _next_prio: std::ptr::null_mut(),
+                _abs_prio: 0,
+                _policy: 0,
+                _error_code: 0,
+                _return_value: 0,
+                offset: 0,
+                _reserved: [0; 32],
+            });
+            Self {
+                cb,
+                storage,
+                in_flight: false,
+                submitted: Instant::now(),
+            }
+        }
+
+        fn submit(&mut self, offset: u64, size: usize) -> bool {
+            let start = self.storage.as_ptr().align_offset(DIRECT_ALIGN);
+            self.cb.buf = self.storage[start..].as_mut_ptr();
+            self.cb.nbytes = size;
+            self.cb.offset = offset as i64;
+            self.submitted = Instant::now();
+            self.in_flight = unsafe { aio_write(&mut *self.cb) } == 0;
+            self.in_flight
+        }
+
+        /// Bytes written once the request has completed, or the error it
+        /// failed with; `None` while it is still in flight.
+        fn poll(&mut self) -> Option<std::io::Result<u64>> {
+            let err = unsafe { aio_error(&*self.cb) };
+            if err == EINPROGRESS {
+                return None;
+            }
+            self.in_flight = false;
+            let ret = unsafe { aio_return(&mut *self.cb) };
+            Some(match (err, ret) {
+                (0, n) if n > 0 => Ok(n as u64),
+                (0, _) => Err(std::io::Error::new(std::io::ErrorKind::WriteZero, "wrote 0 bytes")),
+                (e, _) => Err(std::io::Error::from_raw_os_error(e)),
+            })
+        }
+    }
+
+    /// Keeps `depth` random writes in flight, resubmitting each slot as soon as
+    /// `aio_error` reports it done. One successful completion is one op; failed
+    /// ones are reported as errors and not counted.
+    pub(super) fn aio_writes(stop: &AtomicBool, session: &mut DiskSession, depth: usize) {
+        let fd = session.file.as_raw_fd();
+        let mut slots: Vec<AioSlot> = (0..depth)
+            .map(|_| {
+                let mut slot = AioSlot::new(fd);
+                session.fill(&mut slot.storage);
+                slot
+            })
+            .collect();
+        let timeout = Timespec {
+            tv_sec: 0,
+            tv_nsec: 100_000_000,
+        };
+
+        let mut failed = false;
+        while !stop.load(Ordering::Relaxed) && !failed {
+            wait_if_paused(stop, session.counters);
+            for slot in slots.iter_mut().filter(|s| !s.in_flight) {
+                session.throttle();
+                let (offset, size) = random_block_upto(session, AIO_BLOCK_MAX);
+                if !slot.submit(offset, size) {
+                    session.counters.error("Disk worker: aio_write failed".to_string());
+                    failed = true;
+                    break;
+                }
+            }
+            let pending: Vec<*const AioCb> = slots
+                .iter()
+                .filter(|s| s.in_flight)
+                .map(|s| &*s.cb as *const AioCb)
+                .collect();
+            if !pending.is_empty() {
+                unsafe {
+                    aio_suspend(pending.as_ptr(), pending.len() as i32, &timeout);
+                }
+            }
+            for slot in slots.iter_mut().filter(|s| s.in_flight) {
+                match slot.poll() {
+                    None => {}
+                    Some(Ok(bytes)) => {
+                        session.counters.bytes.fetch_add(bytes, Ordering::Relaxed);
+                        session.counters.phys_bytes.fetch_add(bytes, Ordering::Relaxed);
+                        session.counters.ops.fetch_add(1, Ordering::Relaxed);
+                        session.check_sla(slot.submitted);
+                    }
+                    Some(Err(e)) => {
+                        session.counters.error(format!("Disk worker: aio write failed: {}", e));
+                    }
+                }
+            }
+        }
+
+        // Buffers must outlive their requests.
+        while slots.iter().any(|s| s.in_flight) {
+            for slot in slots.iter_mut().filter(|s| s.in_flight) {
+                if slot.poll().is_none() {
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(
+    target_os = "linux",
+    target_env = "gnu",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+use aio::aio_writes;
+
+#[cfg(not(all(
+    target_os = "linux",
+    target_env = "gnu",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+fn aio_writes(_stop: &AtomicBool, session: &mut DiskSession, _depth: usize) {
+    session
+        .counters
+        .error("Disk worker: POSIX AIO is not supported on this platform".to_string());
+}
+
+fn random_block_upto(session: &mut DiskSession, block_max: usize) -> (u64, usize) {
+    let mut size = BLOCK_MIN + (session.rng.next_u32() as usize % (block_max - BLOCK_MIN + 1));
+    size &= !0xFFF;
+    if size == 0 {
+        size = BLOCK_MIN;
+    }
+    let max_off = session.max_bytes.saturating_sub(size as u64);
+    let mut offset = if max_off == 0 {
+        0
+    } else {
+        match session.cfg.access_pattern {
+            DiskAccessPattern::Uniform => session.rng.next_u64() % max_off,
+            DiskAccessPattern::Hotspot => {
+                let slots = (session.max_bytes / BLOCK_MIN as u64).max(1) as u128;
+                let rank = session.zipf.as_ref().map_or(1, |z| z.sample(&mut session.rng));
+                let slot = (rank as u128 - 1) * HOTSPOT_STRIDE % slots;
+                (slot as u64 * BLOCK_MIN as u64).min(max_off)
+            }
+            DiskAccessPattern::Sequential => {
+                let offset = if session.next_offset > max_off {
+                    0
+                } else {
+                    session.next_offset
+                };
+                session.next_offset = offset + size as u64;
+                offset
+            }
+        }
+    };
+    if session.cfg.direct {
+        offset &= !(DIRECT_ALIGN as u64 - 1);
+    }
+    log::trace!("Disk worker: {} bytes at {}", size, offset);
+    (offset, size)
+}
+
+/// The `BLOCK_MAX` bytes of `buf` starting `DIRECT_ALIGN`-aligned in memory.
+/// `buf` must be `BLOCK_MAX + DIRECT_ALIGN` long.
+fn aligned_block(buf: &mut [u8]) -> &mut [u8] {
+    let start = buf.as_ptr().align_offset(DIRECT_ALIGN);
+    &mut buf[start..start + BLOCK_MAX]
+}
+
+fn random_writes(stop: &AtomicBool, session: &mut DiskSession) {
+    let mut storage = vec![0u8; BLOCK_MAX + DIRECT_ALIGN];
+    let buf = aligned_block(&mut storage);
+    session.fill(buf);
+
+    while !stop.load(Ordering::Relaxed) {
+        let (offset, size) = random_block(session);
+        if !session.write_at(offset, &buf[..size]) {
+            break;
+        }
+    }
+}
+
+/// Writes the whole file front to back so read mode does not read holes.
+/// Not counted as worker I/O.
+fn prefill(stop: &AtomicBool, session: &mut DiskSession) {
+    let mut storage = vec![0u8; BLOCK_MAX + DIRECT_ALIGN];
+    let buf = aligned_block(&mut storage);
+    session.fill(buf);
+    let mut offset = 0u64;
+    while offset < session.max_bytes && !stop.load(Ordering::Relaxed) {
+        let size = (session.max_bytes - offset).min(BLOCK_MAX as u64) as usize;
+        if let Err(e) = session.file.write_all(&buf[..size]) {
+            session
+                .counters
+                .error(format!("Disk worker: prefill at {} failed: {}", offset, e));
+            return;
+        }
+        offset += size as u64;
+    }
+}
+
+/// Random blocks, each a read with probability `read_pct` (100 for `Read`).
+fn random_io(stop: &AtomicBool, session: &mut DiskSession) {
+    let mut storage = vec![0u8; BLOCK_MAX + DIRECT_ALIGN];
+    let buf = aligned_block(&mut storage);
+    session.fill(buf);
+    let read_pct = match session.cfg.io_type {
+        DiskIoType::Read => 100,
+        DiskIoType::Write => 0,
+        DiskIoType::Mixed => session.cfg.read_pct,
+    };
+    let block_max = BLOCK_MAX.min(session.max_bytes as usize);
+
+    while !stop.load(Ordering::Relaxed) {
+        let (offset, size) = random_block_upto(session, block_max);
+        let ok = if session.rng.next_u32() % 100 < read_pct {
+            session.read_at(offset, &mut buf[..size])
+        } else {
+            session.write_at(offset, &buf[..size])
+        };
+        if !ok {
+            break;
+        }
+    }
+}
+
+/// `DiskPhase` state machine: sequential `BLOCK_MAX` writes up to
+/// `max_bytes`, then the usual random loop. Sets `counters.filled` on the
+/// transition.
+fn fill_then_overwrite(stop: &AtomicBool, session: &mut DiskSession) {
+    let mut storage = vec![0u8; BLOCK_MAX + DIRECT_ALIGN];
+    let buf = aligned_block(&mut storage);
+    session.fill(buf);
+
+    let mut phase = DiskPhase::Fill { next_offset: 0 };
+    while !stop.load(Ordering::Relaxed) {
+        match phase {
+            DiskPhase::Fill { next_offset } if next_offset < session.max_bytes => {
+                let size = (session.max_bytes - next_offset).min(BLOCK_MAX as u64) as usize;
+                if !session.write_at(next_offset, &buf[..size]) {
+                    return;
+                }
+                phase = DiskPhase::Fill {
+                    next_offset: next_offset + size as u64,
+                };
+            }
+            DiskPhase::Fill { .. } => {
+                session.counters.filled.store(true, Ordering::Relaxed);
+                phase = DiskPhase::Overwrite;
+            }
+            DiskPhase::Overwrite => {
+                if session.cfg.rmw {
+                    read_modify_write(stop, session);
+                } else {
+                    random_writes(stop, session);
+                }
+                return;
+            }
+        }
+    }
+}
+
+fn read_modify_write(stop: &AtomicBool, session: &mut DiskSession) {
+    let mut storage = vec![0u8; BLOCK_MAX + DIRECT_ALIGN];
+    let buf = aligned_block(&mut storage);
+
+    while !stop.load(Ordering::Relaxed) {
+        let (offset, size) = random_block(session);
+        let block = &mut buf[..size];
+        if !session.read_at(offset, block) {
+            break;
+        }
+        // A zero key would leave the byte unchanged; `| 1` guarantees every
+        // write differs from what was read.
+        for b in block.iter_mut() {
+            *b ^= session.rng.next_u32() as u8 | 1;
+        }
+        if !session.write_at(offset, block) {
+            break;
+        }
+    }
+}
+
+fn replay_trace(stop: &AtomicBool, session: &mut DiskSession, trace: &DiskTrace) {
+    let max_size = trace
+        .ops
+        .iter()
+        .map(|op| match *op {
+            TraceOp::Read { size, .. } | TraceOp::Write { size, .. } => size,
+        })
+        .max()
+        .unwrap_or(0);
+    let mut buf = vec![0u8; max_size];
+    session.fill(&mut buf);
+
+    while !stop.load(Ordering::Relaxed) {
+        for op in &trace.ops {
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            let ok = match *op {
+                TraceOp::Write { offset, size } => session.write_at(offset, &buf[..size]),
+                TraceOp::Read { offset, size } => session.read_at(offset, &mut buf[..size]),
+            };
+            if !ok {
+                return;
+            }
+        }
+    }
+}
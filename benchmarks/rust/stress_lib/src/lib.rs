@@ -0,0 +1,322 @@
+//! CPU, memory, disk and network stress workers shared by `stress_all` and usable from
+//! other programs (integration tests, fuzzing harnesses).
+//!
+//! Each worker is configured with a small builder and started with `spawn()`,
+//! which returns a [`WorkerHandle`] for reading counters and stopping it.
+
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+mod affinity;
+mod compress;
+mod cpu;
+mod disk;
+mod matrix;
+mod mem;
+mod net;
+mod otel;
+mod rng;
+mod simd;
+mod token_bucket;
+
+pub use affinity::{bind_thread_mem_to_node, set_thread_affinity};
+pub use compress::DiskCompress;
+pub use cpu::{
+    cache_levels, CacheLevel, CacheSizes, CpuWorker, CpuWorkerStats, CpuWorkload,
+    CpuWorkloadControl, CpuYieldStrategy, CPU_MIX_DEFAULT, MEMCPY_DEFAULT_BYTES,
+};
+pub use disk::{
+    block_device_size, DiskAccessPattern, DiskIoType, DiskTrace, DiskWorker, DiskWritePattern,
+    IoPattern, IoPriority, LatencyModel, TraceOp,
+};
+pub use matrix::MatrixWorker;
+pub use mem::{
+    latency_sweep, stride_test, GcPattern, LatencyWorker, MemAllocStrategy, MemWorker,
+};
+pub use net::{tcp_accept_server, NetWorker};
+pub use rng::{seed_from_time, Rng};
+pub use simd::SimdWidth;
+
+#[derive(Default)]
+pub(crate) struct Counters {
+    pub(crate) ops: AtomicU64,
+    pub(crate) bytes: AtomicU64,
+    pub(crate) phys_bytes: AtomicU64,
+    pub(crate) read_bytes: AtomicU64,
+    pub(crate) fsyncs: AtomicU64,
+    pub(crate) fsync_lat_us: AtomicU64,
+    pub(crate) allocs: AtomicU64,
+    pub(crate) alloc_fails: AtomicU64,
+    pub(crate) huge_pages: AtomicU64,
+    pub(crate) filled: AtomicBool,
+    pub(crate) drops: AtomicU64,
+    pub(crate) conn_errors: AtomicU64,
+    pub(crate) fault_us: AtomicU64,
+    pub(crate) corruptions: AtomicU64,
+    pub(crate) stalls: AtomicU64,
+    pub(crate) sla_violations: AtomicU64,
+    pub(crate) sla_checked: AtomicU64,
+    /// Integer/float/memory ops of the synthetic-mix CPU workload.
+    pub(crate) mix_ops: [AtomicU64; 3],
+    pub(crate) paused: AtomicBool,
+    /// Per-op latency in ns, when the worker was built with
+    /// `latency_histogram` (`hdr` feature).
+    #[cfg(feature = "hdr")]
+    pub(crate) latency: Mutex<Option<hdrhistogram::Histogram<u64>>>,
+    pub(crate) prealloc_us: AtomicU64,
+    pub(crate) errors: AtomicU64,
+    pub(crate) last_error: Mutex<Option<String>>,
+}
+
+impl Counters {
+    /// Logs a worker error and records it for `error_count()` /
+    /// `last_error()`.
+    pub(crate) fn error(&self, msg: String) {
+        log::error!("{}", msg);
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut last) = self.last_error.lock() {
+            *last = Some(msg);
+        }
+    }
+}
+
+impl Counters {
+    /// Starts the latency histogram with `sigfig` significant digits. A
+    /// no-op without the `hdr` feature.
+    pub(crate) fn enable_latency(&self, sigfig: Option<u8>) {
+        #[cfg(feature = "hdr")]
+        if let Some(sigfig) = sigfig {
+            match hdrhistogram::Histogram::new(sigfig) {
+                Ok(h) => *self.latency.lock().unwrap() = Some(h),
+                Err(e) => log::warn!("Latency histogram: {:?}", e),
+            }
+        }
+        #[cfg(not(feature = "hdr"))]
+        let _ = sigfig;
+    }
+
+    /// Records the latency of an op that began at `t0`.
+    pub(crate) fn record_latency(&self, t0: Instant) {
+        #[cfg(feature = "hdr")]
+        if let Some(h) = self.latency.lock().unwrap().as_mut() {
+            let ns = t0.elapsed().as_nanos() as u64;
+            // Auto-resizing only fails past i64::MAX / 2 ns.
+            if h.record(ns).is_err() {
+                h.saturating_record(ns);
+            }
+        }
+        #[cfg(not(feature = "hdr"))]
+        let _ = t0;
+    }
+}
+
+/// Blocks while the worker is paused (see [`WorkerHandle::pause`]). Workers
+/// call this once per loop iteration.
+pub(crate) fn wait_if_paused(stop: &AtomicBool, counters: &Counters) {
+    while counters.paused.load(Ordering::Relaxed) && !stop.load(Ordering::Relaxed) {
+        thread::park_timeout(Duration::from_millis(100));
+    }
+}
+
+/// A running worker thread. `T` is what the thread returns on exit
+/// (CPU workers return [`CpuWorkerStats`]).
+pub struct WorkerHandle<T = ()> {
+    stop: Arc<AtomicBool>,
+    counters: Arc<Counters>,
+    thread: JoinHandle<T>,
+}
+
+impl<T: Send + 'static> WorkerHandle<T> {
+    pub(crate) fn spawn<F>(f: F) -> Self
+    where
+        F: FnOnce(Arc<AtomicBool>, Arc<Counters>) -> T + Send + 'static,
+    {
+        Self::spawn_with_stack(None, f)
+    }
+
+    /// Like `spawn`, with an explicit thread stack size in bytes.
+    pub(crate) fn spawn_with_stack<F>(stack_size: Option<usize>, f: F) -> Self
+    where
+        F: FnOnce(Arc<AtomicBool>, Arc<Counters>) -> T + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let counters = Arc::new(Counters::default());
+        let s = Arc::clone(&stop);
+        let c = Arc::clone(&counters);
+        let mut builder = thread::Builder::new();
+        if let Some(size) = stack_size {
+            builder = builder.stack_size(size);
+        }
+        let thread = builder
+            .spawn(move || f(s, c))
+            .expect("failed to spawn worker thread");
+        Self {
+            stop,
+            counters,
+            thread,
+        }
+    }
+
+    /// Asks the worker to stop; it exits at its next loop iteration.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.unpark();
+    }
+
+    /// Wakes a worker parked by [`CpuYieldStrategy::Park`] for its next
+    /// burst.
+    pub fn unpark(&self) {
+        self.thread.thread().unpark();
+    }
+
+    /// Parks the worker at its next loop iteration until `resume()`. Counters
+    /// stay as they are; `stop()` still ends a paused worker.
+    pub fn pause(&self) {
+        self.counters.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Lets a paused worker continue.
+    pub fn resume(&self) {
+        self.counters.paused.store(false, Ordering::Relaxed);
+        self.unpark();
+    }
+
+    /// Waits for the worker thread to exit and returns its result (`None`
+    /// if it panicked). Call `stop()` first.
+    pub fn join(self) -> Option<T> {
+        self.thread.join().ok()
+    }
+}
+
+impl<T> WorkerHandle<T> {
+    /// Operations completed so far (loop bursts, memory touches, disk writes
+    /// or network round trips).
+    pub fn ops_count(&self) -> u64 {
+        self.counters.ops.load(Ordering::Relaxed)
+    }
+
+    /// Bytes moved so far. Only the disk and network workers, memory workers
+    /// with a bandwidth target and memcpy CPU workers transfer bytes.
+    pub fn bytes_transferred(&self) -> u64 {
+        self.counters.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Bytes the disk worker actually wrote; below `bytes_transferred()`
+    /// when writes are compressed.
+    pub fn bytes_physical(&self) -> u64 {
+        self.counters.phys_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Bytes read so far (disk trace replay reads).
+    pub fn bytes_read(&self) -> u64 {
+        self.counters.read_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Number of fsync calls issued by the disk worker.
+    pub fn fsync_count(&self) -> u64 {
+        self.counters.fsyncs.load(Ordering::Relaxed)
+    }
+
+    /// Cumulative time spent in fsync, in microseconds.
+    pub fn fsync_latency_us(&self) -> u64 {
+        self.counters.fsync_lat_us.load(Ordering::Relaxed)
+    }
+
+    /// Allocation attempts made by the memory worker's GC pattern.
+    pub fn alloc_attempts(&self) -> u64 {
+        self.counters.allocs.load(Ordering::Relaxed)
+    }
+
+    /// 2 MiB huge pages held by the memory worker.
+    pub fn huge_pages_used(&self) -> u64 {
+        self.counters.huge_pages.load(Ordering::Relaxed)
+    }
+
+    /// I/O errors hit by the worker (disk open/read/write, network socket).
+    pub fn error_count(&self) -> u64 {
+        self.counters.errors.load(Ordering::Relaxed)
+    }
+
+    /// Message of the most recent error, if any.
+    pub fn last_error(&self) -> Option<String> {
+        self.counters.last_error.lock().ok()?.clone()
+    }
+
+    /// Whether a `ZipSequentially` disk worker has finished its fill pass.
+    pub fn disk_filled(&self) -> bool {
+        self.counters.filled.load(Ordering::Relaxed)
+    }
+
+    /// Time the memory worker spent faulting its target in; `None` until it
+    /// has. See [`MemWorker::zero_pages`].
+    pub fn mem_fault_duration(&self) -> Option<Duration> {
+        match self.counters.fault_us.load(Ordering::Relaxed) {
+            0 => None,
+            us => Some(Duration::from_micros(us)),
+        }
+    }
+
+    /// Bit flips injected so far. See [`MemWorker::error_injection`].
+    pub fn mem_corruption_count(&self) -> u64 {
+        self.counters.corruptions.load(Ordering::Relaxed)
+    }
+
+    /// Integer, floating-point and memory ops of the synthetic-mix CPU
+    /// workload so far; they add up to its share of `ops_count()`.
+    pub fn cpu_mix_ops(&self) -> [u64; 3] {
+        self.counters.mix_ops.each_ref().map(|n| n.load(Ordering::Relaxed))
+    }
+
+    /// Injected disk stalls so far. See [`DiskWorker::stall`].
+    pub fn disk_stall_count(&self) -> u64 {
+        self.counters.stalls.load(Ordering::Relaxed)
+    }
+
+    /// Copy of the per-op latency histogram (ns) so far; `None` unless the
+    /// worker was built with `latency_histogram`.
+    #[cfg(feature = "hdr")]
+    pub fn latency_histogram(&self) -> Option<hdrhistogram::Histogram<u64>> {
+        self.counters.latency.lock().unwrap().clone()
+    }
+
+    /// Writes that overran the latency SLA. See [`DiskWorker::latency_sla`].
+    pub fn disk_sla_violation_count(&self) -> u64 {
+        self.counters.sla_violations.load(Ordering::Relaxed)
+    }
+
+    /// Writes timed against the latency SLA: the denominator for
+    /// `disk_sla_violation_count()`. Reads are not timed.
+    pub fn disk_sla_checked_count(&self) -> u64 {
+        self.counters.sla_checked.load(Ordering::Relaxed)
+    }
+
+    /// Time the disk worker's `fallocate` took; `None` until it has run.
+    /// See [`DiskWorker::pre_allocate`].
+    pub fn disk_prealloc_duration(&self) -> Option<Duration> {
+        match self.counters.prealloc_us.load(Ordering::Relaxed) {
+            0 => None,
+            us => Some(Duration::from_micros(us)),
+        }
+    }
+
+    /// Datagrams the network worker could not send (`EAGAIN`), or TCP
+    /// connects that found no free ephemeral port.
+    pub fn drop_count(&self) -> u64 {
+        self.counters.drops.load(Ordering::Relaxed)
+    }
+
+    /// TCP connects that failed for a reason other than port exhaustion
+    /// and were retried. See [`NetWorker::tcp_connect`].
+    pub fn connect_error_count(&self) -> u64 {
+        self.counters.conn_errors.load(Ordering::Relaxed)
+    }
+
+    /// Allocation attempts that failed (`try_reserve` returned an error).
+    pub fn alloc_failures(&self) -> u64 {
+        self.counters.alloc_fails.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use crate::{wait_if_paused, Counters, WorkerHandle};
+
+/// Multiplies two `n`x`n` f64 matrices in a loop with the matrix_multiply
+/// kernel (i-k-j order); each finished product counts as one op.
+#[derive(Clone)]
+pub struct MatrixWorker {
+    n: usize,
+}
+
+impl MatrixWorker {
+    pub fn new(n: usize) -> Self {
+        Self { n: n.max(1) }
+    }
+
+    /// Floating-point operations in one product (`2 n^3`).
+    pub fn flops_per_op(&self) -> f64 {
+        2.0 * (self.n as f64).powi(3)
+    }
+
+    pub fn spawn(self) -> WorkerHandle {
+        WorkerHandle::spawn(move |stop, counters| matrix_worker(stop, counters, self.n))
+    }
+}
+
+fn matrix_worker(stop: Arc<AtomicBool>, counters: Arc<Counters>, n: usize) {
+    log::debug!("Matrix worker: start ({}x{})", n, n);
+    let mut a = vec![0.0f64; n * n];
+    let mut b = vec![0.0f64; n * n];
+    for i in 0..n * n {
+        a[i] = (i as f64 * 0.001).sin();
+        b[i] = (i as f64 * 0.002).cos();
+    }
+    let mut c = vec![0.0f64; n * n];
+    'products: while !stop.load(Ordering::Relaxed) {
+        c.fill(0.0);
+        for i in 0..n {
+            // Large products take seconds; check between rows.
+            if stop.load(Ordering::Relaxed) {
+                break 'products;
+            }
+            wait_if_paused(&stop, &counters);
+            let row = &mut c[i * n..(i + 1) * n];
+            for k in 0..n {
+                let a_ik = a[i * n + k];
+                for (c_ij, b_kj) in row.iter_mut().zip(&b[k * n..(k + 1) * n]) {
+                    *c_ij += a_ik * b_kj;
+                }
+            }
+        }
+        std::hint::black_box(&mut c);
+        counters.ops.fetch_add(1, Ordering::Relaxed);
+    }
+    log::debug!("Matrix worker: stop");
+}
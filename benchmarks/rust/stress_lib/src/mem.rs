@@ -0,0 +1,668 @@
+use std::collections::VecDeque;
+use std::ffi::c_void;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::affinity::{bind_thread_mem_to_node, set_thread_affinity};
+use crate::rng::{seed_from_time, Rng};
+use crate::token_bucket::TokenBucket;
+use crate::{wait_if_paused, Counters, WorkerHandle};
+
+/// Generational allocation pattern: bursts of short-lived young objects,
+/// periodically promoting a large long-lived old object.
+#[derive(Clone, Copy, Debug)]
+pub struct GcPattern {
+    pub young_size_kb: u64,
+    pub young_count: usize,
+    pub old_size_mb: u64,
+}
+
+impl Default for GcPattern {
+    fn default() -> Self {
+        Self {
+            young_size_kb: 4,
+            young_count: 1024,
+            old_size_mb: 64,
+        }
+    }
+}
+
+/// Young bursts between two old-generation promotions.
+const GC_BURSTS_PER_PROMOTION: u64 = 16;
+
+/// Accesses per latency sample; timing every one would dominate the loop.
+const LATENCY_SAMPLE_OPS: u64 = 64;
+/// Bytes each op touches (one write per cache line) under a bandwidth target.
+const BANDWIDTH_SPAN: usize = 64 * 1024;
+/// Ops between token bucket checks, so sleeps are not sub-millisecond.
+const BANDWIDTH_OPS_PER_CHECK: u64 = 16;
+/// Ops between checks for due bit flips under an error injection rate.
+const INJECTION_OPS_PER_CHECK: u64 = 1024;
+
+const HUGE_PAGE_BYTES: u64 = 2 * 1024 * 1024;
+const PROT_READ_WRITE: i32 = 0x1 | 0x2;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+const MAP_HUGETLB: i32 = 0x40000;
+const MAP_HUGE_2MB: i32 = 21 << 26;
+const MADV_HUGEPAGE: i32 = 14;
+
+extern "C" {
+    fn mmap(addr: *mut c_void, len: usize, prot: i32, flags: i32, fd: i32, off: i64)
+        -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> i32;
+    fn madvise(addr: *mut c_void, len: usize, advice: i32) -> i32;
+    fn calloc(n: usize, size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+}
+
+/// How the memory worker allocates its regular-page chunks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MemAllocStrategy {
+    /// `vec![0u8; n]` through the Rust global allocator (the original
+    /// behaviour).
+    #[default]
+    Malloc,
+    /// `calloc(3)` directly, so the C library can hand back lazily zeroed
+    /// pages without clearing them.
+    Calloc,
+    /// Private anonymous `mmap` per chunk, advised `MADV_HUGEPAGE` so
+    /// transparent huge pages can back it.
+    Mmap,
+}
+
+impl MemAllocStrategy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "malloc" => Some(MemAllocStrategy::Malloc),
+            "calloc" => Some(MemAllocStrategy::Calloc),
+            "mmap" => Some(MemAllocStrategy::Mmap),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MemAllocStrategy::Malloc => "malloc",
+            MemAllocStrategy::Calloc => "calloc",
+            MemAllocStrategy::Mmap => "mmap",
+        }
+    }
+}
+
+/// Memory outside the Rust allocator: an anonymous mapping or a `calloc`
+/// block.
+struct RawBuf {
+    ptr: *mut u8,
+    len: usize,
+    mapped: bool,
+}
+
+impl RawBuf {
+    fn map(len: usize, extra_flags: i32) -> Option<Self> {
+        let flags = MAP_PRIVATE | MAP_ANONYMOUS | extra_flags;
+        let ptr = unsafe { mmap(std::ptr::null_mut(), len, PROT_READ_WRITE, flags, -1, 0) };
+        if ptr as isize == -1 || ptr.is_null() {
+            return None;
+        }
+        Some(Self {
+            ptr: ptr as *mut u8,
+            len,
+            mapped: true,
+        })
+    }
+
+    fn calloc(len: usize) -> Option<Self> {
+        let ptr = unsafe { calloc(1, len) };
+        if ptr.is_null() {
+            return None;
+        }
+        Some(Self {
+            ptr: ptr as *mut u8,
+            len,
+            mapped: false,
+        })
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for RawBuf {
+    fn drop(&mut self) {
+        unsafe {
+            if self.mapped {
+                munmap(self.ptr as *mut c_void, self.len);
+            } else {
+                free(self.ptr as *mut c_void);
+            }
+        }
+    }
+}
+
+// The memory is owned exclusively by the worker thread that holds it.
+unsafe impl Send for RawBuf {}
+
+/// Free 2 MiB pages left in the hugetlbfs pool.
+fn read_hugepages_free() -> u64 {
+    std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|s| {
+            s.lines()
+                .find(|l| l.starts_with("HugePages_Free:"))
+                .and_then(|l| l.split_whitespace().nth(1)?.parse().ok())
+        })
+        .unwrap_or(0)
+}
+
+/// Allocates `target_mb`, touches every page, then does random byte updates.
+#[derive(Clone)]
+pub struct MemWorker {
+    target_mb: u64,
+    numa_node: Option<(usize, Vec<usize>)>,
+    bind_cpu: Option<usize>,
+    gc_pattern: Option<GcPattern>,
+    huge_pages: bool,
+    zero_pages: bool,
+    bandwidth_target: Option<f64>,
+    error_injection: Option<f64>,
+    alloc_strategy: MemAllocStrategy,
+    prefetch_distance: usize,
+    busy_wait: bool,
+    latency_sigfig: Option<u8>,
+}
+
+impl MemWorker {
+    pub fn new(target_mb: u64) -> Self {
+        Self {
+            target_mb,
+            numa_node: None,
+            bind_cpu: None,
+            gc_pattern: None,
+            huge_pages: false,
+            zero_pages: false,
+            bandwidth_target: None,
+            error_injection: None,
+            alloc_strategy: MemAllocStrategy::Malloc,
+            prefetch_distance: 0,
+            busy_wait: false,
+            latency_sigfig: None,
+        }
+    }
+
+    /// Replaces the touch loop with a GC-heap style allocate/free pattern.
+    /// The old generation is capped at `target_mb`.
+    pub fn gc_pattern(mut self, pattern: GcPattern) -> Self {
+        self.gc_pattern = Some(pattern);
+        self
+    }
+
+    /// Runs the worker on `cpus` and binds its allocations to NUMA `node`.
+    pub fn numa_node(mut self, node: usize, cpus: Vec<usize>) -> Self {
+        self.numa_node = Some((node, cpus));
+        self
+    }
+
+    /// Pins the worker thread to `cpu`. Overrides the CPUs of `numa_node`
+    /// but keeps its memory binding, so the accesses can come from a
+    /// remote node.
+    pub fn bind_cpu(mut self, cpu: usize) -> Self {
+        self.bind_cpu = Some(cpu);
+        self
+    }
+
+    /// Backs as much of the target as the free huge page pool allows with
+    /// 2 MiB pages, falling back to regular pages. Ignored by the GC pattern.
+    pub fn huge_pages(mut self) -> Self {
+        self.huge_pages = true;
+        self
+    }
+
+    /// Allocates the whole target zeroed without touching it, then times one
+    /// write pass that faults every page in. Without this the fault time is
+    /// the allocate-and-touch loop. Huge pages are always touched up front.
+    pub fn zero_pages(mut self) -> Self {
+        self.zero_pages = true;
+        self
+    }
+
+    /// Limits the access loop to `bytes_per_s`. Each op then updates every
+    /// cache line of a random 64 KiB span and counts it in
+    /// `bytes_transferred()`. Ignored by the GC pattern.
+    pub fn bandwidth_target(mut self, bytes_per_s: f64) -> Self {
+        self.bandwidth_target = Some(bytes_per_s);
+        self
+    }
+
+    /// Flips one random bit of the target at a random location
+    /// `flips_per_s` times a second, counted in `mem_corruption_count()`.
+    /// Ignored by the GC pattern.
+    pub fn error_injection(mut self, flips_per_s: f64) -> Self {
+        self.error_injection = Some(flips_per_s).filter(|r| *r > 0.0);
+        self
+    }
+
+    /// Selects how regular-page chunks are allocated; `mem_fault_duration()`
+    /// then times that strategy. Ignored by the GC pattern.
+    pub fn alloc_strategy(mut self, strategy: MemAllocStrategy) -> Self {
+        self.alloc_strategy = strategy;
+        self
+    }
+
+    /// Picks each random access `distance` ops ahead and issues a software
+    /// prefetch for it (`_mm_prefetch` T0; x86_64 only, a no-op elsewhere)
+    /// so the line is in cache when the access comes due (0 = off). Ignored
+    /// with a bandwidth target and by the GC pattern.
+    pub fn prefetch_distance(mut self, distance: usize) -> Self {
+        self.prefetch_distance = distance;
+        self
+    }
+
+    /// Never takes the occasional 200us sleep in the access loop.
+    pub fn busy_wait(mut self) -> Self {
+        self.busy_wait = true;
+        self
+    }
+
+    /// Records the latency of one in `LATENCY_SAMPLE_OPS` accesses in a
+    /// histogram with `sigfig` significant digits, read with
+    /// `WorkerHandle::latency_histogram`. Needs the `hdr` feature; ignored
+    /// under a GC pattern or bandwidth target.
+    pub fn latency_histogram(mut self, sigfig: u8) -> Self {
+        self.latency_sigfig = Some(sigfig);
+        self
+    }
+
+    pub fn spawn(self) -> WorkerHandle {
+        WorkerHandle::spawn(move |stop, counters| {
+            if let Some((node, cpus)) = self.numa_node.as_ref() {
+                if !cpus.is_empty() && !set_thread_affinity(cpus) {
+                    log::warn!("Mem worker: cannot set affinity to node {}", node);
+                }
+                if !bind_thread_mem_to_node(*node) {
+                    log::warn!("Mem worker: cannot bind memory to node {}", node);
+                }
+            }
+            if let Some(cpu) = self.bind_cpu {
+                if !set_thread_affinity(&[cpu]) {
+                    log::warn!("Mem worker: cannot set affinity to CPU {}", cpu);
+                }
+            }
+            log::debug!("Mem worker: start ({} MB)", self.target_mb);
+            counters.enable_latency(self.latency_sigfig);
+            match self.gc_pattern {
+                Some(pattern) => gc_worker(stop, self.target_mb, pattern, counters),
+                None => mem_worker(stop, &self, counters),
+            }
+        })
+    }
+}
+
+/// Writes one byte per 4 KiB page.
+fn touch_pages(buf: &mut [u8]) {
+    for i in (0..buf.len()).step_by(4096) {
+        buf[i] = 1;
+    }
+}
+
+/// One regular-page chunk of the memory worker.
+enum MemChunk {
+    Heap(Vec<u8>),
+    Raw(RawBuf),
+}
+
+impl MemChunk {
+    fn alloc(size: usize, strategy: MemAllocStrategy) -> Option<Self> {
+        match strategy {
+            MemAllocStrategy::Malloc => Some(MemChunk::Heap(vec![0u8; size])),
+            MemAllocStrategy::Calloc => RawBuf::calloc(size).map(MemChunk::Raw),
+            MemAllocStrategy::Mmap => {
+                let buf = RawBuf::map(size, 0)?;
+                unsafe {
+                    madvise(buf.ptr as *mut c_void, buf.len, MADV_HUGEPAGE);
+                }
+                Some(MemChunk::Raw(buf))
+            }
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        match self {
+            MemChunk::Heap(v) => v.as_mut_slice(),
+            MemChunk::Raw(b) => b.as_mut_slice(),
+        }
+    }
+}
+
+/// Buffer `slot` of the worker; the huge page mapping is the last slot.
+fn buffer_at<'a>(
+    buffers: &'a mut [MemChunk],
+    huge: &'a mut Option<RawBuf>,
+    slot: usize,
+) -> &'a mut [u8] {
+    match buffers.get_mut(slot) {
+        Some(b) => b.as_mut_slice(),
+        None => huge.as_mut().map(RawBuf::as_mut_slice).unwrap_or_default(),
+    }
+}
+
+/// A random one of the worker's buffers, the huge page mapping included.
+fn pick_buffer<'a>(
+    buffers: &'a mut [MemChunk],
+    huge: &'a mut Option<RawBuf>,
+    rng: &mut Rng,
+) -> &'a mut [u8] {
+    let slots = buffers.len() + huge.is_some() as usize;
+    buffer_at(buffers, huge, (rng.next_u32() as usize) % slots)
+}
+
+/// Hints the CPU to pull the cache line at `ptr` into L1.
+#[inline(always)]
+fn prefetch(ptr: *const u8) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        use std::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr as *const i8);
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    let _ = ptr;
+}
+
+fn mem_worker(stop: Arc<AtomicBool>, cfg: &MemWorker, counters: Arc<Counters>) {
+    let chunk = 8 * 1024 * 1024usize;
+    let target_bytes = cfg.target_mb * 1024 * 1024;
+    let mut huge = None;
+    let mut allocated = 0u64;
+    let fault_start = Instant::now();
+    if cfg.huge_pages {
+        let pages = (target_bytes / HUGE_PAGE_BYTES).min(read_hugepages_free());
+        let len = (pages * HUGE_PAGE_BYTES) as usize;
+        match RawBuf::map(len, MAP_HUGETLB | MAP_HUGE_2MB).filter(|_| pages > 0) {
+            Some(mut buf) => {
+                touch_pages(buf.as_mut_slice());
+                counters.huge_pages.store(pages, Ordering::Relaxed);
+                allocated = pages * HUGE_PAGE_BYTES;
+                huge = Some(buf);
+            }
+            None => log::warn!("Mem worker: huge pages unavailable, using regular pages"),
+        }
+    }
+
+    let mut buffers: Vec<MemChunk> = Vec::new();
+    while allocated < target_bytes && !stop.load(Ordering::Relaxed) {
+        let size = (target_bytes - allocated).min(chunk as u64) as usize;
+        counters.allocs.fetch_add(1, Ordering::Relaxed);
+        let Some(mut buf) = MemChunk::alloc(size, cfg.alloc_strategy) else {
+            counters.alloc_fails.fetch_add(1, Ordering::Relaxed);
+            log::warn!(
+                "Mem worker: {} of {} bytes failed, stopping at {} MB",
+                cfg.alloc_strategy.name(),
+                size,
+                allocated / (1024 * 1024)
+            );
+            break;
+        };
+        if !cfg.zero_pages {
+            touch_pages(buf.as_mut_slice());
+        }
+        buffers.push(buf);
+        allocated += size as u64;
+    }
+    let fault_time = if cfg.zero_pages {
+        let first_write = Instant::now();
+        for buf in buffers.iter_mut() {
+            touch_pages(buf.as_mut_slice());
+        }
+        first_write.elapsed()
+    } else {
+        fault_start.elapsed()
+    };
+    counters
+        .fault_us
+        .store((fault_time.as_micros() as u64).max(1), Ordering::Relaxed);
+
+    let mut rng = Rng::new(seed_from_time());
+    let mut bucket = cfg.bandwidth_target.map(TokenBucket::new);
+    let mut unmetered = 0u64;
+    let flip_interval = cfg.error_injection.map(|r| Duration::from_secs_f64(1.0 / r));
+    let mut next_flip = Instant::now() + flip_interval.unwrap_or_default();
+    let mut ops = 0u64;
+    let mut ahead: VecDeque<(usize, usize)> = VecDeque::with_capacity(cfg.prefetch_distance + 1);
+    while !stop.load(Ordering::Relaxed) {
+        wait_if_paused(&stop, &counters);
+        let slots = buffers.len() + huge.is_some() as usize;
+        if slots == 0 {
+            thread::sleep(Duration::from_millis(200));
+            continue;
+        }
+        ops += 1;
+        if let Some(interval) = flip_interval.filter(|_| ops.is_multiple_of(INJECTION_OPS_PER_CHECK)) {
+            let now = Instant::now();
+            while next_flip <= now {
+                let buf = pick_buffer(&mut buffers, &mut huge, &mut rng);
+                let off = (rng.next_u64() % buf.len() as u64) as usize;
+                buf[off] ^= 1 << (rng.next_u32() % 8);
+                counters.corruptions.fetch_add(1, Ordering::Relaxed);
+                next_flip += interval;
+            }
+        }
+        let slot = (rng.next_u32() as usize) % slots;
+        let buf = buffer_at(&mut buffers, &mut huge, slot);
+        if let Some(bucket) = bucket.as_mut() {
+            let span = BANDWIDTH_SPAN.min(buf.len());
+            let off = (rng.next_u32() as usize) % (buf.len() - span + 1);
+            for b in buf[off..off + span].iter_mut().step_by(64) {
+                *b = b.wrapping_add(1);
+            }
+            counters.bytes.fetch_add(span as u64, Ordering::Relaxed);
+            counters.ops.fetch_add(1, Ordering::Relaxed);
+            unmetered += span as u64;
+            if unmetered >= BANDWIDTH_OPS_PER_CHECK * BANDWIDTH_SPAN as u64 {
+                bucket.consume(unmetered, &stop);
+                unmetered = 0;
+            }
+            continue;
+        }
+        let off = (rng.next_u32() as usize) % buf.len();
+        if cfg.prefetch_distance > 0 {
+            // Queue this access and do the one queued `prefetch_distance`
+            // ops ago, whose line should have arrived by now.
+            prefetch(buf[off..].as_ptr());
+            ahead.push_back((slot, off));
+            if ahead.len() <= cfg.prefetch_distance {
+                continue;
+            }
+        }
+        let (buf, off) = match ahead.pop_front() {
+            Some((slot, off)) => (buffer_at(&mut buffers, &mut huge, slot), off),
+            None => (buf, off),
+        };
+        let sample = (cfg.latency_sigfig.is_some() && ops.is_multiple_of(LATENCY_SAMPLE_OPS))
+            .then(Instant::now);
+        buf[off] = buf[off].wrapping_add(1);
+        if let Some(t0) = sample {
+            counters.record_latency(t0);
+        }
+        counters.ops.fetch_add(1, Ordering::Relaxed);
+        if !cfg.busy_wait && rng.next_u32().is_multiple_of(1000) {
+            thread::sleep(Duration::from_micros(200));
+        }
+    }
+    std::hint::black_box((buffers, huge));
+}
+
+fn try_alloc(size: usize, counters: &Counters) -> Option<Vec<u8>> {
+    counters.allocs.fetch_add(1, Ordering::Relaxed);
+    let mut v = Vec::new();
+    if v.try_reserve_exact(size).is_err() {
+        counters.alloc_fails.fetch_add(1, Ordering::Relaxed);
+        return None;
+    }
+    v.resize(size, 0);
+    for i in (0..size).step_by(4096) {
+        v[i] = 1;
+    }
+    Some(v)
+}
+
+fn gc_worker(stop: Arc<AtomicBool>, target_mb: u64, pattern: GcPattern, counters: Arc<Counters>) {
+    let young_size = (pattern.young_size_kb.max(1) * 1024) as usize;
+    let old_size = (pattern.old_size_mb.max(1) * 1024 * 1024) as usize;
+    let old_cap = (target_mb * 1024 * 1024).max(old_size as u64);
+    let mut old_gen: VecDeque<Vec<u8>> = VecDeque::new();
+    let mut old_bytes = 0u64;
+    let mut young: Vec<Vec<u8>> = Vec::with_capacity(pattern.young_count);
+    let mut rng = Rng::new(seed_from_time());
+    let mut bursts = 0u64;
+
+    while !stop.load(Ordering::Relaxed) {
+        wait_if_paused(&stop, &counters);
+        for _ in 0..pattern.young_count {
+            if let Some(mut obj) = try_alloc(young_size, &counters) {
+                let off = (rng.next_u32() as usize) % obj.len();
+                obj[off] = obj[off].wrapping_add(1);
+                young.push(obj);
+            }
+            counters.ops.fetch_add(1, Ordering::Relaxed);
+        }
+        std::hint::black_box(&young);
+        young.clear();
+        bursts += 1;
+
+        if bursts.is_multiple_of(GC_BURSTS_PER_PROMOTION) {
+            while old_bytes + old_size as u64 > old_cap {
+                match old_gen.pop_front() {
+                    Some(obj) => old_bytes -= obj.len() as u64,
+                    None => break,
+                }
+            }
+            if let Some(obj) = try_alloc(old_size, &counters) {
+                old_bytes += obj.len() as u64;
+                old_gen.push_back(obj);
+            }
+            counters.ops.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    std::hint::black_box(old_gen);
+}
+
+/// Access strides (bytes) swept by [`stride_test`].
+pub const STRIDE_TEST_STRIDES: [usize; 8] = [8, 16, 32, 64, 128, 256, 512, 4096];
+
+/// Increments one `u64` every `stride` bytes of a `buf_mb` buffer, for
+/// `per_stride` at each of [`STRIDE_TEST_STRIDES`]. Returns (stride, MB/s)
+/// where MB/s counts the 8 bytes actually used per access, so strides past
+/// a cache line or page show how much of each fetch is wasted.
+pub fn stride_test(buf_mb: u64, per_stride: Duration) -> Vec<(usize, f64)> {
+    let mut buf = vec![1u64; (buf_mb * 1024 * 1024 / 8) as usize];
+    STRIDE_TEST_STRIDES
+        .iter()
+        .map(|&stride| {
+            let step = stride / 8;
+            let per_pass = buf.len().div_ceil(step) as u64;
+            let mut accesses = 0u64;
+            let start = Instant::now();
+            while start.elapsed() < per_stride {
+                for v in buf.iter_mut().step_by(step) {
+                    *v = v.wrapping_add(1);
+                }
+                accesses += per_pass;
+            }
+            std::hint::black_box(&buf);
+            let mb = accesses as f64 * 8.0 / (1024.0 * 1024.0);
+            (stride, mb / start.elapsed().as_secs_f64())
+        })
+        .collect()
+}
+
+/// Node size of the pointer-chasing list: one cache line.
+const CHASE_NODE_WORDS: usize = 64 / std::mem::size_of::<usize>();
+/// Hops between stop/pause checks and counter updates.
+const CHASE_HOPS_PER_CHECK: u64 = 4096;
+
+/// Builds a single random cycle through every cache-line node of a
+/// `bytes`-sized buffer: each node's first word holds the index of the next
+/// node. Random order defeats the hardware prefetcher, so every hop is one
+/// dependent load at the latency of whichever level holds the buffer.
+fn build_chase(bytes: usize, rng: &mut Rng) -> Vec<usize> {
+    let nodes = (bytes / 64).max(2);
+    let mut order: Vec<usize> = (0..nodes).collect();
+    for i in (1..nodes).rev() {
+        order.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+    }
+    let mut buf = vec![0usize; nodes * CHASE_NODE_WORDS];
+    for (i, &node) in order.iter().enumerate() {
+        buf[node * CHASE_NODE_WORDS] = order[(i + 1) % nodes] * CHASE_NODE_WORDS;
+    }
+    buf
+}
+
+/// Follows `hops` links from `pos`; returns where it stopped.
+#[inline(never)]
+fn chase(buf: &[usize], mut pos: usize, hops: u64) -> usize {
+    for _ in 0..hops {
+        pos = buf[pos];
+    }
+    std::hint::black_box(pos)
+}
+
+/// Random-access memory latency probe: chases pointers through a randomly
+/// linked list of cache-line nodes spanning `buf_mb`. Each op is one hop,
+/// so latency in ns is `1e9 / ops_per_sec`.
+#[derive(Clone, Debug)]
+pub struct LatencyWorker {
+    buf_mb: u64,
+}
+
+impl LatencyWorker {
+    pub fn new(buf_mb: u64) -> Self {
+        Self {
+            buf_mb: buf_mb.max(1),
+        }
+    }
+
+    pub fn spawn(self) -> WorkerHandle {
+        WorkerHandle::spawn(move |stop, counters| {
+            log::debug!("Mem latency worker: start ({} MB)", self.buf_mb);
+            let mut rng = Rng::new(seed_from_time());
+            let buf = build_chase((self.buf_mb * 1024 * 1024) as usize, &mut rng);
+            let mut pos = 0;
+            while !stop.load(Ordering::Relaxed) {
+                wait_if_paused(&stop, &counters);
+                pos = chase(&buf, pos, CHASE_HOPS_PER_CHECK);
+                counters.ops.fetch_add(CHASE_HOPS_PER_CHECK, Ordering::Relaxed);
+            }
+        })
+    }
+}
+
+/// Working-set sizes (MB) swept by [`latency_sweep`].
+pub const LATENCY_SWEEP_MB: [u64; 7] = [1, 2, 4, 8, 16, 32, 64];
+
+/// Pointer-chases a fresh list of each of [`LATENCY_SWEEP_MB`] for
+/// `per_size`, after one untimed pass to fault in the pages. Returns
+/// (MB, ns per access); the steps in ns mark where the working set stops
+/// fitting in a cache level.
+pub fn latency_sweep(per_size: Duration) -> Vec<(u64, f64)> {
+    let mut rng = Rng::new(seed_from_time());
+    LATENCY_SWEEP_MB
+        .iter()
+        .map(|&mb| {
+            let buf = build_chase((mb * 1024 * 1024) as usize, &mut rng);
+            let nodes = (buf.len() / CHASE_NODE_WORDS) as u64;
+            let mut pos = chase(&buf, 0, nodes);
+            let mut hops = 0u64;
+            let start = Instant::now();
+            while start.elapsed() < per_size {
+                pos = chase(&buf, pos, CHASE_HOPS_PER_CHECK);
+                hops += CHASE_HOPS_PER_CHECK;
+            }
+            (mb, start.elapsed().as_secs_f64() * 1e9 / hops.max(1) as f64)
+        })
+        .collect()
+}
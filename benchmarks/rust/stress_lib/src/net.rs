@@ -0,0 +1,330 @@
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::{UnixDatagram, UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{wait_if_paused, Counters, WorkerHandle};
+
+/// Bytes sent (and echoed back) per round trip.
+const NET_MSG_BYTES: usize = 64 * 1024;
+/// Payload of each `AF_UNIX` datagram.
+const NET_DGRAM_BYTES: usize = 4 * 1024;
+/// Bounds how long a TCP connect worker blocks, so it notices `stop`.
+const TCP_IO_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long the accept loop waits in poll() before rechecking `stop`.
+const ACCEPT_POLL_MS: i32 = 100;
+/// Back-off after a failed TCP connect before retrying.
+const CONNECT_RETRY: Duration = Duration::from_millis(1);
+
+#[repr(C)]
+struct PollFd {
+    fd: i32,
+    events: i16,
+    revents: i16,
+}
+
+const POLLIN: i16 = 1;
+
+extern "C" {
+    fn poll(fds: *mut PollFd, nfds: u64, timeout: i32) -> i32;
+}
+
+/// Waits up to `timeout_ms` for `fd` to become readable (for a listener: a
+/// pending connection). Interrupted waits count as not ready.
+fn wait_readable(fd: i32, timeout_ms: i32) -> bool {
+    let mut pfd = PollFd {
+        fd,
+        events: POLLIN,
+        revents: 0,
+    };
+    unsafe { poll(&mut pfd, 1, timeout_ms) > 0 }
+}
+
+/// A client/server thread pair over an `AF_UNIX` socket.
+///
+/// Stream mode exchanges fixed-size messages over a blocking stream socket;
+/// one op is one round trip and bytes count both directions. Datagram mode
+/// sends fixed-size datagrams from a non-blocking client to a receiver; one
+/// op is one datagram received, and sends that fail with `EAGAIN` count as
+/// drops.
+///
+/// TCP connect mode opens a connection to a [`tcp_accept_server`], exchanges
+/// one byte and closes it, in a loop; one op is one completed connection.
+/// Connects that fail with `EADDRNOTAVAIL` (ephemeral ports exhausted) count
+/// as drops, other failures (refused, reset, timed out) in
+/// `connect_error_count()`; both are retried.
+#[derive(Clone)]
+pub struct NetWorker {
+    mode: NetMode,
+    latency_sigfig: Option<u8>,
+}
+
+#[derive(Clone)]
+enum NetMode {
+    Unix(PathBuf),
+    UnixDgram(PathBuf),
+    TcpConnect(SocketAddr),
+}
+
+impl NetWorker {
+    /// The socket is created at `socket_path` and removed when the worker exits.
+    pub fn unix(socket_path: PathBuf) -> Self {
+        Self {
+            mode: NetMode::Unix(socket_path),
+            latency_sigfig: None,
+        }
+    }
+
+    /// `SOCK_DGRAM` variant of [`NetWorker::unix`].
+    pub fn unix_dgram(socket_path: PathBuf) -> Self {
+        Self {
+            mode: NetMode::UnixDgram(socket_path),
+            latency_sigfig: None,
+        }
+    }
+
+    /// Connect/close loop against the listener at `addr`.
+    pub fn tcp_connect(addr: SocketAddr) -> Self {
+        Self {
+            mode: NetMode::TcpConnect(addr),
+            latency_sigfig: None,
+        }
+    }
+
+    /// Records each round trip (stream mode) or connection (TCP connect
+    /// mode) in a latency histogram with `sigfig` significant digits, read
+    /// with `WorkerHandle::latency_histogram`. Needs the `hdr` feature;
+    /// ignored in datagram mode, which has no round trip.
+    pub fn latency_histogram(mut self, sigfig: u8) -> Self {
+        self.latency_sigfig = Some(sigfig);
+        self
+    }
+
+    pub fn spawn(self) -> WorkerHandle {
+        WorkerHandle::spawn(move |stop, counters| {
+            counters.enable_latency(self.latency_sigfig);
+            match self.mode {
+                NetMode::Unix(path) => unix_worker(stop, counters, path),
+                NetMode::UnixDgram(path) => dgram_worker(stop, counters, path),
+                NetMode::TcpConnect(addr) => tcp_connect_worker(stop, counters, addr),
+            }
+        })
+    }
+}
+
+/// Serves [`NetWorker::tcp_connect`] workers on `listener`: each accepted
+/// connection gets its byte echoed and is closed. The loop sleeps in poll()
+/// until a connection is pending, then accepts every queued one, so it does
+/// not add latency to the clients' connect rate. The returned worker counts
+/// no ops; it only reports errors.
+pub fn tcp_accept_server(listener: TcpListener) -> WorkerHandle {
+    WorkerHandle::spawn(move |stop, counters| {
+        if let Err(e) = listener.set_nonblocking(true) {
+            counters.error(format!("Socket server: cannot set non-blocking: {}", e));
+            return;
+        }
+        let mut byte = [0u8; 1];
+        while !stop.load(Ordering::Relaxed) {
+            if !wait_readable(listener.as_raw_fd(), ACCEPT_POLL_MS) {
+                continue;
+            }
+            loop {
+                match listener.accept() {
+                    Ok((mut stream, _)) => {
+                        // The client may give up at shutdown; nothing to report.
+                        let _ = stream
+                            .set_nonblocking(false)
+                            .and_then(|_| stream.set_read_timeout(Some(TCP_IO_TIMEOUT)))
+                            .and_then(|_| stream.read_exact(&mut byte))
+                            .and_then(|_| stream.write_all(&byte));
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                    // The client gave up before we got to it.
+                    Err(e) if e.kind() == ErrorKind::ConnectionAborted => {}
+                    Err(e) => {
+                        counters.error(format!("Socket server: accept failed: {}", e));
+                        return;
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn unix_worker(stop: Arc<AtomicBool>, counters: Arc<Counters>, path: PathBuf) {
+    let _ = std::fs::remove_file(&path);
+    let listener = match UnixListener::bind(&path) {
+        Ok(l) => l,
+        Err(e) => {
+            counters.error(format!("Net worker: cannot bind {}: {}", path.display(), e));
+            return;
+        }
+    };
+    if let Err(e) = listener.set_nonblocking(true) {
+        counters.error(format!("Net worker: cannot set non-blocking: {}", e));
+        let _ = std::fs::remove_file(&path);
+        return;
+    }
+    // Set once the client is done, so the server stops waiting for a
+    // connection that will never come (failed connect).
+    let done = Arc::new(AtomicBool::new(false));
+    let server_done = Arc::clone(&done);
+    let server = thread::spawn(move || loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if stream.set_nonblocking(false).is_ok() {
+                    echo(stream);
+                }
+                return;
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                if server_done.load(Ordering::Relaxed) {
+                    return;
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            Err(_) => return,
+        }
+    });
+
+    match UnixStream::connect(&path) {
+        Ok(stream) => round_trips(&stop, &counters, stream),
+        Err(e) => counters.error(format!("Net worker: cannot connect {}: {}", path.display(), e)),
+    }
+    done.store(true, Ordering::Relaxed);
+    let _ = server.join();
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Echoes everything back until the peer closes the connection.
+fn echo<S: Read + Write>(mut stream: S) {
+    let mut buf = vec![0u8; NET_MSG_BYTES];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return,
+            Ok(n) => {
+                if stream.write_all(&buf[..n]).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn round_trips<S: Read + Write>(stop: &AtomicBool, counters: &Counters, mut stream: S) {
+    let msg = vec![0x5au8; NET_MSG_BYTES];
+    let mut reply = vec![0u8; NET_MSG_BYTES];
+    while !stop.load(Ordering::Relaxed) {
+        wait_if_paused(stop, counters);
+        let t0 = Instant::now();
+        if let Err(e) = stream.write_all(&msg).and_then(|_| stream.read_exact(&mut reply)) {
+            counters.error(format!("Net worker: round trip failed: {}", e));
+            return;
+        }
+        counters.record_latency(t0);
+        counters
+            .bytes
+            .fetch_add(2 * NET_MSG_BYTES as u64, Ordering::Relaxed);
+        counters.ops.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn tcp_connect_worker(stop: Arc<AtomicBool>, counters: Arc<Counters>, addr: SocketAddr) {
+    let mut byte = [0u8; 1];
+    while !stop.load(Ordering::Relaxed) {
+        wait_if_paused(&stop, &counters);
+        let t0 = Instant::now();
+        let exchange = TcpStream::connect_timeout(&addr, TCP_IO_TIMEOUT).and_then(|mut s| {
+            s.set_read_timeout(Some(TCP_IO_TIMEOUT))?;
+            s.write_all(b"x")?;
+            s.read_exact(&mut byte)
+        });
+        match exchange {
+            Ok(()) => {
+                counters.record_latency(t0);
+                counters.ops.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) if e.kind() == ErrorKind::AddrNotAvailable => {
+                counters.drops.fetch_add(1, Ordering::Relaxed);
+                thread::sleep(CONNECT_RETRY);
+            }
+            // The server stops alongside us and may drop in-flight connects.
+            Err(_) if stop.load(Ordering::Relaxed) => return,
+            // Refused, reset or timed out under load: count it and retry.
+            Err(e) => {
+                if counters.conn_errors.fetch_add(1, Ordering::Relaxed) == 0 {
+                    log::warn!("Socket worker: {} failed: {} (retrying)", addr, e);
+                }
+                thread::sleep(CONNECT_RETRY);
+            }
+        }
+    }
+}
+
+fn dgram_worker(stop: Arc<AtomicBool>, counters: Arc<Counters>, path: PathBuf) {
+    let _ = std::fs::remove_file(&path);
+    let receiver = match UnixDatagram::bind(&path) {
+        Ok(s) => s,
+        Err(e) => {
+            counters.error(format!("Net worker: cannot bind {}: {}", path.display(), e));
+            return;
+        }
+    };
+    // Lets the receiver notice `stop` once the sender has gone quiet.
+    let _ = receiver.set_read_timeout(Some(Duration::from_millis(100)));
+    let sender = UnixDatagram::unbound()
+        .and_then(|s| s.connect(&path).map(|_| s))
+        .and_then(|s| s.set_nonblocking(true).map(|_| s));
+    let sender = match sender {
+        Ok(s) => s,
+        Err(e) => {
+            counters.error(format!("Net worker: cannot connect {}: {}", path.display(), e));
+            let _ = std::fs::remove_file(&path);
+            return;
+        }
+    };
+
+    let rx_stop = Arc::clone(&stop);
+    let rx_counters = Arc::clone(&counters);
+    let server = thread::spawn(move || {
+        let mut buf = vec![0u8; NET_DGRAM_BYTES];
+        while !rx_stop.load(Ordering::Relaxed) {
+            match receiver.recv(&mut buf) {
+                Ok(n) => {
+                    rx_counters.bytes.fetch_add(n as u64, Ordering::Relaxed);
+                    rx_counters.ops.fetch_add(1, Ordering::Relaxed);
+                }
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) => {
+                    rx_counters.error(format!("Net worker: recv failed: {}", e));
+                    return;
+                }
+            }
+        }
+    });
+
+    let msg = vec![0x5au8; NET_DGRAM_BYTES];
+    while !stop.load(Ordering::Relaxed) {
+        wait_if_paused(&stop, &counters);
+        match sender.send(&msg) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                counters.drops.fetch_add(1, Ordering::Relaxed);
+                thread::yield_now();
+            }
+            Err(e) => {
+                counters.error(format!("Net worker: send failed: {}", e));
+                break;
+            }
+        }
+    }
+    let _ = server.join();
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,92 @@
+use std::time::Instant;
+#[cfg(feature = "otel")]
+use std::time::SystemTime;
+
+use crate::rng::{seed_from_time, Rng};
+
+/// Per-worker span sampling for OpenTelemetry export (`otel` feature).
+///
+/// Spans go to the global tracer provider, which the binary installs before
+/// spawning workers. Without the feature `start` never samples.
+pub(crate) struct SpanSampler {
+    rate: f64,
+    rng: Rng,
+    #[cfg(feature = "otel")]
+    tracer: opentelemetry::global::BoxedTracer,
+}
+
+impl SpanSampler {
+    pub(crate) fn new(rate: f64) -> Self {
+        Self {
+            rate: rate.clamp(0.0, 1.0),
+            // Separate from the workers' own RNGs so seeded runs stay
+            // reproducible with tracing on.
+            rng: Rng::new(seed_from_time()),
+            #[cfg(feature = "otel")]
+            tracer: opentelemetry::global::tracer("stress"),
+        }
+    }
+
+    /// `Some(start)` if the next operation should get a span.
+    pub(crate) fn start(&mut self) -> Option<Instant> {
+        if !cfg!(feature = "otel") || self.rate <= 0.0 {
+            return None;
+        }
+        (self.rng.next_f64() < self.rate).then(Instant::now)
+    }
+
+    /// Records a disk I/O that began at `t0`.
+    pub(crate) fn disk_span(&self, operation: &'static str, offset: u64, size: usize, t0: Instant) {
+        #[cfg(feature = "otel")]
+        {
+            use opentelemetry::KeyValue;
+            self.emit(
+                if operation == "read" { "disk.read" } else { "disk.write" },
+                t0,
+                vec![
+                    KeyValue::new("operation", operation),
+                    KeyValue::new("offset_bytes", offset as i64),
+                    KeyValue::new("size_bytes", size as i64),
+                ],
+            );
+        }
+        #[cfg(not(feature = "otel"))]
+        let _ = (operation, offset, size, t0);
+    }
+
+    /// Records a CPU burst of `ops` operations that began at `t0`.
+    pub(crate) fn cpu_span(&self, workload: &'static str, ops: u64, t0: Instant) {
+        #[cfg(feature = "otel")]
+        {
+            use opentelemetry::KeyValue;
+            self.emit(
+                "cpu.burst",
+                t0,
+                vec![
+                    KeyValue::new("workload", workload),
+                    KeyValue::new("ops", ops as i64),
+                ],
+            );
+        }
+        #[cfg(not(feature = "otel"))]
+        let _ = (workload, ops, t0);
+    }
+
+    #[cfg(feature = "otel")]
+    fn emit(&self, name: &'static str, t0: Instant, mut attributes: Vec<opentelemetry::KeyValue>) {
+        use opentelemetry::trace::{Span, Tracer};
+        let elapsed = t0.elapsed();
+        let end = SystemTime::now();
+        attributes.push(opentelemetry::KeyValue::new(
+            "duration_us",
+            elapsed.as_micros() as i64,
+        ));
+        let mut span = self
+            .tracer
+            .span_builder(name)
+            .with_start_time(end - elapsed)
+            .with_attributes(attributes)
+            .start(&self.tracer);
+        span.end_with_timestamp(end);
+    }
+}
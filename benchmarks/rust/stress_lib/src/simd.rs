@@ -0,0 +1,112 @@
+/// Widest vector unit the `avx512` CPU workload can use on this machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimdWidth {
+    /// 512-bit AVX-512F fused multiply-add.
+    Avx512,
+    /// 256-bit AVX2 + FMA fallback.
+    Avx2,
+    /// No usable vector unit; the workload runs the scalar LCG.
+    Scalar,
+}
+
+impl SimdWidth {
+    /// Runtime CPU feature detection (cached by std).
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx512f") {
+                return SimdWidth::Avx512;
+            }
+            if is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+                return SimdWidth::Avx2;
+            }
+        }
+        SimdWidth::Scalar
+    }
+
+    pub fn bits(self) -> u32 {
+        match self {
+            SimdWidth::Avx512 => 512,
+            SimdWidth::Avx2 => 256,
+            SimdWidth::Scalar => 64,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SimdWidth::Avx512 => "AVX-512F",
+            SimdWidth::Avx2 => "AVX2+FMA",
+            SimdWidth::Scalar => "scalar LCG",
+        }
+    }
+}
+
+/// Independent FMA chains per burst, enough to keep both FMA ports busy.
+const CHAINS: usize = 8;
+/// f64 accumulators a burst needs: `CHAINS` vectors of up to 8 lanes.
+pub(crate) const SIMD_LANES: usize = CHAINS * 8;
+
+const FMA_MUL: f64 = 0.999_999_9;
+const FMA_ADD: f64 = 1e-7;
+
+/// Runs `iters` rounds of `CHAINS` vector multiply-adds over `acc`, which
+/// holds the accumulators between bursts. Returns false when `width` has no
+/// vector path so the caller can fall back.
+pub(crate) fn fma_burst(width: SimdWidth, acc: &mut [f64], iters: u64) -> bool {
+    assert!(acc.len() >= SIMD_LANES);
+    match width {
+        #[cfg(target_arch = "x86_64")]
+        SimdWidth::Avx512 => {
+            // SAFETY: `detect` saw avx512f.
+            unsafe { fma_avx512(acc, iters) };
+            true
+        }
+        #[cfg(target_arch = "x86_64")]
+        SimdWidth::Avx2 => {
+            // SAFETY: `detect` saw avx2 and fma.
+            unsafe { fma_avx2(acc, iters) };
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f")]
+fn fma_avx512(acc: &mut [f64], iters: u64) {
+    use std::arch::x86_64::*;
+    let mul = _mm512_set1_pd(FMA_MUL);
+    let add = _mm512_set1_pd(FMA_ADD);
+    let mut v = [_mm512_setzero_pd(); CHAINS];
+    for (i, r) in v.iter_mut().enumerate() {
+        *r = unsafe { _mm512_loadu_pd(acc.as_ptr().add(i * 8)) };
+    }
+    for _ in 0..iters {
+        for r in v.iter_mut() {
+            *r = _mm512_fmadd_pd(*r, mul, add);
+        }
+    }
+    for (i, r) in v.iter().enumerate() {
+        unsafe { _mm512_storeu_pd(acc.as_mut_ptr().add(i * 8), *r) };
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
+fn fma_avx2(acc: &mut [f64], iters: u64) {
+    use std::arch::x86_64::*;
+    let mul = _mm256_set1_pd(FMA_MUL);
+    let add = _mm256_set1_pd(FMA_ADD);
+    let mut v = [_mm256_setzero_pd(); CHAINS];
+    for (i, r) in v.iter_mut().enumerate() {
+        *r = unsafe { _mm256_loadu_pd(acc.as_ptr().add(i * 4)) };
+    }
+    for _ in 0..iters {
+        for r in v.iter_mut() {
+            *r = _mm256_fmadd_pd(*r, mul, add);
+        }
+    }
+    for (i, r) in v.iter().enumerate() {
+        unsafe { _mm256_storeu_pd(acc.as_mut_ptr().add(i * 4), *r) };
+    }
+}
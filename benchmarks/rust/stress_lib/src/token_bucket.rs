@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest single sleep while paying off a deficit, so a stop request is
+/// noticed promptly even at very low rates.
+const SLEEP_SLICE: Duration = Duration::from_millis(50);
+
+/// Longest deficit paid off in one call; keeps a tiny rate from asking
+/// for a wait that does not fit in a `Duration`.
+const MAX_WAIT: Duration = Duration::from_secs(3600);
+
+/// Token bucket refilled at `rate` ops/s; holds at most 50ms worth of ops.
+/// Shared by the CPU ops target, the disk IOPS limit and the memory
+/// bandwidth target.
+pub(crate) struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    /// Most tokens banked while idle: how far a burst may run ahead.
+    burst: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(rate: f64) -> Self {
+        Self {
+            rate,
+            tokens: 0.0,
+            burst: rate * 0.05,
+            last: Instant::now(),
+        }
+    }
+
+    /// Banks up to `tokens` while idle instead of 50ms worth.
+    pub(crate) fn with_burst(mut self, tokens: f64) -> Self {
+        self.burst = tokens;
+        self
+    }
+
+    /// Takes `ops` tokens, sleeping off any deficit in short slices and
+    /// returning early once `stop` is set. Returns the time slept.
+    pub(crate) fn consume(&mut self, ops: u64, stop: &AtomicBool) -> Duration {
+        let now = Instant::now();
+        let refill = now.duration_since(self.last).as_secs_f64() * self.rate;
+        self.last = now;
+        self.tokens = (self.tokens + refill).min(self.burst) - ops as f64;
+        if self.tokens >= 0.0 {
+            return Duration::ZERO;
+        }
+        let wait = Duration::try_from_secs_f64(-self.tokens / self.rate).unwrap_or(MAX_WAIT);
+        let end = now + wait.min(MAX_WAIT);
+        while !stop.load(Ordering::Relaxed) {
+            let left = end.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                break;
+            }
+            thread::sleep(left.min(SLEEP_SLICE));
+        }
+        now.elapsed()
+    }
+}
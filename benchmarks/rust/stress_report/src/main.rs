@@ -0,0 +1,269 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+
+/// Chart.js build the report loads; viewing it needs network access.
+const CHART_JS_URL: &str = "https://cdn.jsdelivr.net/npm/chart.js@4.4.1/dist/chart.umd.min.js";
+/// First column of every `stress_all --csv` row: seconds since the run
+/// started. A line starting with it is a header.
+const TS_COLUMN: &str = "ts";
+/// Line colours, one per run, reused after the last.
+const PALETTE: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+
+/// Command line.
+#[derive(Parser, Debug)]
+#[command(
+    name = "stress_report",
+    about = "Compare stress_all --csv runs in one HTML report"
+)]
+struct Cli {
+    /// A stress_all --csv file; repeat for each run to compare. A file that
+    /// several runs appended to is split at each header line.
+    #[arg(long = "input", value_name = "path", required = true)]
+    inputs: Vec<PathBuf>,
+    /// HTML file to write.
+    #[arg(long, value_name = "path", default_value = "report.html")]
+    output: PathBuf,
+}
+
+/// Samples from one header line to the next.
+struct Run {
+    label: String,
+    /// (column, (ts, value) samples) in header order, without `ts`.
+    series: Vec<(String, Vec<(f64, f64)>)>,
+}
+
+impl Run {
+    fn samples(&self, column: &str) -> Option<&[(f64, f64)]> {
+        self.series
+            .iter()
+            .find(|(name, _)| name == column)
+            .map(|(_, samples)| samples.as_slice())
+    }
+}
+
+/// Runs in `path`, labelled with the file name (plus " (2)", " (3)", ...
+/// for later runs in the same file). Unparsable fields are skipped.
+fn read_runs(path: &Path) -> io::Result<Vec<Run>> {
+    let text = std::fs::read_to_string(path)?;
+    let name = path
+        .file_name()
+        .map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+    let mut runs: Vec<Run> = Vec::new();
+    for line in text.lines() {
+        let fields: Vec<&str> = line.trim().split(',').map(str::trim).collect();
+        if fields[0] == TS_COLUMN {
+            let label = match runs.len() {
+                0 => name.clone(),
+                n => format!("{} ({})", name, n + 1),
+            };
+            let series = fields[1..].iter().map(|c| (c.to_string(), Vec::new())).collect();
+            runs.push(Run { label, series });
+            continue;
+        }
+        let (Some(run), Ok(ts)) = (runs.last_mut(), fields[0].parse::<f64>()) else {
+            continue;
+        };
+        for ((_, samples), field) in run.series.iter_mut().zip(&fields[1..]) {
+            if let Some(v) = field.parse::<f64>().ok().filter(|v| v.is_finite()) {
+                samples.push((ts, v));
+            }
+        }
+    }
+    runs.retain(|run| run.series.iter().any(|(_, samples)| !samples.is_empty()));
+    Ok(runs)
+}
+
+/// Summary of one series; percentiles are nearest-rank.
+struct Stats {
+    samples: usize,
+    min: f64,
+    avg: f64,
+    p50: f64,
+    p95: f64,
+    max: f64,
+}
+
+fn stats(samples: &[(f64, f64)]) -> Option<Stats> {
+    if samples.is_empty() {
+        return None;
+    }
+    let mut values: Vec<f64> = samples.iter().map(|&(_, v)| v).collect();
+    values.sort_by(f64::total_cmp);
+    let n = values.len();
+    let rank = |p: f64| values[((p * n as f64).ceil() as usize).clamp(1, n) - 1];
+    Some(Stats {
+        samples: n,
+        min: values[0],
+        avg: values.iter().sum::<f64>() / n as f64,
+        p50: rank(0.50),
+        p95: rank(0.95),
+        max: values[n - 1],
+    })
+}
+
+/// Columns of any run in first-seen order, dropping ones that are 0 in
+/// every run (sensors or subsystems that were off).
+fn metrics(runs: &[Run]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for run in runs {
+        for (name, samples) in &run.series {
+            let seen = out.contains(name);
+            if !seen && samples.iter().any(|&(_, v)| v != 0.0) {
+                out.push(name.clone());
+            }
+        }
+    }
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `s` as a double-quoted JavaScript string that is also safe inside a
+/// `<script>` element.
+fn js_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '<' => out.push_str("\\u003c"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// One overlaid line chart per metric and a summary table, as a single
+/// HTML page.
+fn write_report(out: &mut impl Write, runs: &[Run], metrics: &[String]) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html><head><meta charset=\"utf-8\"><title>stress_all report</title>")?;
+    writeln!(out, "<script src=\"{}\"></script>", CHART_JS_URL)?;
+    writeln!(
+        out,
+        "<style>body{{font-family:sans-serif;margin:2em}}\
+         .chart{{width:900px;height:300px;margin-bottom:2em}}\
+         table{{border-collapse:collapse}}\
+         td,th{{border:1px solid #ccc;padding:2px 8px;text-align:right}}\
+         td:nth-child(-n+2),th{{text-align:left}}</style>"
+    )?;
+    writeln!(out, "</head><body>")?;
+    writeln!(out, "<h1>stress_all report</h1>")?;
+    writeln!(out, "<ul>")?;
+    for (i, run) in runs.iter().enumerate() {
+        writeln!(
+            out,
+            "<li style=\"color:{}\">{}</li>",
+            PALETTE[i % PALETTE.len()],
+            html_escape(&run.label)
+        )?;
+    }
+    writeln!(out, "</ul>")?;
+
+    writeln!(out, "<h2>Summary</h2>")?;
+    writeln!(
+        out,
+        "<table><tr><th>metric</th><th>run</th><th>samples</th>\
+         <th>min</th><th>avg</th><th>p50</th><th>p95</th><th>max</th></tr>"
+    )?;
+    for metric in metrics {
+        for run in runs {
+            let Some(s) = run.samples(metric).and_then(stats) else {
+                continue;
+            };
+            writeln!(
+                out,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.2}</td><td>{:.2}</td>\
+                 <td>{:.2}</td><td>{:.2}</td><td>{:.2}</td></tr>",
+                html_escape(metric),
+                html_escape(&run.label),
+                s.samples,
+                s.min,
+                s.avg,
+                s.p50,
+                s.p95,
+                s.max
+            )?;
+        }
+    }
+    writeln!(out, "</table>")?;
+
+    writeln!(out, "<h2>Time series</h2>")?;
+    for i in 0..metrics.len() {
+        writeln!(out, "<div class=\"chart\"><canvas id=\"c{}\"></canvas></div>", i)?;
+    }
+    writeln!(out, "<script>")?;
+    for (i, metric) in metrics.iter().enumerate() {
+        write!(out, "new Chart(document.getElementById('c{}'),{{type:'line',data:{{datasets:[", i)?;
+        for (r, run) in runs.iter().enumerate() {
+            let Some(samples) = run.samples(metric) else {
+                continue;
+            };
+            write!(
+                out,
+                "{{label:{},borderColor:'{}',borderWidth:1.5,pointRadius:0,data:[",
+                js_string(&run.label),
+                PALETTE[r % PALETTE.len()]
+            )?;
+            for (ts, v) in samples {
+                write!(out, "{{x:{},y:{}}},", ts, v)?;
+            }
+            write!(out, "]}},")?;
+        }
+        writeln!(
+            out,
+            "]}},options:{{animation:false,parsing:false,maintainAspectRatio:false,\
+             scales:{{x:{{type:'linear',title:{{display:true,text:'seconds'}}}}}},\
+             plugins:{{title:{{display:true,text:{}}}}}}}}});",
+            js_string(metric)
+        )?;
+    }
+    writeln!(out, "</script>")?;
+    writeln!(out, "</body></html>")
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let mut runs = Vec::new();
+    for path in &cli.inputs {
+        match read_runs(path) {
+            Ok(found) if found.is_empty() => {
+                eprintln!("{}: no samples under a `{},...` header", path.display(), TS_COLUMN);
+                std::process::exit(2);
+            }
+            Ok(found) => runs.extend(found),
+            Err(e) => {
+                eprintln!("Cannot read {}: {}", path.display(), e);
+                std::process::exit(2);
+            }
+        }
+    }
+    let metrics = metrics(&runs);
+    let written = File::create(&cli.output).and_then(|f| {
+        let mut out = BufWriter::new(f);
+        write_report(&mut out, &runs, &metrics)?;
+        out.flush()
+    });
+    if let Err(e) = written {
+        eprintln!("Cannot write {}: {}", cli.output.display(), e);
+        std::process::exit(1);
+    }
+    eprintln!(
+        "Wrote {} ({} runs, {} metrics)",
+        cli.output.display(),
+        runs.len(),
+        metrics.len()
+    );
+}
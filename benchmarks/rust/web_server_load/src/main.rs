@@ -1,72 +1,446 @@
+use actix_web::http::KeepAlive;
+use actix_web::web::Bytes;
 use actix_web::{web, App, HttpServer, HttpResponse};
+use std::collections::HashMap;
+use std::env;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 static COUNTER: AtomicU64 = AtomicU64::new(0);
 
-async fn handle_request() -> HttpResponse {
-    COUNTER.fetch_add(1, Ordering::Relaxed);
-    HttpResponse::Ok().body("ok")
+/// Query parameters appended per request by `--web-server-route-params`.
+const QUERY_PARAMS: usize = 4;
+
+struct Args {
+    http2: bool,
+    http2_compare: bool,
+    request_size: usize,
+    response_size: usize,
+    workers: usize,
+    /// Keep-alive timeout in seconds; 0 disables keep-alive.
+    keepalive_s: u64,
+    backlog: u32,
+    /// Routes `/0`..`/N-1`; 1 keeps the single `/` route.
+    route_count: usize,
+    route_params: bool,
 }
 
-#[tokio::main]
-async fn main() -> std::io::Result<()> {
-    let total_requests: u64 = 10_000;
-    let concurrency: usize = 100;
-    let port = 18787u16;
+struct LoadResult {
+    elapsed: f64,
+    /// Requests answered with a 2xx status and a fully read body.
+    completed: u64,
+    /// Requests that failed to send, got a non-2xx status or lost the body.
+    errors: u64,
+    /// Request plus response body bytes.
+    bytes: u64,
+    /// Latencies of completed requests in microseconds, sorted.
+    latencies_us: Vec<u64>,
+}
+
+impl LoadResult {
+    fn rps(&self) -> f64 {
+        self.completed as f64 / self.elapsed
+    }
 
-    // Start server in background
-    let server = HttpServer::new(|| {
-        App::new().route("/", web::get().to(handle_request))
+    fn mb_s(&self) -> f64 {
+        self.bytes as f64 / (1024.0 * 1024.0) / self.elapsed
+    }
+
+    fn percentile_us(&self, p: f64) -> u64 {
+        if self.latencies_us.is_empty() {
+            return 0;
+        }
+        let idx = ((self.latencies_us.len() - 1) as f64 * p).round() as usize;
+        self.latencies_us[idx]
+    }
+}
+
+fn print_usage() {
+    eprintln!(
+        "Rust Web Server Load\n\
+Usage: web_server_load [options]\n\n\
+Options:\n\
+  --http2               Use cleartext HTTP/2 (h2c, prior knowledge) instead of\n\
+                        HTTP/1.1 keep-alive.\n\
+  --http2-compare       Run HTTP/1.1 then HTTP/2 and print them side by side.\n\
+  --net-request-size <bytes>\n\
+                        POST a body of this size with every request\n\
+                        (default: 0 = plain GET).\n\
+  --net-response-size <bytes>\n\
+                        Response body size, zero-padded (default: \"ok\").\n\
+  --web-server-workers <N>\n\
+                        Server worker threads (default: 4).\n\
+  --web-server-keepalive <s>\n\
+                        Connection keep-alive timeout (default: 5, 0 = off).\n\
+  --web-server-backlog <N>\n\
+                        Listen backlog (default: 2048).\n\
+  --web-server-route-count <N>\n\
+                        Register routes /0../N-1 and cycle requests through\n\
+                        them (default: 1 = just /).\n\
+  --web-server-route-params\n\
+                        Append random query parameters to every request and\n\
+                        have the server parse them.\n\
+  -h, --help            Show this help.\n"
+    );
+}
+
+fn parse_u64(s: &str, name: &str) -> u64 {
+    s.parse::<u64>().unwrap_or_else(|_| {
+        eprintln!("Invalid {}: {}", name, s);
+        std::process::exit(2);
     })
-    .workers(4)
-    .bind(format!("127.0.0.1:{}", port))?
-    .run();
+}
 
-    let server_handle = server.handle();
-    let server_task = tokio::spawn(server);
+fn parse_args() -> Args {
+    let mut http2 = false;
+    let mut http2_compare = false;
+    let mut request_size = 0usize;
+    let mut response_size = 0usize;
+    let mut workers = 4usize;
+    let mut keepalive_s = 5u64;
+    let mut backlog = 2048u32;
+    let mut route_count = 1usize;
+    let mut route_params = false;
 
-    // Wait for server to be ready
-    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    let mut it = env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--http2" => http2 = true,
+            "--http2-compare" => http2_compare = true,
+            "--net-request-size" => {
+                if let Some(v) = it.next() {
+                    request_size = parse_u64(&v, "net-request-size") as usize;
+                } else {
+                    eprintln!("--net-request-size requires a value");
+                    std::process::exit(2);
+                }
+            }
+            "--net-response-size" => {
+                if let Some(v) = it.next() {
+                    response_size = parse_u64(&v, "net-response-size") as usize;
+                } else {
+                    eprintln!("--net-response-size requires a value");
+                    std::process::exit(2);
+                }
+            }
+            "--web-server-workers" => {
+                if let Some(v) = it.next() {
+                    workers = (parse_u64(&v, "web-server-workers") as usize).max(1);
+                } else {
+                    eprintln!("--web-server-workers requires a value");
+                    std::process::exit(2);
+                }
+            }
+            "--web-server-keepalive" => {
+                if let Some(v) = it.next() {
+                    keepalive_s = parse_u64(&v, "web-server-keepalive");
+                } else {
+                    eprintln!("--web-server-keepalive requires a value");
+                    std::process::exit(2);
+                }
+            }
+            "--web-server-backlog" => {
+                if let Some(v) = it.next() {
+                    backlog = parse_u64(&v, "web-server-backlog").clamp(1, u32::MAX as u64) as u32;
+                } else {
+                    eprintln!("--web-server-backlog requires a value");
+                    std::process::exit(2);
+                }
+            }
+            "--web-server-route-count" => {
+                if let Some(v) = it.next() {
+                    route_count = (parse_u64(&v, "web-server-route-count") as usize).max(1);
+                } else {
+                    eprintln!("--web-server-route-count requires a value");
+                    std::process::exit(2);
+                }
+            }
+            "--web-server-route-params" => route_params = true,
+            "-h" | "--help" => {
+                print_usage();
+                std::process::exit(0);
+            }
+            _ => {
+                eprintln!("Unknown option: {}", arg);
+                print_usage();
+                std::process::exit(2);
+            }
+        }
+    }
 
-    println!("Server started on port {}", port);
-    println!("Sending {} requests with {} concurrency...", total_requests, concurrency);
+    Args {
+        http2,
+        http2_compare,
+        request_size,
+        response_size,
+        workers,
+        keepalive_s,
+        backlog,
+        route_count,
+        route_params,
+    }
+}
+
+async fn handle_request(_body: Bytes, response: web::Data<Bytes>) -> HttpResponse {
+    COUNTER.fetch_add(1, Ordering::Relaxed);
+    HttpResponse::Ok().body(response.get_ref().clone())
+}
+
+async fn handle_params_request(
+    query: web::Query<HashMap<String, String>>,
+    _body: Bytes,
+    response: web::Data<Bytes>,
+) -> HttpResponse {
+    COUNTER.fetch_add(1, Ordering::Relaxed);
+    std::hint::black_box(query.into_inner());
+    HttpResponse::Ok().body(response.get_ref().clone())
+}
+
+/// Path and query for a worker's next request: routes are taken in turn
+/// across all workers, and the query (if any) holds `QUERY_PARAMS` random
+/// values from `state`.
+fn request_path(seq: u64, routes: usize, params: bool, state: &mut u64) -> String {
+    let mut path = if routes > 1 {
+        format!("{}", seq % routes as u64)
+    } else {
+        String::new()
+    };
+    if params {
+        for i in 0..QUERY_PARAMS {
+            // xorshift64
+            *state ^= *state << 13;
+            *state ^= *state >> 7;
+            *state ^= *state << 17;
+            path.push(if i == 0 { '?' } else { '&' });
+            path.push_str(&format!("p{}={:x}", i, *state));
+        }
+    }
+    path
+}
+
+async fn run_load(
+    url: &str,
+    http2: bool,
+    routes: usize,
+    route_params: bool,
+    total_requests: u64,
+    concurrency: usize,
+    body: Bytes,
+) -> LoadResult {
+    let builder = reqwest::Client::builder();
+    let client = if http2 {
+        builder.http2_prior_knowledge()
+    } else {
+        builder.http1_only()
+    }
+    .build()
+    .expect("cannot build HTTP client");
 
     let start = Instant::now();
-    let client = reqwest::Client::new();
-    let url = format!("http://127.0.0.1:{}/", port);
     let completed = Arc::new(AtomicU64::new(0));
+    let errors = Arc::new(AtomicU64::new(0));
+    let bytes = Arc::new(AtomicU64::new(0));
 
     let mut handles = Vec::new();
     let requests_per_worker = total_requests / concurrency as u64;
 
-    for _ in 0..concurrency {
+    for w in 0..concurrency {
         let client = client.clone();
-        let url = url.clone();
+        let url = url.to_string();
         let completed = completed.clone();
+        let errors = errors.clone();
+        let bytes = bytes.clone();
+        let body = body.clone();
         handles.push(tokio::spawn(async move {
-            for _ in 0..requests_per_worker {
-                let _ = client.get(&url).send().await;
-                completed.fetch_add(1, Ordering::Relaxed);
+            let mut latencies = Vec::with_capacity(requests_per_worker as usize);
+            let mut rng_state = 0x9E37_79B9_7F4A_7C15u64 ^ (w as u64 + 1);
+            for k in 0..requests_per_worker {
+                let seq = w as u64 + k * concurrency as u64;
+                let target = format!(
+                    "{}{}",
+                    url,
+                    request_path(seq, routes, route_params, &mut rng_state)
+                );
+                let t0 = Instant::now();
+                let request = if body.is_empty() {
+                    client.get(&target)
+                } else {
+                    client.post(&target).body(body.clone())
+                };
+                let received = match request.send().await {
+                    Ok(resp) if resp.status().is_success() => resp.bytes().await.ok(),
+                    _ => None,
+                };
+                match received {
+                    Some(received) => {
+                        latencies.push(t0.elapsed().as_micros() as u64);
+                        bytes.fetch_add((body.len() + received.len()) as u64, Ordering::Relaxed);
+                        completed.fetch_add(1, Ordering::Relaxed);
+                    }
+                    None => {
+                        errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
             }
+            latencies
         }));
     }
 
+    let mut latencies_us = Vec::new();
     for h in handles {
-        let _ = h.await;
+        if let Ok(l) = h.await {
+            latencies_us.extend(l);
+        }
+    }
+    latencies_us.sort_unstable();
+
+    LoadResult {
+        elapsed: start.elapsed().as_secs_f64(),
+        completed: completed.load(Ordering::Relaxed),
+        errors: errors.load(Ordering::Relaxed),
+        bytes: bytes.load(Ordering::Relaxed),
+        latencies_us,
     }
+}
+
+fn print_result(label: &str, r: &LoadResult) {
+    println!(
+        "{}: {} requests ({} errors) in {:.3}s, {:.0} req/s, {:.1} MB/s, latency p50 {}us p99 {}us",
+        label,
+        r.completed,
+        r.errors,
+        r.elapsed,
+        r.rps(),
+        r.mb_s(),
+        r.percentile_us(0.50),
+        r.percentile_us(0.99)
+    );
+}
 
-    let elapsed = start.elapsed().as_secs_f64();
-    let total = completed.load(Ordering::Relaxed);
-    let rps = total as f64 / elapsed;
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let args = parse_args();
+    let total_requests: u64 = 10_000;
+    let concurrency: usize = 100;
+    let port = 18787u16;
 
-    println!("Completed: {} requests", total);
-    println!("Time: {:.3}s", elapsed);
-    println!("Throughput: {:.0} req/s", rps);
-    println!("RESULT:rust_web_server_load:{:.4}", elapsed);
+    let response = if args.response_size > 0 {
+        Bytes::from(vec![0u8; args.response_size])
+    } else {
+        Bytes::from_static(b"ok")
+    };
+    let request_body = Bytes::from(vec![0u8; args.request_size]);
+    let payload_limit = args.request_size.max(256 * 1024);
+
+    // Start server in background; bind_auto_h2c serves HTTP/1.1 and h2c
+    let (route_count, route_params) = (args.route_count, args.route_params);
+    let server = HttpServer::new(move || {
+        let mut app = App::new()
+            .app_data(web::Data::new(response.clone()))
+            .app_data(web::PayloadConfig::new(payload_limit));
+        let paths: Vec<String> = if route_count > 1 {
+            (0..route_count).map(|i| format!("/{}", i)).collect()
+        } else {
+            vec!["/".to_string()]
+        };
+        for path in &paths {
+            app = if route_params {
+                app.route(path, web::route().to(handle_params_request))
+            } else {
+                app.route(path, web::route().to(handle_request))
+            };
+        }
+        app
+    })
+    .workers(args.workers)
+    .keep_alive(if args.keepalive_s == 0 {
+        KeepAlive::Disabled
+    } else {
+        KeepAlive::Timeout(Duration::from_secs(args.keepalive_s))
+    })
+    .backlog(args.backlog)
+    .bind_auto_h2c(format!("127.0.0.1:{}", port))?
+    .run();
+
+    let server_handle = server.handle();
+    let server_task = tokio::spawn(server);
+
+    // Wait for server to be ready
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+    println!("Server started on port {}", port);
+    println!(
+        "Server config: {} workers, keep-alive {}, backlog {}",
+        args.workers,
+        if args.keepalive_s == 0 {
+            "off".to_string()
+        } else {
+            format!("{}s", args.keepalive_s)
+        },
+        args.backlog
+    );
+    if args.route_count > 1 || args.route_params {
+        println!(
+            "Routes: {}{}",
+            if args.route_count > 1 {
+                format!("{} (/0../{})", args.route_count, args.route_count - 1)
+            } else {
+                "1 (/)".to_string()
+            },
+            if args.route_params {
+                format!(", {} random query params per request", QUERY_PARAMS)
+            } else {
+                String::new()
+            }
+        );
+    }
+    println!("Sending {} requests with {} concurrency...", total_requests, concurrency);
+    if args.request_size > 0 || args.response_size > 0 {
+        println!(
+            "Request body: {} bytes  Response body: {} bytes",
+            args.request_size,
+            args.response_size.max(2)
+        );
+    }
+
+    let url = format!("http://127.0.0.1:{}/", port);
+
+    let all_failed;
+    if args.http2_compare {
+        let h1 = run_load(&url, false, args.route_count, args.route_params, total_requests, concurrency, request_body.clone()).await;
+        let h2 = run_load(&url, true, args.route_count, args.route_params, total_requests, concurrency, request_body).await;
+        print_result("HTTP/1.1", &h1);
+        print_result("HTTP/2  ", &h2);
+        println!("HTTP/2 vs HTTP/1.1 throughput: {:.2}x", h2.rps() / h1.rps());
+        println!("RESULT:rust_web_server_load:{:.4}", h1.elapsed);
+        println!("RESULT:rust_web_server_load_h2:{:.4}", h2.elapsed);
+        all_failed = h1.completed == 0 || h2.completed == 0;
+    } else {
+        let r = run_load(&url, args.http2, args.route_count, args.route_params, total_requests, concurrency, request_body).await;
+        println!("Completed: {} requests", r.completed);
+        println!("Errors: {}", r.errors);
+        println!("Time: {:.3}s", r.elapsed);
+        println!("Throughput: {:.0} req/s", r.rps());
+        println!("Data: {:.1} MB/s", r.mb_s());
+        println!(
+            "Latency: p50 {}us p99 {}us",
+            r.percentile_us(0.50),
+            r.percentile_us(0.99)
+        );
+        if args.http2 {
+            println!("RESULT:rust_web_server_load_h2:{:.4}", r.elapsed);
+        } else {
+            println!("RESULT:rust_web_server_load:{:.4}", r.elapsed);
+        }
+        all_failed = r.completed == 0;
+    }
 
     server_handle.stop(true).await;
     let _ = server_task.await;
+    if all_failed {
+        eprintln!("Every request failed");
+        std::process::exit(1);
+    }
     Ok(())
 }